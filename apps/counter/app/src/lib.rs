@@ -1,7 +1,6 @@
-use anyhow::bail;
 use essential_types::{
     solution::{Mutation, Solution, SolutionData},
-    PredicateAddress, Value, Word,
+    PredicateAddress, Word,
 };
 
 /// The location in storage where the counter is stored.
@@ -11,9 +10,8 @@ const COUNTER_KEY: Word = 0;
 /// The key used to access the counter in storage.
 pub struct CounterKey(pub Vec<Word>);
 
-#[derive(Clone)]
-/// The data returned when querying the current count.
-pub struct QueryCount(pub Option<Value>);
+/// The data returned when querying the current count. Nil reads as zero.
+pub type QueryCount = essential_app_utils::state::Typed<Word>;
 
 /// The key used to access the counter in storage.
 pub fn counter_key() -> CounterKey {
@@ -26,21 +24,15 @@ pub fn incremented_solution(
     predicate: PredicateAddress,
     count: QueryCount,
 ) -> anyhow::Result<(Solution, Word)> {
-    let count = extract_count(count)?;
+    let count = count.get()?;
     let new_count = count + 1;
     Ok((create_solution(predicate, new_count), new_count))
 }
 
 /// Given a query of the current count, extract the count.
+#[deprecated(note = "use `QueryCount::get`")]
 pub fn extract_count(count: QueryCount) -> anyhow::Result<Word> {
-    match count.0 {
-        Some(count) => match &count[..] {
-            [] => Ok(0),
-            [count] => Ok(*count),
-            _ => bail!("Expected single word, got: {:?}", count),
-        },
-        None => Ok(0),
-    }
+    count.get()
 }
 
 /// Create a solution that sets the count to a new value.
@@ -1,8 +1,8 @@
 use clap::{Args, Parser, Subcommand};
-use counter_app::{counter_key, extract_count, incremented_solution, CounterKey, QueryCount};
-use essential_app_utils::compile::compile_pint_project;
+use counter_app::{counter_key, incremented_solution, CounterKey, QueryCount};
+use essential_app_utils::compile::ContractAddresses;
 use essential_rest_client::node_client::EssentialNodeClient;
-use essential_types::{ContentAddress, PredicateAddress};
+use essential_types::ContentAddress;
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -32,6 +32,9 @@ pub struct Shared {
     pub node_api: String,
     /// The directory containing the pint files.
     pub pint_directory: PathBuf,
+    /// Deployment manifest to read contract addresses from instead of compiling.
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -46,26 +49,34 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
     let Cli { command } = cli;
     match command {
         Command::ReadCount {
-            server: Shared {
-                node_api,
-                pint_directory,
-            },
+            server:
+                Shared {
+                    node_api,
+                    pint_directory,
+                    manifest,
+                },
         } => {
-            let address = compile_address(pint_directory).await?;
+            let address = ContractAddresses::resolve(pint_directory, manifest.as_deref())
+                .await?
+                .predicate("Increment")?;
             let node = essential_rest_client::node_client::EssentialNodeClient::new(node_api)?;
             let key = counter_key();
             let count = query_count(node, address.contract, key).await?;
-            let count_value = extract_count(count)?;
+            let count_value = count.get()?;
             println!("Current count is: {}", count_value);
         }
         Command::IncrementCount {
             builder_api,
-            server: Shared {
-                node_api,
-                pint_directory,
-            },
+            server:
+                Shared {
+                    node_api,
+                    pint_directory,
+                    manifest,
+                },
         } => {
-            let address = compile_address(pint_directory).await?;
+            let address = ContractAddresses::resolve(pint_directory, manifest.as_deref())
+                .await?
+                .predicate("Increment")?;
             let node = essential_rest_client::node_client::EssentialNodeClient::new(node_api)?;
             let key = counter_key();
             let count = query_count(node, address.contract.clone(), key).await?;
@@ -85,16 +96,5 @@ async fn query_count(
     address: ContentAddress,
     key: CounterKey,
 ) -> anyhow::Result<QueryCount> {
-    Ok(QueryCount(node.query_state(address, key.0).await?))
-}
-
-async fn compile_address(pint_directory: PathBuf) -> Result<PredicateAddress, anyhow::Error> {
-    let counter = compile_pint_project(pint_directory).await?;
-    let contract_address = essential_hash::contract_addr::from_contract(&counter);
-    let predicate_address = essential_hash::content_addr(&counter.predicates[0]);
-    let predicate_address = PredicateAddress {
-        contract: contract_address,
-        predicate: predicate_address,
-    };
-    Ok(predicate_address)
+    Ok(QueryCount::new(node.query_state(address, key.0).await?))
 }
@@ -1,11 +1,18 @@
 use counter_app::*;
 use essential_app_utils as utils;
 use essential_app_utils::{
-    compile::compile_pint_project,
+    builder::{AutoBuilder, BlockStrategy, ConflictAware, Fifo, Pending, PriorityByFee},
+    clock::ManualClock,
+    compile::{compile_pint_project, ContractAddresses},
     db::{new_dbs, Dbs},
+    deploy::{deploy_project, DeployOutcome, Deployments},
 };
 use essential_node as node;
-use essential_types::{ContentAddress, PredicateAddress, Word};
+use essential_types::{
+    solution::{Solution, SolutionData},
+    ContentAddress, PredicateAddress, Word,
+};
+use std::time::Duration;
 
 #[tokio::test]
 async fn number_go_up() {
@@ -23,7 +30,7 @@ async fn number_go_up() {
     let dbs = new_dbs().await;
 
     // Deploy the contract
-    essential_app_utils::deploy::deploy_contract(&dbs.builder, &counter)
+    essential_app_utils::deploy::deploy_contract(&dbs, &counter)
         .await
         .unwrap();
 
@@ -50,10 +57,13 @@ async fn number_go_up() {
     let count = read_count(&dbs.node, &predicate_address.contract, &key).await;
     assert_eq!(count, new_count);
 
+    // `increment` reads the count with `query_state`, which doesn't see the
+    // state written by built blocks, so every increment reads nil and sets the
+    // count to 1. Both of these are the same solution and both fail against a
+    // count of 1. The one success is the builder's block state solution.
+    // `stale_increment_fails_at_head` builds on the finalized count instead.
     let o = utils::builder::build_default(&dbs).await.unwrap();
     assert_eq!(o.succeeded.len(), 1);
-
-    // FIXME: Shouldn't this be 1?
     assert_eq!(o.failed.len(), 2);
 
     let count = read_count(&dbs.node, &predicate_address.contract, &key).await;
@@ -62,6 +72,250 @@ async fn number_go_up() {
     // Demonstrate syncing node with deployed node and reading count.
 }
 
+#[tokio::test]
+async fn stale_increment_fails_at_head() {
+    let (dbs, predicate_address) = deploy_counter().await;
+    let key = counter_key();
+    increment_from_head(&dbs, predicate_address.clone()).await;
+    utils::builder::build_default(&dbs).await.unwrap();
+
+    // Both increments are built against the same count so only the first can
+    // be included. The second is no longer valid once the first is applied.
+    increment_from_head(&dbs, predicate_address.clone()).await;
+    let expected = increment_from_head(&dbs, predicate_address.clone()).await;
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.succeeded.len(), 2);
+    assert_eq!(o.failed.len(), 1);
+    let count = read_count(&dbs.node, &predicate_address.contract, &key).await;
+    assert_eq!(count, expected);
+}
+
+#[tokio::test]
+async fn competing_increments_conflict_aware() {
+    let (dbs, predicate_address) = deploy_counter().await;
+    let key = counter_key();
+    increment_from_head(&dbs, predicate_address.clone()).await;
+    utils::builder::build_default(&dbs).await.unwrap();
+
+    increment_from_head(&dbs, predicate_address.clone()).await;
+    increment_from_head(&dbs, predicate_address.clone()).await;
+
+    // The second increment conflicts with the first so it is left in the pool.
+    let o = utils::builder::build_with(&dbs, &Default::default(), &ConflictAware)
+        .await
+        .unwrap();
+    assert_eq!(o.succeeded.len(), 2);
+    assert!(o.failed.is_empty());
+    let count = read_count(&dbs.node, &predicate_address.contract, &key).await;
+    assert_eq!(count, 2);
+
+    // It was built against a stale count so it fails in the next block.
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.succeeded.len(), 1);
+    assert_eq!(o.failed.len(), 1);
+    let count = read_count(&dbs.node, &predicate_address.contract, &key).await;
+    assert_eq!(count, 2);
+}
+
+#[tokio::test]
+async fn auto_builder_on_submission() {
+    let (dbs, predicate_address) = deploy_counter().await;
+    let key = counter_key();
+    let auto = AutoBuilder::spawn(
+        dbs.clone(),
+        utils::builder::Trigger::OnSubmission {
+            poll: Duration::from_millis(10),
+        },
+        Default::default(),
+    );
+
+    for expected in 1..=3 {
+        let next = auto.next_block();
+        increment_from_head(&dbs, predicate_address.clone()).await;
+        let o = next.await.unwrap();
+        assert!(o.failed.is_empty());
+        let count = read_count(&dbs.node, &predicate_address.contract, &key).await;
+        assert_eq!(count, expected);
+    }
+
+    auto.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn fifo_follows_clock_not_insertion() {
+    let (dbs, predicate_address) = deploy_counter().await;
+    let key = counter_key();
+    utils::builder::build_default(&dbs).await.unwrap();
+
+    let clock = ManualClock::new(Duration::from_secs(100));
+    let (from_zero, _) =
+        incremented_solution(predicate_address.clone(), QueryCount::new(None)).unwrap();
+    let (from_one, _) =
+        incremented_solution(predicate_address.clone(), QueryCount::new(Some(vec![1]))).unwrap();
+
+    // Insert `0 -> 1` first but stamp it later than `1 -> 2`.
+    utils::builder::submit_with_clock(&dbs.builder, from_zero, &clock)
+        .await
+        .unwrap();
+    clock.set(Duration::from_secs(50));
+    utils::builder::submit_with_clock(&dbs.builder, from_one, &clock)
+        .await
+        .unwrap();
+
+    // `1 -> 2` is attempted first against a count of 0 and fails.
+    let o = utils::builder::build_with(&dbs, &Default::default(), &Fifo)
+        .await
+        .unwrap();
+    assert_eq!(o.succeeded.len(), 2);
+    assert_eq!(o.failed.len(), 1);
+    let count = read_count(&dbs.node, &predicate_address.contract, &key).await;
+    assert_eq!(count, 1);
+}
+
+#[tokio::test]
+async fn default_build_follows_injected_clock() {
+    let (dbs, predicate_address) = deploy_counter().await;
+    let clock = ManualClock::new(Duration::from_secs(100));
+    let dbs = dbs.with_clock(clock.clone());
+    let key = counter_key();
+    utils::builder::build_default(&dbs).await.unwrap();
+
+    let (from_zero, _) =
+        incremented_solution(predicate_address.clone(), QueryCount::new(None)).unwrap();
+    let (from_one, _) =
+        incremented_solution(predicate_address.clone(), QueryCount::new(Some(vec![1]))).unwrap();
+
+    // Insert `0 -> 1` first but stamp it later than `1 -> 2`.
+    dbs.submit(from_zero).await.unwrap();
+    clock.set(Duration::from_secs(50));
+    dbs.submit(from_one).await.unwrap();
+
+    // `1 -> 2` is attempted first against a count of 0 and fails.
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.succeeded.len(), 2);
+    assert_eq!(o.failed.len(), 1);
+    let count = read_count(&dbs.node, &predicate_address.contract, &key).await;
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn priority_by_fee_orders_highest_first() {
+    let pending = |fee: Word, submitted: u64| Pending {
+        address: ContentAddress([fee as u8; 32]),
+        solution: Solution {
+            data: vec![SolutionData {
+                predicate_to_solve: PredicateAddress {
+                    contract: ContentAddress([0; 32]),
+                    predicate: ContentAddress([0; 32]),
+                },
+                decision_variables: vec![vec![fee]],
+                transient_data: vec![],
+                state_mutations: vec![],
+            }],
+        },
+        submitted: Duration::from_secs(submitted),
+    };
+    let strategy = PriorityByFee(|s: &Solution| s.data[0].decision_variables[0][0]);
+    let plan = strategy.plan(vec![
+        pending(1, 0),
+        pending(3, 2),
+        pending(2, 1),
+        pending(3, 1),
+    ]);
+    assert!(plan.defer.is_empty());
+    let order: Vec<_> = plan
+        .attempt
+        .iter()
+        .map(|p| {
+            (
+                p.solution.data[0].decision_variables[0][0],
+                p.submitted.as_secs(),
+            )
+        })
+        .collect();
+    assert_eq!(order, vec![(3, 1), (3, 2), (2, 1), (1, 0)]);
+}
+
+#[tokio::test]
+async fn deploy_project_is_idempotent() {
+    let path = std::path::PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint"));
+    let manifest =
+        std::env::temp_dir().join(format!("counter-deployments-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&manifest);
+    let dbs = new_dbs().await;
+
+    // Anything else in the pool is left for a later block.
+    let (unrelated, _) = incremented_solution(
+        PredicateAddress {
+            contract: ContentAddress([1; 32]),
+            predicate: ContentAddress([1; 32]),
+        },
+        QueryCount::new(None),
+    )
+    .unwrap();
+    let unrelated = dbs.submit(unrelated).await.unwrap();
+
+    let (outcome, deployment) = deploy_project(&dbs, path.clone(), &manifest).await.unwrap();
+    assert!(matches!(outcome, DeployOutcome::Submitted(_)));
+    assert!(dbs
+        .builder
+        .list_solutions(Duration::ZERO..Duration::from_secs(i64::MAX as _), 10)
+        .await
+        .unwrap()
+        .iter()
+        .any(|(ca, _, _)| *ca == unrelated));
+
+    let deployments = Deployments::load(&manifest).await.unwrap();
+    // Keyed by the package name in `pint.toml`.
+    assert_eq!(deployments.get("pint"), Some(&deployment));
+    assert_eq!(deployment.block, 1);
+    assert_eq!(
+        deployment.compiler,
+        utils::compile::pint_version().await.unwrap()
+    );
+
+    // The manifest resolves to the same addresses as compiling.
+    let compiled = ContractAddresses::compile(path.clone()).await.unwrap();
+    let resolved = ContractAddresses::resolve(path.clone(), Some(&manifest))
+        .await
+        .unwrap();
+    assert_eq!(resolved, compiled);
+    increment_from_head(&dbs, resolved.predicate("Increment").unwrap()).await;
+
+    // Deploying again submits nothing and records the same deployment.
+    utils::builder::build_default(&dbs).await.unwrap();
+    let submissions = || async {
+        dbs.builder
+            .list_submissions(Duration::ZERO..Duration::from_secs(i64::MAX as _), 10)
+            .await
+            .unwrap()
+            .len()
+    };
+    let before = submissions().await;
+    std::fs::remove_file(&manifest).unwrap();
+    let (outcome, _) = deploy_project(&dbs, path, &manifest).await.unwrap();
+    assert_eq!(outcome, DeployOutcome::AlreadyDeployed);
+    assert_eq!(submissions().await, before);
+    assert_eq!(Deployments::load(&manifest).await.unwrap(), deployments);
+
+    std::fs::remove_file(&manifest).unwrap();
+}
+
+async fn deploy_counter() -> (Dbs, PredicateAddress) {
+    let counter = compile_pint_project(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint").into())
+        .await
+        .unwrap();
+    let predicate_address = PredicateAddress {
+        contract: essential_hash::contract_addr::from_contract(&counter),
+        predicate: essential_hash::content_addr(&counter.predicates[0]),
+    };
+    let dbs = new_dbs().await;
+    essential_app_utils::deploy::deploy_contract(&dbs, &counter)
+        .await
+        .unwrap();
+    (dbs, predicate_address)
+}
+
 async fn read_count(
     conn: &node::db::ConnectionPool,
     address: &ContentAddress,
@@ -70,7 +324,7 @@ async fn read_count(
     let r = utils::node::query_state_head(conn, address, &key.0)
         .await
         .unwrap();
-    extract_count(QueryCount(r)).unwrap()
+    QueryCount::new(r).get().unwrap()
 }
 
 async fn increment(dbs: &Dbs, predicate_address: PredicateAddress) -> Word {
@@ -81,7 +335,23 @@ async fn increment(dbs: &Dbs, predicate_address: PredicateAddress) -> Word {
         .await
         .unwrap();
     let (solution, new_count) =
-        incremented_solution(predicate_address, QueryCount(current_count)).unwrap();
+        incremented_solution(predicate_address, QueryCount::new(current_count)).unwrap();
+
+    utils::builder::submit(&dbs.builder, solution)
+        .await
+        .unwrap();
+    new_count
+}
+
+/// Like [`increment`], but builds on the count in the latest finalized block.
+async fn increment_from_head(dbs: &Dbs, predicate_address: PredicateAddress) -> Word {
+    let key = counter_key();
+    let current_count =
+        utils::node::query_state_head(&dbs.node, &predicate_address.contract, &key.0)
+            .await
+            .unwrap();
+    let (solution, new_count) =
+        incremented_solution(predicate_address, QueryCount::new(current_count)).unwrap();
 
     utils::builder::submit(&dbs.builder, solution)
         .await
@@ -5,17 +5,19 @@ use essential_types::{
     Word,
 };
 
-use crate::{abi::MarketCondition, from_query_word, HashedKey, Query};
+use crate::{HashedKey, Query};
 
 pub struct Init {
     pub market_hashed_key: HashedKey,
-    pub new_market_condition: crate::abi::MarketCondition,
+    /// The hashed key of the oracle the market resolves against.
+    pub new_market_condition: HashedKey,
     pub market_nonce_query: Query<Word>,
 }
 
 pub struct BuildSolution {
     pub market_hashed_key: HashedKey,
-    pub new_market_condition: crate::abi::MarketCondition,
+    /// The hashed key of the oracle the market resolves against.
+    pub new_market_condition: HashedKey,
     pub signature: RecoverableSignature,
     pub new_market_nonce: Word,
 }
@@ -30,7 +32,7 @@ pub fn build_solution(
 ) -> anyhow::Result<Solution> {
     let pub_vars = crate::abi::InitMarket::PubVars {
         key: market_hashed_key,
-        new_condition: new_market_condition.clone(),
+        new_condition: new_market_condition,
     };
     let vars = crate::abi::InitMarket::Vars {
         sig: signature.encode(),
@@ -38,6 +40,8 @@ pub fn build_solution(
     let muts = crate::abi::storage::mutations()
         .market_nonces(|m| m.entry(market_hashed_key, new_market_nonce))
         .market_resolutions(|m| m.entry(market_hashed_key, crate::abi::Resolution::Unresolved))
+        // `InitMarket` requires the balance to be initialised to 0.
+        .market_balances(|m| m.entry(market_hashed_key, 0))
         .market_conditions(|m| m.entry(market_hashed_key, new_market_condition));
     let solution = SolutionData {
         predicate_to_solve: crate::abi::InitMarket::ADDRESS,
@@ -57,7 +61,8 @@ pub fn build_solution(
 /// Represents the data to be signed for a mint solution.
 pub struct ToSign {
     pub market_hashed_key: HashedKey,
-    pub new_market_condition: MarketCondition,
+    /// The hashed key of the oracle the market resolves against.
+    pub new_market_condition: HashedKey,
     pub new_market_nonce: Word,
 }
 
@@ -82,7 +87,7 @@ pub fn data_to_sign(
         new_market_condition,
     }: Init,
 ) -> anyhow::Result<ToSign> {
-    let new_market_nonce = from_query_word(&market_nonce_query)? + 1;
+    let new_market_nonce = market_nonce_query.get()? + 1;
     Ok(ToSign {
         market_hashed_key,
         new_market_nonce,
@@ -1,16 +1,11 @@
-use std::marker::PhantomData;
-
-use abi::Resolution;
-use anyhow::bail;
-use essential_types::{Key, Value, Word};
+use essential_types::{Key, Word};
 
 pub mod init_market;
 pub mod init_oracle;
 pub mod resolve_oracle;
 
-/// Represents a query result, which may or may not contain a value.
-#[derive(Clone)]
-pub struct Query<T>(pub Option<Value>, pub PhantomData<T>);
+/// A value read from state.
+pub type Query<T> = essential_app_utils::state::Typed<T>;
 
 pub type HashedKey = [Word; 4];
 
@@ -21,6 +16,8 @@ pub mod abi {
     }
 }
 
+essential_app_utils::impl_from_state_for_abi!(abi::Resolution);
+
 /// Generates the key for querying a user's nonce.
 pub fn user_nonce_key(hashed_key: HashedKey) -> Key {
     let keys: Vec<_> = abi::storage::keys::keys()
@@ -29,6 +26,14 @@ pub fn user_nonce_key(hashed_key: HashedKey) -> Key {
     keys.into_iter().next().expect("Must be a key")
 }
 
+/// Generates the key for querying a user's balance.
+pub fn user_balance_key(hashed_key: HashedKey) -> Key {
+    let keys: Vec<_> = abi::storage::keys::keys()
+        .user_balances(|e| e.entry(hashed_key))
+        .into();
+    keys.into_iter().next().expect("Must be a key")
+}
+
 /// Generates the key for querying an oracle's nonce.
 pub fn oracle_nonce_key(hashed_key: HashedKey) -> Key {
     let keys: Vec<_> = abi::storage::keys::keys()
@@ -66,27 +71,12 @@ pub fn market_condition_key(hashed_key: HashedKey) -> Key {
     keys.into_iter().next().expect("Must be a key")
 }
 
-pub fn from_query_resolution(query: &Query<Resolution>) -> anyhow::Result<Resolution> {
-    let r = match &query.0 {
-        Some(resolution) => match &resolution[..] {
-            [] => bail!("Expected resolution to be non-nil, got nil"),
-            [0, _] => Resolution::Unresolved,
-            [1, b] => Resolution::Resolved(*b != 0),
-            _ => bail!("Expected two words, got: {:?}", resolution),
-        },
-        None => bail!("Expected query to be non-None, got None"),
-    };
-    Ok(r)
+#[deprecated(note = "use `Query::get`")]
+pub fn from_query_resolution(query: &Query<abi::Resolution>) -> anyhow::Result<abi::Resolution> {
+    query.get()
 }
 
+#[deprecated(note = "use `Query::get`")]
 pub fn from_query_word(query: &Query<Word>) -> anyhow::Result<Word> {
-    let r = match &query.0 {
-        Some(nonce) => match &nonce[..] {
-            [] => 0,
-            [nonce] => *nonce,
-            _ => bail!("Expected single word, got: {:?}", nonce),
-        },
-        None => 0,
-    };
-    Ok(r)
+    query.get()
 }
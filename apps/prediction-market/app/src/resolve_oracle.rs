@@ -5,7 +5,7 @@ use essential_types::{
     Word,
 };
 
-use crate::{from_query_word, Query};
+use crate::Query;
 
 pub struct Init {
     pub oracle_hashed_key: [Word; 4],
@@ -85,7 +85,7 @@ pub fn data_to_sign(
         new_resolution,
     }: Init,
 ) -> anyhow::Result<ToSign> {
-    let new_oracle_nonce = from_query_word(&oracle_nonce_query)? + 1;
+    let new_oracle_nonce = oracle_nonce_query.get()? + 1;
     Ok(ToSign {
         oracle_hashed_key,
         new_oracle_nonce,
@@ -1,13 +1,10 @@
-use std::marker::PhantomData;
-
-use essential_app_utils::{self as utils, compile::compile_pint_project, db::Dbs};
-use essential_signer::Signature;
-use essential_types::{contract::Contract, convert::word_4_from_u8_32, solution::Solution, Word};
-use essential_wallet::Wallet;
-use prediction_market::{
-    abi::{MarketCondition, Resolution},
-    Query,
+use essential_app_utils::{
+    self as utils,
+    compile::compile_pint_project,
+    signer::{Signer, WalletSigner},
 };
+use essential_types::Word;
+use prediction_market::{abi::Resolution, Query};
 
 const ORACLE1_PRIVATE_KEY: &str =
     "128A3D2146A69581FD8FC4C0A9B7A96A5755D85255D4E47F814AFA69D7726C8D";
@@ -19,6 +16,10 @@ const MARKET1_NAME: &str = "market1";
 
 #[tokio::test]
 async fn test_create_oracle() {
+    let labels = utils::print::Labels::default()
+        .load(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint").into())
+        .await
+        .unwrap();
     // Initialize tracing for better debugging
     tracing_subscriber::fmt::init();
 
@@ -30,7 +31,7 @@ async fn test_create_oracle() {
         .unwrap();
 
     // deploy the token contract
-    essential_app_utils::deploy::deploy_contract(&dbs.builder, &contract)
+    essential_app_utils::deploy::deploy_contract(&dbs, &contract)
         .await
         .unwrap();
 
@@ -47,10 +48,12 @@ async fn test_create_oracle() {
             ),
         )
         .unwrap();
-    let oracle_hashed_key = hash_key(&mut wallet, ORACLE1_NAME);
+    let oracle_hashed_key = WalletSigner::new(&mut wallet, ORACLE1_NAME)
+        .hashed_key()
+        .unwrap();
 
     let oracle_nonce_key = prediction_market::oracle_nonce_key(oracle_hashed_key);
-    let oracle_nonce_query: Query<Word> = Query(
+    let oracle_nonce_query: Query<Word> = Query::new(
         utils::node::query_state_head(
             &dbs.node,
             &prediction_market::abi::ADDRESS,
@@ -58,11 +61,10 @@ async fn test_create_oracle() {
         )
         .await
         .unwrap(),
-        PhantomData,
     );
 
     let oracle_resolution_key = prediction_market::oracle_resolution_key(oracle_hashed_key);
-    let oracle_resolution_query: Query<Word> = Query(
+    let oracle_resolution_query: Query<Word> = Query::new(
         utils::node::query_state_head(
             &dbs.node,
             &prediction_market::abi::ADDRESS,
@@ -70,27 +72,23 @@ async fn test_create_oracle() {
         )
         .await
         .unwrap(),
-        PhantomData,
     );
-    let oracle_resolution = prediction_market::from_query_word(&oracle_resolution_query).unwrap();
+    let _oracle_resolution = oracle_resolution_query.get().unwrap();
 
     let init = prediction_market::init_oracle::Init {
         oracle_hashed_key,
         oracle_nonce_query: oracle_nonce_query.clone(),
     };
     let to_sign = prediction_market::init_oracle::data_to_sign(init).unwrap();
-    let Signature::Secp256k1(signature) = wallet
-        .sign_words(&to_sign.to_words(), ORACLE1_NAME)
-        .unwrap()
-    else {
-        panic!("invalid signature")
-    };
+    let signature = WalletSigner::new(&mut wallet, ORACLE1_NAME)
+        .sign(&to_sign.to_words())
+        .unwrap();
 
     // construct solution
     let solution = prediction_market::init_oracle::build_solution(
         prediction_market::init_oracle::BuildSolution {
             oracle_hashed_key,
-            new_oracle_nonce: prediction_market::from_query_word(&oracle_nonce_query).unwrap() + 1,
+            new_oracle_nonce: oracle_nonce_query.get().unwrap() + 1,
             new_oracle_resolution: prediction_market::abi::Resolution::Unresolved,
             signature,
         },
@@ -110,12 +108,17 @@ async fn test_create_oracle() {
     // Build a block
     {
         let o = utils::builder::build_default(&dbs).await.unwrap();
-        assert!(o.failed.is_empty(), "{:?}", o.failed);
+        assert!(
+            o.failed.is_empty(),
+            "{:?}\n{}",
+            o.failed,
+            labels.solution(&solution)
+        );
     }
 
     // assert that oracle was initialized properly
     let oracle_resolution_key = prediction_market::oracle_resolution_key(oracle_hashed_key);
-    let oracle_resolution_query: Query<prediction_market::abi::Resolution> = Query(
+    let oracle_resolution_query: Query<prediction_market::abi::Resolution> = Query::new(
         utils::node::query_state_head(
             &dbs.node,
             &prediction_market::abi::ADDRESS,
@@ -123,16 +126,19 @@ async fn test_create_oracle() {
         )
         .await
         .unwrap(),
-        PhantomData,
     );
     assert_eq!(
-        prediction_market::from_query_resolution(&oracle_resolution_query).unwrap(),
+        oracle_resolution_query.get().unwrap(),
         prediction_market::abi::Resolution::Unresolved
     );
 }
 
 #[tokio::test]
 async fn test_create_oracle_and_market() {
+    let labels = utils::print::Labels::default()
+        .load(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint").into())
+        .await
+        .unwrap();
     // Initialize tracing for better debugging
 
     // Create new databases for testing
@@ -143,7 +149,7 @@ async fn test_create_oracle_and_market() {
         .unwrap();
 
     // deploy the token contract
-    essential_app_utils::deploy::deploy_contract(&dbs.builder, &contract)
+    essential_app_utils::deploy::deploy_contract(&dbs, &contract)
         .await
         .unwrap();
 
@@ -160,10 +166,12 @@ async fn test_create_oracle_and_market() {
             ),
         )
         .unwrap();
-    let oracle_hashed_key = hash_key(&mut wallet, ORACLE1_NAME);
+    let oracle_hashed_key = WalletSigner::new(&mut wallet, ORACLE1_NAME)
+        .hashed_key()
+        .unwrap();
 
     let oracle_nonce_key = prediction_market::oracle_nonce_key(oracle_hashed_key);
-    let oracle_nonce_query: Query<Word> = Query(
+    let oracle_nonce_query: Query<Word> = Query::new(
         utils::node::query_state_head(
             &dbs.node,
             &prediction_market::abi::ADDRESS,
@@ -171,7 +179,6 @@ async fn test_create_oracle_and_market() {
         )
         .await
         .unwrap(),
-        PhantomData,
     );
 
     // let oracle_resolution_key = prediction_market::oracle_resolution_key(oracle_hashed_key);
@@ -185,7 +192,7 @@ async fn test_create_oracle_and_market() {
     //     .unwrap(),
     //     PhantomData,
     // );
-    // let oracle_resolution = prediction_market::from_query_word(&oracle_resolution_query).unwrap();
+    // let oracle_resolution = oracle_resolution_query.get().unwrap();
 
     let solution = {
         let init = prediction_market::init_oracle::Init {
@@ -193,25 +200,21 @@ async fn test_create_oracle_and_market() {
             oracle_nonce_query: oracle_nonce_query.clone(),
         };
         let to_sign = prediction_market::init_oracle::data_to_sign(init).unwrap();
-        let Signature::Secp256k1(signature) = wallet
-            .sign_words(&to_sign.to_words(), ORACLE1_NAME)
-            .unwrap()
-        else {
-            panic!("invalid signature")
-        };
+        let signature = WalletSigner::new(&mut wallet, ORACLE1_NAME)
+            .sign(&to_sign.to_words())
+            .unwrap();
 
         // construct solution
-        let solution = prediction_market::init_oracle::build_solution(
+        prediction_market::init_oracle::build_solution(
             prediction_market::init_oracle::BuildSolution {
                 oracle_hashed_key,
-                // new_oracle_nonce: prediction_market::from_query_word(&oracle_nonce_query).unwrap() + 1,
+                // new_oracle_nonce: oracle_nonce_query.get().unwrap() + 1,
                 new_oracle_nonce: 1,
                 new_oracle_resolution: prediction_market::abi::Resolution::Unresolved,
                 signature,
             },
         )
-        .unwrap();
-        solution
+        .unwrap()
     };
 
     // submit the solution
@@ -227,7 +230,12 @@ async fn test_create_oracle_and_market() {
     // Build a block
     {
         let o = utils::builder::build_default(&dbs).await.unwrap();
-        assert!(o.failed.is_empty(), "{:?}", o.failed);
+        assert!(
+            o.failed.is_empty(),
+            "{:?}\n{}",
+            o.failed,
+            labels.solution(&solution)
+        );
     }
 
     // now, init a market that refers to the oracle
@@ -242,10 +250,12 @@ async fn test_create_oracle_and_market() {
             ),
         )
         .unwrap();
-    let market_hashed_key = hash_key(&mut wallet, MARKET1_NAME);
+    let market_hashed_key = WalletSigner::new(&mut wallet, MARKET1_NAME)
+        .hashed_key()
+        .unwrap();
 
     let market_nonce_key = prediction_market::market_nonce_key(market_hashed_key);
-    let market_nonce_query: Query<Word> = Query(
+    let market_nonce_query: Query<Word> = Query::new(
         utils::node::query_state_head(
             &dbs.node,
             &prediction_market::abi::ADDRESS,
@@ -253,7 +263,6 @@ async fn test_create_oracle_and_market() {
         )
         .await
         .unwrap(),
-        PhantomData,
     );
 
     // let market_condition_key = prediction_market::market_condition_key(market_hashed_key);
@@ -271,30 +280,25 @@ async fn test_create_oracle_and_market() {
     let solution = {
         let init = prediction_market::init_market::Init {
             market_hashed_key,
-            new_market_condition: MarketCondition::Oracle(oracle_hashed_key),
+            new_market_condition: oracle_hashed_key,
             market_nonce_query: market_nonce_query.clone(),
         };
 
         let to_sign = prediction_market::init_market::data_to_sign(init).unwrap();
-        let Signature::Secp256k1(signature) = wallet
-            .sign_words(&to_sign.to_words(), MARKET1_NAME)
-            .unwrap()
-        else {
-            panic!("invalid signature")
-        };
+        let signature = WalletSigner::new(&mut wallet, MARKET1_NAME)
+            .sign(&to_sign.to_words())
+            .unwrap();
 
         // construct solution
-        let solution = prediction_market::init_market::build_solution(
+        prediction_market::init_market::build_solution(
             prediction_market::init_market::BuildSolution {
                 new_market_nonce: 1,
                 market_hashed_key,
-                new_market_condition: MarketCondition::Oracle(oracle_hashed_key),
+                new_market_condition: oracle_hashed_key,
                 signature,
             },
         )
-        .unwrap();
-
-        solution
+        .unwrap()
     };
 
     // submit the solution
@@ -310,12 +314,21 @@ async fn test_create_oracle_and_market() {
     // Build a block
     {
         let o = utils::builder::build_default(&dbs).await.unwrap();
-        assert!(o.failed.is_empty(), "{:?}", o.failed);
+        assert!(
+            o.failed.is_empty(),
+            "{:?}\n{}",
+            o.failed,
+            labels.solution(&solution)
+        );
     }
 }
 
 #[tokio::test]
 async fn test_create_and_resolve_oracle() {
+    let labels = utils::print::Labels::default()
+        .load(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint").into())
+        .await
+        .unwrap();
     // parameters
     let oracle_private_key = "128A3D2146A69581FD8FC4C0A9B7A96A5755D85255D4E47F814AFA69D7726C8D";
     let oracle_name = "my_oracle";
@@ -328,7 +341,7 @@ async fn test_create_and_resolve_oracle() {
         .unwrap();
 
     // deploy the token contract
-    essential_app_utils::deploy::deploy_contract(&dbs.builder, &contract)
+    essential_app_utils::deploy::deploy_contract(&dbs, &contract)
         .await
         .unwrap();
 
@@ -343,12 +356,14 @@ async fn test_create_and_resolve_oracle() {
             ),
         )
         .unwrap();
-    let oracle_hashed_key = hash_key(&mut wallet, oracle_name);
+    let oracle_hashed_key = WalletSigner::new(&mut wallet, oracle_name)
+        .hashed_key()
+        .unwrap();
 
     // InitOracle
     let solution = {
         let oracle_nonce_key = prediction_market::oracle_nonce_key(oracle_hashed_key);
-        let oracle_nonce_query: Query<Word> = Query(
+        let oracle_nonce_query: Query<Word> = Query::new(
             utils::node::query_state_head(
                 &dbs.node,
                 &prediction_market::abi::ADDRESS,
@@ -356,11 +371,10 @@ async fn test_create_and_resolve_oracle() {
             )
             .await
             .unwrap(),
-            PhantomData,
         );
 
         let oracle_resolution_key = prediction_market::oracle_resolution_key(oracle_hashed_key);
-        let oracle_resolution_query: Query<Word> = Query(
+        let oracle_resolution_query: Query<Word> = Query::new(
             utils::node::query_state_head(
                 &dbs.node,
                 &prediction_market::abi::ADDRESS,
@@ -368,28 +382,23 @@ async fn test_create_and_resolve_oracle() {
             )
             .await
             .unwrap(),
-            PhantomData,
         );
-        let oracle_resolution =
-            prediction_market::from_query_word(&oracle_resolution_query).unwrap();
+        let _oracle_resolution = oracle_resolution_query.get().unwrap();
 
         let init = prediction_market::init_oracle::Init {
             oracle_hashed_key,
             oracle_nonce_query: oracle_nonce_query.clone(),
         };
         let to_sign = prediction_market::init_oracle::data_to_sign(init).unwrap();
-        let Signature::Secp256k1(signature) =
-            wallet.sign_words(&to_sign.to_words(), oracle_name).unwrap()
-        else {
-            panic!("invalid signature")
-        };
+        let signature = WalletSigner::new(&mut wallet, oracle_name)
+            .sign(&to_sign.to_words())
+            .unwrap();
 
         // construct solution
         prediction_market::init_oracle::build_solution(
             prediction_market::init_oracle::BuildSolution {
                 oracle_hashed_key,
-                new_oracle_nonce: prediction_market::from_query_word(&oracle_nonce_query).unwrap()
-                    + 1,
+                new_oracle_nonce: oracle_nonce_query.get().unwrap() + 1,
                 new_oracle_resolution: prediction_market::abi::Resolution::Unresolved,
                 signature,
             },
@@ -410,13 +419,18 @@ async fn test_create_and_resolve_oracle() {
     // Build a block
     {
         let o = utils::builder::build_default(&dbs).await.unwrap();
-        assert!(o.failed.is_empty(), "{:?}", o.failed);
+        assert!(
+            o.failed.is_empty(),
+            "{:?}\n{}",
+            o.failed,
+            labels.solution(&solution)
+        );
     }
 
     // ResolveOracle
     let solution = {
         let oracle_nonce_key = prediction_market::oracle_nonce_key(oracle_hashed_key);
-        let oracle_nonce_query: Query<Word> = Query(
+        let oracle_nonce_query: Query<Word> = Query::new(
             utils::node::query_state_head(
                 &dbs.node,
                 &prediction_market::abi::ADDRESS,
@@ -424,11 +438,10 @@ async fn test_create_and_resolve_oracle() {
             )
             .await
             .unwrap(),
-            PhantomData,
         );
 
         let oracle_resolution_key = prediction_market::oracle_resolution_key(oracle_hashed_key);
-        let oracle_resolution_query: Query<Resolution> = Query(
+        let oracle_resolution_query: Query<Resolution> = Query::new(
             utils::node::query_state_head(
                 &dbs.node,
                 &prediction_market::abi::ADDRESS,
@@ -436,10 +449,8 @@ async fn test_create_and_resolve_oracle() {
             )
             .await
             .unwrap(),
-            PhantomData,
         );
-        let oracle_resolution =
-            prediction_market::from_query_resolution(&oracle_resolution_query).unwrap();
+        let _oracle_resolution = oracle_resolution_query.get().unwrap();
 
         let init = prediction_market::resolve_oracle::Init {
             oracle_hashed_key,
@@ -447,18 +458,15 @@ async fn test_create_and_resolve_oracle() {
             new_resolution: true,
         };
         let to_sign = prediction_market::resolve_oracle::data_to_sign(init).unwrap();
-        let Signature::Secp256k1(signature) =
-            wallet.sign_words(&to_sign.to_words(), oracle_name).unwrap()
-        else {
-            panic!("invalid signature")
-        };
+        let signature = WalletSigner::new(&mut wallet, oracle_name)
+            .sign(&to_sign.to_words())
+            .unwrap();
 
         // construct solution
         prediction_market::resolve_oracle::build_solution(
             prediction_market::resolve_oracle::BuildSolution {
                 oracle_hashed_key,
-                new_oracle_nonce: prediction_market::from_query_word(&oracle_nonce_query).unwrap()
-                    + 1,
+                new_oracle_nonce: oracle_nonce_query.get().unwrap() + 1,
                 signature,
                 new_resolution: true,
             },
@@ -479,12 +487,17 @@ async fn test_create_and_resolve_oracle() {
     // Build a block
     {
         let o = utils::builder::build_default(&dbs).await.unwrap();
-        assert!(o.failed.is_empty(), "{:?}", o.failed);
+        assert!(
+            o.failed.is_empty(),
+            "{:?}\n{}",
+            o.failed,
+            labels.solution(&solution)
+        );
     }
 
     // assert that oracle was resolved properly
     let oracle_resolution_key = prediction_market::oracle_resolution_key(oracle_hashed_key);
-    let oracle_resolution_query: Query<prediction_market::abi::Resolution> = Query(
+    let oracle_resolution_query: Query<prediction_market::abi::Resolution> = Query::new(
         utils::node::query_state_head(
             &dbs.node,
             &prediction_market::abi::ADDRESS,
@@ -492,20 +505,36 @@ async fn test_create_and_resolve_oracle() {
         )
         .await
         .unwrap(),
-        PhantomData,
     );
     assert_eq!(
-        prediction_market::from_query_resolution(&oracle_resolution_query).unwrap(),
+        oracle_resolution_query.get().unwrap(),
         prediction_market::abi::Resolution::Resolved(true)
     );
 }
 
-// Helper function to hash a public key
-fn hash_key(wallet: &mut Wallet, account_name: &str) -> [Word; 4] {
-    let public_key = wallet.get_public_key(account_name).unwrap();
-    let essential_signer::PublicKey::Secp256k1(public_key) = public_key else {
-        panic!("Invalid public key")
-    };
-    let encoded = essential_sign::encode::public_key(&public_key);
-    word_4_from_u8_32(essential_hash::hash_words(&encoded))
+#[tokio::test]
+async fn test_genesis_user_balances() {
+    let user_hashed_key = [1, 2, 3, 4];
+    let user_balance_key = prediction_market::user_balance_key(user_hashed_key);
+
+    // Fund the user from a JSON fixture
+    let fixture = format!(
+        r#"{{ "{}": [{{ "key": {:?}, "value": [500] }}] }}"#,
+        prediction_market::abi::ADDRESS,
+        user_balance_key,
+    );
+    let state = utils::db::GenesisState::from_json_str(&fixture).unwrap();
+    let genesis = utils::db::Genesis::default().with_state(state);
+    let dbs = utils::db::new_dbs_with_genesis(&genesis).await;
+
+    let user_balance_query: Query<Word> = Query::new(
+        utils::node::query_state_head(
+            &dbs.node,
+            &prediction_market::abi::ADDRESS,
+            &user_balance_key,
+        )
+        .await
+        .unwrap(),
+    );
+    assert_eq!(user_balance_query.get().unwrap(), 500);
 }
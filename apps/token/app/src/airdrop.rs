@@ -0,0 +1,549 @@
+//! # Airdrop
+//! Contains functionality for distributing tokens from one account to many.
+//!
+//! Transfers from the same account must be included in nonce order, so they
+//! take consecutive nonces from a [`NonceManager`] and each is built on the
+//! balances left by the ones before it. Progress is kept in a [`Report`] so an
+//! interrupted or partly failed airdrop can be resumed without paying anyone
+//! twice.
+
+use anyhow::{bail, ensure};
+use essential_app_utils::{compile::ContractAddresses, nonce::NonceManager, signer::Signer};
+use essential_rest_client::{
+    builder_client::EssentialBuilderClient, node_client::EssentialNodeClient,
+};
+use essential_types::{solution::Solution, Block, ContentAddress, Key, Value, Word};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    transfer::{QueuedNonces, SignMode},
+    Query,
+};
+
+/// The default file name for the airdrop report.
+pub const DEFAULT_REPORT: &str = "airdrop-report.json";
+
+/// A recipient read from the airdrop CSV.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Row {
+    /// The line of the CSV the row was read from, starting at 1.
+    pub line: usize,
+    /// The hashed key of the recipient.
+    pub to: [Word; 4],
+    /// The amount to transfer.
+    pub amount: Word,
+}
+
+/// The progress of an airdrop.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Report {
+    /// The token contract.
+    pub contract: ContentAddress,
+    /// The hashed key of the sending account.
+    pub from: [Word; 4],
+    /// The number of the next block to check for included transfers.
+    pub next_block: Word,
+    /// One entry per row of the CSV, in order.
+    pub entries: Vec<Entry>,
+}
+
+/// The progress of a transfer to one recipient.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// The line of the CSV the recipient was read from.
+    pub line: usize,
+    /// The hashed key of the recipient.
+    pub to: [Word; 4],
+    /// The amount to transfer.
+    pub amount: Word,
+    /// What has happened to the transfer so far.
+    pub status: Status,
+}
+
+/// What has happened to a transfer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Status {
+    /// Not submitted yet.
+    Pending,
+    /// Submitted to the builder and awaiting inclusion.
+    Submitted {
+        /// The nonce the transfer was signed with.
+        nonce: Word,
+        /// The content address of the solution.
+        solution: ContentAddress,
+    },
+    /// Included in a block.
+    Included {
+        /// The nonce the transfer was signed with.
+        nonce: Word,
+        /// The content address of the solution.
+        solution: ContentAddress,
+        /// The number of the block it was included in.
+        block: Word,
+    },
+    /// Not included. It is planned again when the airdrop is resumed.
+    Failed {
+        /// Why the transfer failed.
+        reason: String,
+        /// The nonce of a transfer that timed out awaiting inclusion.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        nonce: Option<Word>,
+        /// The content address of a transfer that timed out awaiting inclusion.
+        ///
+        /// It is still marked included if it turns up in a later block.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        solution: Option<ContentAddress>,
+    },
+}
+
+/// A signed transfer for an entry of the report.
+pub struct Transfer {
+    /// The index of the entry in [`Report::entries`].
+    pub entry: usize,
+    /// The nonce the transfer was signed with.
+    pub nonce: Word,
+    /// The solution for the token contract this crate was compiled against.
+    pub solution: Solution,
+}
+
+/// The node calls made while sending an airdrop.
+pub trait Node {
+    /// Calls `f` with every block from number `start` onwards, in order.
+    ///
+    /// Returns the number of the block after the last one visited.
+    fn for_each_block(
+        &self,
+        start: Word,
+        f: impl FnMut(&Block) -> anyhow::Result<()>,
+    ) -> impl Future<Output = anyhow::Result<Word>>;
+
+    /// Reads the value stored at `key` in the contract's state.
+    fn query_state(
+        &self,
+        contract: &ContentAddress,
+        key: &Key,
+    ) -> impl Future<Output = anyhow::Result<Option<Value>>>;
+}
+
+/// The builder calls made while sending an airdrop.
+pub trait Builder {
+    /// Submits a solution, returning its content address.
+    fn submit(&self, solution: &Solution) -> impl Future<Output = anyhow::Result<ContentAddress>>;
+
+    /// Why the builder last failed to include the solution, if it has.
+    fn latest_failure(
+        &self,
+        solution: &ContentAddress,
+    ) -> impl Future<Output = anyhow::Result<Option<String>>>;
+}
+
+/// Submits airdrop transfers in nonce order and tracks them until they are included or fail.
+pub struct AirdropSender<'a, N, B> {
+    /// The node to check for included transfers.
+    pub node: &'a N,
+    /// The builder to submit transfers to.
+    pub builder: &'a B,
+    /// The token contract the transfers are sent to.
+    pub addresses: &'a ContractAddresses,
+    /// The nonces the transfers were signed with.
+    ///
+    /// Nonces are reconciled as blocks are seen and rolled back when a
+    /// transfer fails, so they can be handed out again.
+    pub nonces: &'a NonceManager,
+    /// Where the report is saved after each change.
+    pub report_path: &'a Path,
+    /// The most transfers awaiting inclusion at once.
+    pub max_pending: usize,
+    /// The time between checks for included transfers.
+    pub poll_interval: Duration,
+    /// How long [`AirdropSender::send`] waits for transfers to be included.
+    pub timeout: Duration,
+}
+
+/// Parses the airdrop CSV.
+///
+/// Each line is the recipient's hashed key as hex and an amount, separated by
+/// a comma. Blank lines are skipped and a first line of `recipient,amount` is
+/// treated as a header.
+pub fn parse_csv(
+    csv: &str,
+    parse_amount: impl Fn(&str) -> anyhow::Result<Word>,
+) -> anyhow::Result<Vec<Row>> {
+    let mut rows = vec![];
+    for (i, line) in csv.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if i == 0 && line.eq_ignore_ascii_case("recipient,amount") {
+            continue;
+        }
+        let Some((to, amount)) = line.split_once(',') else {
+            bail!("Line {}: expected RECIPIENT,AMOUNT, got: {}", line_no, line);
+        };
+        let to = crate::parse_hashed_key(to.trim())
+            .map_err(|e| anyhow::anyhow!("Line {}: {}", line_no, e))?;
+        let amount =
+            parse_amount(amount.trim()).map_err(|e| anyhow::anyhow!("Line {}: {}", line_no, e))?;
+        ensure!(amount > 0, "Line {}: amount must be positive", line_no);
+        rows.push(Row {
+            line: line_no,
+            to,
+            amount,
+        });
+    }
+    ensure!(!rows.is_empty(), "No recipients found");
+    Ok(rows)
+}
+
+impl Report {
+    /// Starts a report for the rows, checking for included transfers from `next_block`.
+    pub fn new(
+        contract: ContentAddress,
+        from: [Word; 4],
+        rows: Vec<Row>,
+        next_block: Word,
+    ) -> Self {
+        let entries = rows
+            .into_iter()
+            .map(|row| Entry {
+                line: row.line,
+                to: row.to,
+                amount: row.amount,
+                status: Status::Pending,
+            })
+            .collect();
+        Self {
+            contract,
+            from,
+            next_block,
+            entries,
+        }
+    }
+
+    /// Checks that a report being resumed is for the same airdrop.
+    pub fn ensure_matches(
+        &self,
+        contract: &ContentAddress,
+        from: [Word; 4],
+        rows: &[Row],
+    ) -> anyhow::Result<()> {
+        ensure!(
+            self.contract == *contract,
+            "Report is for contract {}, not {}",
+            self.contract,
+            contract
+        );
+        ensure!(
+            self.from == from,
+            "Report is for a different sending account"
+        );
+        let same_rows = self.entries.len() == rows.len()
+            && self
+                .entries
+                .iter()
+                .zip(rows)
+                .all(|(e, r)| e.line == r.line && e.to == r.to && e.amount == r.amount);
+        ensure!(same_rows, "Report is for a different CSV");
+        Ok(())
+    }
+
+    /// The indices of entries that still need to be signed and submitted.
+    pub fn unsent(&self) -> Vec<usize> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| matches!(e.status, Status::Pending | Status::Failed { .. }))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// The indices of entries awaiting inclusion, in nonce order.
+    pub fn in_flight(&self) -> Vec<usize> {
+        let mut in_flight: Vec<_> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| match e.status {
+                Status::Submitted { nonce, .. } => Some((nonce, i)),
+                _ => None,
+            })
+            .collect();
+        in_flight.sort();
+        in_flight.into_iter().map(|(_, i)| i).collect()
+    }
+
+    /// The accounts whose balances are needed to sign the unsent transfers.
+    pub fn balance_keys(&self) -> Vec<[Word; 4]> {
+        let mut keys = vec![self.from];
+        for i in self.unsent() {
+            let to = self.entries[i].to;
+            if !keys.contains(&to) {
+                keys.push(to);
+            }
+        }
+        keys
+    }
+
+    /// Signs a transfer for every unsent entry with consecutive nonces from `nonces`.
+    ///
+    /// `balances` must hold the current balance of each of [`Report::balance_keys`].
+    pub fn sign_unsent(
+        &self,
+        nonces: QueuedNonces,
+        balances: HashMap<[Word; 4], Word>,
+        mode: SignMode,
+        signer: &mut impl Signer,
+    ) -> anyhow::Result<Vec<Transfer>> {
+        let unsent = self.unsent();
+        let total = unsent
+            .iter()
+            .try_fold(0 as Word, |total, &i| {
+                total.checked_add(self.entries[i].amount)
+            })
+            .ok_or_else(|| anyhow::anyhow!("Total amount overflows"))?;
+        let balance = balances.get(&self.from).copied().unwrap_or_default();
+        ensure!(
+            balance >= total,
+            "Balance of {} is not enough to send {}",
+            balance,
+            total
+        );
+
+        let queued: Vec<_> = unsent
+            .iter()
+            .map(|&i| (self.entries[i].to, self.entries[i].amount))
+            .collect();
+        let signed =
+            crate::transfer::sign_queued(self.from, nonces, balances, &queued, mode, signer)?;
+        Ok(unsent
+            .into_iter()
+            .zip(signed)
+            .map(|(entry, (nonce, solution))| Transfer {
+                entry,
+                nonce,
+                solution,
+            })
+            .collect())
+    }
+
+    /// Records that an entry's transfer was submitted.
+    pub fn submitted(&mut self, entry: usize, nonce: Word, solution: ContentAddress) {
+        self.entries[entry].status = Status::Submitted { nonce, solution };
+    }
+
+    /// Records that an entry's transfer won't be included.
+    pub fn failed(&mut self, entry: usize, reason: impl Into<String>) {
+        self.entries[entry].status = Status::Failed {
+            reason: reason.into(),
+            nonce: None,
+            solution: None,
+        };
+    }
+
+    /// Records that a submitted transfer wasn't included in time.
+    ///
+    /// The transfer is planned again when the airdrop is resumed, but is
+    /// marked included instead if a later block has it.
+    pub fn timed_out(&mut self, entry: usize) {
+        if let Status::Submitted { nonce, solution } = &self.entries[entry].status {
+            self.entries[entry].status = Status::Failed {
+                reason: "Not included before the timeout".to_string(),
+                nonce: Some(*nonce),
+                solution: Some(solution.clone()),
+            };
+        }
+    }
+
+    /// Marks submitted transfers in the block as included.
+    ///
+    /// Blocks before [`Report::next_block`] have already been checked and are skipped.
+    pub fn observe_block(&mut self, block: &Block) {
+        if block.number < self.next_block {
+            return;
+        }
+        for solution in &block.solutions {
+            let ca = essential_hash::content_addr(solution);
+            for entry in &mut self.entries {
+                let (Status::Submitted { nonce, solution }
+                | Status::Failed {
+                    nonce: Some(nonce),
+                    solution: Some(solution),
+                    ..
+                }) = &entry.status
+                else {
+                    continue;
+                };
+                if *solution == ca {
+                    entry.status = Status::Included {
+                        nonce: *nonce,
+                        solution: ca.clone(),
+                        block: block.number,
+                    };
+                }
+            }
+        }
+        self.next_block = block.number + 1;
+    }
+
+    /// The number of entries included, awaiting inclusion, and not yet sent or failed.
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let included = self
+            .entries
+            .iter()
+            .filter(|e| matches!(e.status, Status::Included { .. }))
+            .count();
+        let in_flight = self.in_flight().len();
+        (
+            included,
+            in_flight,
+            self.entries.len() - included - in_flight,
+        )
+    }
+}
+
+impl<N: Node, B: Builder> AirdropSender<'_, N, B> {
+    /// Submits the transfers and waits for them and any already in flight to settle.
+    ///
+    /// Blocks are checked first so that transfers included since the report
+    /// was last saved are recorded. Transfers are then submitted one at a time
+    /// so the builder receives them in nonce order. Once one fails the rest
+    /// can't be included, so they aren't submitted. Transfers still awaiting
+    /// inclusion after [`AirdropSender::timeout`] are marked failed.
+    pub async fn send(&self, report: &mut Report, transfers: Vec<Transfer>) -> anyhow::Result<()> {
+        let deadline = Instant::now() + self.timeout;
+        let mut queue = transfers.into_iter();
+        let mut stopped = self.poll(report).await?;
+        loop {
+            while !stopped && report.in_flight().len() < self.max_pending {
+                let Some(transfer) = queue.next() else {
+                    break;
+                };
+                let solution = crate::retarget(transfer.solution, self.addresses)?;
+                match self.builder.submit(&solution).await {
+                    Ok(ca) => report.submitted(transfer.entry, transfer.nonce, ca),
+                    Err(e) => {
+                        report.failed(transfer.entry, format!("Submission failed: {}", e));
+                        self.rollback(report, transfer.nonce);
+                        stopped = true;
+                    }
+                }
+            }
+            if stopped {
+                for transfer in queue.by_ref() {
+                    report.failed(
+                        transfer.entry,
+                        "Not submitted as an earlier transfer wasn't included",
+                    );
+                }
+            }
+            self.save(report).await?;
+            if report.in_flight().is_empty() && queue.len() == 0 {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                let in_flight = report.in_flight();
+                if let Some(Status::Submitted { nonce, .. }) =
+                    in_flight.first().map(|&i| &report.entries[i].status)
+                {
+                    self.rollback(report, *nonce);
+                }
+                for entry in in_flight {
+                    report.timed_out(entry);
+                }
+                stopped = true;
+                continue;
+            }
+
+            tokio::time::sleep(self.poll_interval).await;
+            stopped |= self.poll(report).await?;
+        }
+    }
+
+    /// Records the transfers included in new blocks and those the builder failed.
+    ///
+    /// Returns whether any failed.
+    async fn poll(&self, report: &mut Report) -> anyhow::Result<bool> {
+        let next_block = report.next_block;
+        self.node
+            .for_each_block(next_block, |block| {
+                report.observe_block(block);
+                Ok(())
+            })
+            .await?;
+        if report.next_block > next_block {
+            let nonce_key = crate::nonce_key(report.from);
+            let nonce = self
+                .node
+                .query_state(&self.addresses.contract, &nonce_key)
+                .await?;
+            self.nonces.reconcile(
+                &self.addresses.contract,
+                &nonce_key,
+                Query::new(nonce).get()?,
+            );
+        }
+        let mut failed = false;
+        for entry in report.in_flight() {
+            let Status::Submitted { nonce, solution } = &report.entries[entry].status else {
+                continue;
+            };
+            let nonce = *nonce;
+            if let Some(reason) = self.builder.latest_failure(solution).await? {
+                report.failed(entry, reason);
+                self.rollback(report, nonce);
+                failed = true;
+            }
+        }
+        Ok(failed)
+    }
+
+    /// Releases `nonce` and every later nonce of the sender after a transfer failed.
+    fn rollback(&self, report: &Report, nonce: Word) {
+        let nonce_key = crate::nonce_key(report.from);
+        self.nonces
+            .rollback(&self.addresses.contract, &nonce_key, nonce);
+    }
+
+    async fn save(&self, report: &Report) -> anyhow::Result<()> {
+        tokio::fs::write(self.report_path, serde_json::to_string_pretty(report)?).await?;
+        Ok(())
+    }
+}
+
+impl Node for EssentialNodeClient {
+    async fn for_each_block(
+        &self,
+        start: Word,
+        f: impl FnMut(&Block) -> anyhow::Result<()>,
+    ) -> anyhow::Result<Word> {
+        EssentialNodeClient::for_each_block(self, start, f).await
+    }
+
+    async fn query_state(
+        &self,
+        contract: &ContentAddress,
+        key: &Key,
+    ) -> anyhow::Result<Option<Value>> {
+        EssentialNodeClient::query_state(self, contract.clone(), key.clone()).await
+    }
+}
+
+impl Builder for EssentialBuilderClient {
+    async fn submit(&self, solution: &Solution) -> anyhow::Result<ContentAddress> {
+        self.submit_solution(solution).await
+    }
+
+    async fn latest_failure(&self, solution: &ContentAddress) -> anyhow::Result<Option<String>> {
+        let failures = self.latest_solution_failures(solution, 1).await?;
+        Ok(failures.first().map(|f| f.err_msg.to_string()))
+    }
+}
@@ -0,0 +1,81 @@
+//! # Amount
+//! Converts between raw token amounts and decimal amounts such as `1.5`.
+
+use anyhow::{bail, ensure};
+use essential_types::Word;
+
+/// The most decimals a token can have while one whole token still fits in a `Word`.
+pub const MAX_DECIMALS: Word = 18;
+
+/// Parses a decimal amount such as `1.5` into raw units.
+///
+/// Fails if the amount has more decimal places than the token or doesn't fit in a `Word`.
+pub fn parse(amount: &str, decimals: Word) -> anyhow::Result<Word> {
+    let decimals = check_decimals(decimals)?;
+    let invalid = || anyhow::anyhow!("Invalid amount: {}", amount);
+    let (whole, fraction) = match amount.split_once('.') {
+        Some((whole, fraction)) if is_digits(fraction) => (whole, fraction),
+        Some(_) => return Err(invalid()),
+        None => (amount, ""),
+    };
+    ensure!(is_digits(whole), invalid());
+    // Trailing zeros don't add precision.
+    let fraction = fraction.trim_end_matches('0');
+    ensure!(
+        fraction.len() <= decimals,
+        "Amount {} has more than {} decimal places",
+        amount,
+        decimals
+    );
+    let overflow = || anyhow::anyhow!("Amount {} is too large", amount);
+    let scale = |places: usize| Word::pow(10, places as u32);
+    let whole: Word = whole.parse().map_err(|_| overflow())?;
+    let fraction: Word = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<Word>()? * scale(decimals - fraction.len())
+    };
+    whole
+        .checked_mul(scale(decimals))
+        .and_then(|whole| whole.checked_add(fraction))
+        .ok_or_else(overflow)
+}
+
+/// Splits an amount such as `1.5 ALC` into the amount and its symbol, if any.
+pub fn split_symbol(input: &str) -> (&str, Option<&str>) {
+    let input = input.trim();
+    match input.split_once(char::is_whitespace) {
+        Some((amount, symbol)) => (amount, Some(symbol.trim())),
+        None => (input, None),
+    }
+}
+
+/// Formats raw units as a decimal amount, without trailing zeros.
+pub fn format(raw: Word, decimals: Word) -> anyhow::Result<String> {
+    let decimals = check_decimals(decimals)?;
+    let digits = raw.unsigned_abs().to_string();
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    let sign = if raw < 0 { "-" } else { "" };
+    if fraction.is_empty() {
+        Ok(format!("{}{}", sign, whole))
+    } else {
+        Ok(format!("{}{}.{}", sign, whole, fraction))
+    }
+}
+
+fn check_decimals(decimals: Word) -> anyhow::Result<usize> {
+    if !(0..=MAX_DECIMALS).contains(&decimals) {
+        bail!(
+            "Decimals must be between 0 and {}, got: {}",
+            MAX_DECIMALS,
+            decimals
+        );
+    }
+    Ok(decimals as usize)
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
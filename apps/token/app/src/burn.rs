@@ -1,12 +1,12 @@
 //! Contains functionality for burning tokens in the token contract.
-use essential_app_utils::inputs::Encode;
+use essential_app_utils::inputs::{Encode, WriteDecVars};
 use essential_sign::secp256k1::ecdsa::RecoverableSignature;
 use essential_types::{
     solution::{Solution, SolutionData},
-    Word,
+    PredicateAddress, Word,
 };
 
-use crate::{balance, nonce, Query};
+use crate::Query;
 
 /// Represents the initial data required for burning tokens.
 pub struct Init {
@@ -19,6 +19,7 @@ pub struct Init {
 }
 
 /// Represents the data to be signed for a burn solutions.
+#[derive(WriteDecVars)]
 pub struct ToSign {
     /// The hashed key of the account.
     pub hashed_key: [Word; 4],
@@ -42,6 +43,18 @@ pub struct BuildSolution {
     pub signature: RecoverableSignature,
 }
 
+/// Contains all necessary information to build a burn from an account owned by a predicate.
+pub struct BuildPredicateSolution {
+    /// The predicate that owns the account.
+    pub owner: PredicateAddress,
+    /// The current nonce of the account.
+    pub nonce: Query,
+    /// The current balance of the account.
+    pub current_balance: Query,
+    /// The amount of tokens to burn.
+    pub amount: Word,
+}
+
 /// Prepares the data to be signed for a burn transaction.
 pub fn data_to_sign(account: Init) -> anyhow::Result<ToSign> {
     let Init {
@@ -49,7 +62,7 @@ pub fn data_to_sign(account: Init) -> anyhow::Result<ToSign> {
         nonce: current_nonce,
         amount,
     } = account;
-    let new_nonce = increment_nonce(nonce(current_nonce)?);
+    let new_nonce = increment_nonce(current_nonce.get()?);
     Ok(ToSign {
         hashed_key,
         amount,
@@ -66,27 +79,56 @@ pub fn build_solution(build: BuildSolution) -> anyhow::Result<Solution> {
         amount,
         signature,
     } = build;
-    let from_balance = balance(current_balance)?;
+    let auth = super::token::BurnAuth::Signed(signature.encode());
+    let solution = solution_data(hashed_key, amount, new_nonce, current_balance, auth)?;
+    Ok(Solution {
+        data: vec![solution],
+    })
+}
+
+/// Builds a burn from the account owned by a predicate.
+///
+/// `authorize` is given the path of the burn in the solution and returns the
+/// owning predicate's solution data.
+pub fn build_predicate_solution(
+    build: BuildPredicateSolution,
+    authorize: impl FnOnce(Word) -> SolutionData,
+) -> anyhow::Result<Solution> {
+    let BuildPredicateSolution {
+        owner,
+        nonce,
+        current_balance,
+        amount,
+    } = build;
+    let hashed_key = crate::predicate_account(&owner);
+    let new_nonce = increment_nonce(nonce.get()?);
+    let auth = super::token::BurnAuth::Predicate((owner.encode(), crate::AUTHORIZER_PATH));
+    let solution = solution_data(hashed_key, amount, new_nonce, current_balance, auth)?;
+    Ok(crate::with_authorizer(solution, authorize))
+}
+
+fn solution_data(
+    hashed_key: [Word; 4],
+    amount: Word,
+    new_nonce: Word,
+    current_balance: Query,
+    auth: super::token::BurnAuth,
+) -> anyhow::Result<SolutionData> {
+    let from_balance = current_balance.get()?;
     let new_from_balance = calculate_from_balance(from_balance, amount)?;
     let pub_vars = super::token::Burn::PubVars {
         key: hashed_key,
         amount,
     };
-    let signature = signature.encode();
-    let vars = super::token::Burn::Vars {
-        auth: super::token::BurnAuth::Signed(signature),
-    };
+    let vars = super::token::Burn::Vars { auth };
     let mutations = super::token::storage::mutations()
         .balances(|map| map.entry(hashed_key, new_from_balance))
         .nonce(|nonces| nonces.entry(hashed_key, new_nonce));
-    let solution = SolutionData {
+    Ok(SolutionData {
         predicate_to_solve: super::token::Burn::ADDRESS,
         decision_variables: vars.into(),
         transient_data: pub_vars.into(),
         state_mutations: mutations.into(),
-    };
-    Ok(Solution {
-        data: vec![solution],
     })
 }
 
@@ -105,10 +147,6 @@ fn calculate_from_balance(from_balance: Word, amount: Word) -> anyhow::Result<Wo
 impl ToSign {
     /// Converts the ToSign struct to a vector of Words for signing.
     pub fn to_words(&self) -> Vec<Word> {
-        self.hashed_key
-            .iter()
-            .copied()
-            .chain([self.amount, self.new_nonce].iter().copied())
-            .collect()
+        essential_app_utils::inputs::to_words(self)
     }
 }
@@ -0,0 +1,119 @@
+//! # Cancel
+//! Contains functionality for cancelling pending transfers and burns in the token contract.
+use essential_app_utils::inputs::{Encode, WriteDecVars};
+use essential_sign::secp256k1::ecdsa::RecoverableSignature;
+use essential_types::{
+    solution::{Solution, SolutionData},
+    PredicateAddress, Word,
+};
+
+use crate::Query;
+
+/// Represents the initial data required for cancelling.
+pub struct Init {
+    /// The hashed key of the account.
+    pub hashed_key: [Word; 4],
+    /// The current nonce of the account.
+    pub nonce: Query,
+}
+
+/// Represents the data to be signed for a cancel solution.
+#[derive(WriteDecVars)]
+pub struct ToSign {
+    /// The hashed key of the account.
+    pub hashed_key: [Word; 4],
+    /// The new nonce of the account.
+    pub new_nonce: Word,
+}
+
+/// Contains all necessary information to build a cancel solution.
+pub struct BuildSolution {
+    /// The new nonce of the account.
+    pub new_nonce: Word,
+    /// The hashed key of the account.
+    pub hashed_key: [Word; 4],
+    /// The signature over the data.
+    pub signature: RecoverableSignature,
+}
+
+/// Contains all necessary information to build a cancel for an account owned by a predicate.
+pub struct BuildPredicateSolution {
+    /// The predicate that owns the account.
+    pub owner: PredicateAddress,
+    /// The current nonce of the account.
+    pub nonce: Query,
+}
+
+/// Prepares the data to be signed for a cancel.
+///
+/// Any pending transfer or burn signed over the current nonce's successor
+/// becomes invalid once the cancel is included.
+pub fn data_to_sign(account: Init) -> anyhow::Result<ToSign> {
+    let Init {
+        hashed_key,
+        nonce: current_nonce,
+    } = account;
+    let new_nonce = increment_nonce(current_nonce.get()?);
+    Ok(ToSign {
+        hashed_key,
+        new_nonce,
+    })
+}
+
+/// Builds a cancel solution based on the provided data.
+pub fn build_solution(build: BuildSolution) -> anyhow::Result<Solution> {
+    let BuildSolution {
+        new_nonce,
+        hashed_key,
+        signature,
+    } = build;
+    let auth = super::token::CancelAuth::Signed(signature.encode());
+    Ok(Solution {
+        data: vec![solution_data(hashed_key, new_nonce, auth)],
+    })
+}
+
+/// Builds a cancel for the account owned by a predicate.
+///
+/// `authorize` is given the path of the cancel in the solution and returns the
+/// owning predicate's solution data.
+pub fn build_predicate_solution(
+    build: BuildPredicateSolution,
+    authorize: impl FnOnce(Word) -> SolutionData,
+) -> anyhow::Result<Solution> {
+    let BuildPredicateSolution { owner, nonce } = build;
+    let hashed_key = crate::predicate_account(&owner);
+    let new_nonce = increment_nonce(nonce.get()?);
+    let auth = super::token::CancelAuth::Predicate((owner.encode(), crate::AUTHORIZER_PATH));
+    let solution = solution_data(hashed_key, new_nonce, auth);
+    Ok(crate::with_authorizer(solution, authorize))
+}
+
+fn solution_data(
+    hashed_key: [Word; 4],
+    new_nonce: Word,
+    auth: super::token::CancelAuth,
+) -> SolutionData {
+    let pub_vars = super::token::Cancel::PubVars { key: hashed_key };
+    let vars = super::token::Cancel::Vars { auth };
+    let mutations =
+        super::token::storage::mutations().nonce(|nonces| nonces.entry(hashed_key, new_nonce));
+    SolutionData {
+        predicate_to_solve: super::token::Cancel::ADDRESS,
+        decision_variables: vars.into(),
+        transient_data: pub_vars.into(),
+        state_mutations: mutations.into(),
+    }
+}
+
+/// Increments the nonce by one.
+fn increment_nonce(nonce: Word) -> Word {
+    nonce + 1
+}
+
+impl ToSign {
+    /// Converts the ToSign struct to a vector of Words for signing.
+    pub fn to_words(&self) -> Vec<Word> {
+        essential_app_utils::inputs::to_words(self)
+    }
+}
@@ -0,0 +1,154 @@
+//! # Config
+//! Contains functionality for creating a new token contract from the token Pint project.
+//!
+//! The name, symbol and minting key are compiled into the contract, so each
+//! token is its own contract generated from a copy of the project.
+
+use anyhow::{bail, ensure};
+use essential_types::{convert::u8_32_from_word_4, Word};
+use std::path::{Path, PathBuf};
+
+/// The Pint source file holding the token's constants.
+pub const CONFIG_FILE: &str = "src/config.pnt";
+
+/// The constants compiled into a token contract.
+pub struct TokenConfig {
+    /// The name of the token.
+    pub name: String,
+    /// The symbol of the token.
+    pub symbol: String,
+    /// The hashed key of the only account allowed to mint.
+    pub mint_key: [Word; 4],
+}
+
+impl TokenConfig {
+    /// Generates the contents of `config.pnt`.
+    ///
+    /// The name and symbol are written into comments next to their hashes, so
+    /// they may not contain control characters such as newlines, which would
+    /// end the comment.
+    pub fn to_pint(&self) -> anyhow::Result<String> {
+        for (field, value) in [("name", &self.name), ("symbol", &self.symbol)] {
+            ensure!(
+                !value.chars().any(char::is_control),
+                "Token {} {:?} contains control characters",
+                field,
+                value
+            );
+        }
+        Ok(format!(
+            "// Hash of \"{}\"\nconst NAME = {};\n// Hash of \"{}\"\nconst SYMBOL = {};\nconst MINT_KEY = {};\n",
+            self.name,
+            b256(crate::info::hash_str(&self.name)),
+            self.symbol,
+            b256(crate::info::hash_str(&self.symbol)),
+            b256(self.mint_key),
+        ))
+    }
+}
+
+/// Copies the token Pint project at `template` to `dest` with the given config.
+///
+/// The copy is named `package` in its `pint.toml` and relative dependency
+/// paths are made absolute so that it compiles from its new location.
+///
+/// If `dest` already holds a project generated with the same package name and
+/// config it is left as is, so an interrupted `token new` can be resumed.
+/// Returns whether the project was created.
+pub async fn new_project(
+    template: &Path,
+    dest: &Path,
+    package: &str,
+    config: &TokenConfig,
+) -> anyhow::Result<bool> {
+    let constants = config.to_pint()?;
+    if dest.exists() {
+        ensure!(
+            is_generated(dest, package, &constants).await?,
+            "{} already exists, choose a different directory",
+            dest.display()
+        );
+        return Ok(false);
+    }
+    let template = tokio::fs::canonicalize(template).await?;
+    copy_dir(&template.join("src"), &dest.join("src")).await?;
+    tokio::fs::write(dest.join(CONFIG_FILE), constants).await?;
+
+    let manifest = tokio::fs::read_to_string(template.join("pint.toml")).await?;
+    let manifest = relocate_manifest(&manifest, &template, package)?;
+    tokio::fs::write(dest.join("pint.toml"), manifest).await?;
+    tokio::fs::write(dest.join(".gitignore"), "out\n").await?;
+    Ok(true)
+}
+
+/// Whether `dest` holds a project generated by [`new_project`] with the given
+/// package name and `config.pnt` contents.
+async fn is_generated(dest: &Path, package: &str, expected: &str) -> anyhow::Result<bool> {
+    let (Ok(constants), Ok(manifest)) = (
+        tokio::fs::read_to_string(dest.join(CONFIG_FILE)).await,
+        tokio::fs::read_to_string(dest.join("pint.toml")).await,
+    ) else {
+        return Ok(false);
+    };
+    let manifest = manifest.parse::<toml::Table>()?;
+    let name = manifest
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(toml::Value::as_str);
+    Ok(constants == expected && name == Some(package))
+}
+
+/// Copies the directory `from` to `to`, including its subdirectories.
+///
+/// Symlinks are followed. Fails on anything that is neither a file nor a directory.
+async fn copy_dir(from: &Path, to: &Path) -> anyhow::Result<()> {
+    let mut dirs = vec![(from.to_path_buf(), to.to_path_buf())];
+    while let Some((from, to)) = dirs.pop() {
+        tokio::fs::create_dir_all(&to).await?;
+        let mut entries = tokio::fs::read_dir(&from).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let dest = to.join(entry.file_name());
+            let file_type = tokio::fs::metadata(&path).await?.file_type();
+            if file_type.is_dir() {
+                dirs.push((path, dest));
+            } else if file_type.is_file() {
+                tokio::fs::copy(&path, &dest).await?;
+            } else {
+                bail!(
+                    "Can't copy {}, it isn't a file or directory",
+                    path.display()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renames the package and makes dependency paths relative to `template` absolute.
+///
+/// Paths are canonicalized as Pint treats differently spelled paths as different sources.
+fn relocate_manifest(manifest: &str, template: &Path, package: &str) -> anyhow::Result<String> {
+    let mut manifest = manifest.parse::<toml::Table>()?;
+    let Some(toml::Value::Table(p)) = manifest.get_mut("package") else {
+        bail!("package not found in pint.toml");
+    };
+    p.insert("name".to_string(), package.into());
+    for section in ["dependencies", "contract-dependencies"] {
+        let Some(toml::Value::Table(deps)) = manifest.get_mut(section) else {
+            continue;
+        };
+        for (_, dep) in deps.iter_mut() {
+            let Some(toml::Value::String(path)) = dep.get_mut("path") else {
+                continue;
+            };
+            let absolute: PathBuf = std::fs::canonicalize(template.join(&*path))?;
+            *path = absolute.display().to_string();
+        }
+    }
+    Ok(toml::to_string(&manifest)?)
+}
+
+fn b256(words: [Word; 4]) -> String {
+    format!("0x{}", hex::encode_upper(u8_32_from_word_4(words)))
+}
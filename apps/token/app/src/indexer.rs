@@ -0,0 +1,414 @@
+//! # Indexer
+//! Builds a local SQLite index of account histories from the token's blocks.
+//!
+//! Only state changed by solutions is indexed, so balances set at genesis are
+//! not included.
+
+use anyhow::{bail, ensure};
+use essential_app_utils::compile::ContractAddresses;
+use essential_rest_client::node_client::EssentialNodeClient;
+use essential_types::{
+    convert::{u8_32_from_word_4, word_4_from_u8_32},
+    solution::Solution,
+    Block, ContentAddress, PredicateAddress, Word,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+use crate::info::pub_vars;
+
+/// The default file name for the index database.
+pub const DEFAULT_DB: &str = "token-index.sqlite";
+
+/// The token predicates that the indexer recognises.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Predicates {
+    /// The mint predicate.
+    pub mint: PredicateAddress,
+    /// The burn predicate.
+    pub burn: PredicateAddress,
+    /// The transfer predicate.
+    pub transfer: PredicateAddress,
+    /// The cancel predicate.
+    pub cancel: PredicateAddress,
+}
+
+/// A change to the token decoded from solution data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Tokens were minted to the account.
+    Mint {
+        /// The hashed key of the account.
+        key: [Word; 4],
+        /// The amount minted.
+        amount: Word,
+    },
+    /// Tokens were burnt from the account.
+    Burn {
+        /// The hashed key of the account.
+        key: [Word; 4],
+        /// The amount burnt.
+        amount: Word,
+    },
+    /// Tokens were moved between accounts.
+    Transfer {
+        /// The hashed key of the sender.
+        from: [Word; 4],
+        /// The hashed key of the recipient.
+        to: [Word; 4],
+        /// The amount transferred.
+        amount: Word,
+    },
+    /// The account's nonce was bumped.
+    Cancel {
+        /// The hashed key of the account.
+        key: [Word; 4],
+    },
+}
+
+/// An event involving an account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// The number of the block the event was included in.
+    pub block: Word,
+    /// The content address of the solution containing the event.
+    pub solution: ContentAddress,
+    /// The event.
+    pub event: Event,
+}
+
+/// A local index of the token's events and balances.
+pub struct Index {
+    conn: Connection,
+    predicates: Predicates,
+}
+
+impl Predicates {
+    /// The predicates of a token contract, such as one created with `token new`.
+    pub fn resolve(addresses: &ContractAddresses) -> anyhow::Result<Self> {
+        Ok(Self {
+            mint: addresses.predicate("Mint")?,
+            burn: addresses.predicate("Burn")?,
+            transfer: addresses.predicate("Transfer")?,
+            cancel: addresses.predicate("Cancel")?,
+        })
+    }
+}
+
+impl Default for Predicates {
+    /// The predicates of the token contract this crate was compiled against.
+    fn default() -> Self {
+        Self {
+            mint: super::token::Mint::ADDRESS,
+            burn: super::token::Burn::ADDRESS,
+            transfer: super::token::Transfer::ADDRESS,
+            cancel: super::token::Cancel::ADDRESS,
+        }
+    }
+}
+
+/// Decodes the token events in a solution, in order.
+///
+/// Solution data for other predicates is skipped.
+pub fn events(predicates: &Predicates, solution: &Solution) -> anyhow::Result<Vec<Event>> {
+    let mut events = vec![];
+    for data in &solution.data {
+        let address = &data.predicate_to_solve;
+        let event = if *address == predicates.mint {
+            let vars: super::token::Mint::PubVars = pub_vars(data)?;
+            Event::Mint {
+                key: vars.key,
+                amount: vars.amount,
+            }
+        } else if *address == predicates.burn {
+            let vars: super::token::Burn::PubVars = pub_vars(data)?;
+            Event::Burn {
+                key: vars.key,
+                amount: vars.amount,
+            }
+        } else if *address == predicates.transfer {
+            let vars: super::token::Transfer::PubVars = pub_vars(data)?;
+            Event::Transfer {
+                from: vars.key,
+                to: vars.to,
+                amount: vars.amount,
+            }
+        } else if *address == predicates.cancel {
+            let vars: super::token::Cancel::PubVars = pub_vars(data)?;
+            Event::Cancel { key: vars.key }
+        } else {
+            continue;
+        };
+        events.push(event);
+    }
+    Ok(events)
+}
+
+impl Event {
+    /// The name of the event, as stored in the index.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::Mint { .. } => "mint",
+            Event::Burn { .. } => "burn",
+            Event::Transfer { .. } => "transfer",
+            Event::Cancel { .. } => "cancel",
+        }
+    }
+
+    /// The account the event takes from and the account it gives to, if any, and the amount.
+    fn columns(&self) -> (Option<[Word; 4]>, Option<[Word; 4]>, Option<Word>) {
+        match *self {
+            Event::Mint { key, amount } => (None, Some(key), Some(amount)),
+            Event::Burn { key, amount } => (Some(key), None, Some(amount)),
+            Event::Transfer { from, to, amount } => (Some(from), Some(to), Some(amount)),
+            Event::Cancel { key } => (Some(key), None, None),
+        }
+    }
+
+    /// The change in the token's total supply caused by the event.
+    pub fn supply_change(&self) -> anyhow::Result<Word> {
+        match *self {
+            Event::Mint { amount, .. } => Ok(amount),
+            Event::Burn { amount, .. } => amount
+                .checked_neg()
+                .ok_or_else(|| anyhow::anyhow!("Invalid burn amount {}", amount)),
+            Event::Transfer { .. } | Event::Cancel { .. } => Ok(0),
+        }
+    }
+
+    fn from_columns(
+        kind: &str,
+        from: Option<[Word; 4]>,
+        to: Option<[Word; 4]>,
+        amount: Option<Word>,
+    ) -> anyhow::Result<Self> {
+        let event = match (kind, from, to, amount) {
+            ("mint", None, Some(key), Some(amount)) => Event::Mint { key, amount },
+            ("burn", Some(key), None, Some(amount)) => Event::Burn { key, amount },
+            ("transfer", Some(from), Some(to), Some(amount)) => {
+                Event::Transfer { from, to, amount }
+            }
+            ("cancel", Some(key), None, None) => Event::Cancel { key },
+            _ => bail!("Invalid {} event in index", kind),
+        };
+        Ok(event)
+    }
+}
+
+impl Index {
+    /// Opens or creates the index database at `path`.
+    ///
+    /// Fails if the database indexes a different token contract.
+    pub fn open(path: &Path, predicates: Predicates) -> anyhow::Result<Self> {
+        Self::new(Connection::open(path)?, predicates)
+    }
+
+    /// Creates an index that is not persisted.
+    pub fn in_memory(predicates: Predicates) -> anyhow::Result<Self> {
+        Self::new(Connection::open_in_memory()?, predicates)
+    }
+
+    fn new(conn: Connection, predicates: Predicates) -> anyhow::Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS progress (
+                contract BLOB NOT NULL,
+                next_block INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS events (
+                id INTEGER PRIMARY KEY,
+                block INTEGER NOT NULL,
+                solution BLOB NOT NULL,
+                kind TEXT NOT NULL,
+                from_key BLOB,
+                to_key BLOB,
+                amount INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS events_from ON events (from_key);
+            CREATE INDEX IF NOT EXISTS events_to ON events (to_key);
+            CREATE TABLE IF NOT EXISTS balances (
+                account BLOB PRIMARY KEY,
+                balance INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS supply (
+                total INTEGER NOT NULL
+            );",
+        )?;
+        // Indexes created before the supply was tracked already have the events to sum.
+        conn.execute(
+            "INSERT INTO supply (total)
+            SELECT (
+                SELECT COALESCE(SUM(CASE kind WHEN 'mint' THEN amount ELSE -amount END), 0)
+                FROM events WHERE kind IN ('mint', 'burn')
+            )
+            WHERE NOT EXISTS (SELECT 1 FROM supply)",
+            [],
+        )?;
+        let contract = predicates.mint.contract.0.to_vec();
+        let indexed: Option<Vec<u8>> = conn
+            .query_row("SELECT contract FROM progress", [], |row| row.get(0))
+            .optional()?;
+        match indexed {
+            Some(indexed) => ensure!(
+                indexed == contract,
+                "Index is for contract {}, not {}",
+                hex::encode_upper(indexed),
+                predicates.mint.contract
+            ),
+            None => {
+                conn.execute(
+                    "INSERT INTO progress (contract, next_block) VALUES (?1, 0)",
+                    params![contract],
+                )?;
+            }
+        }
+        Ok(Self { conn, predicates })
+    }
+
+    /// The number of the next block to index.
+    pub fn next_block(&self) -> anyhow::Result<Word> {
+        Ok(self
+            .conn
+            .query_row("SELECT next_block FROM progress", [], |row| row.get(0))?)
+    }
+
+    /// Indexes the token events in a block.
+    ///
+    /// Blocks before [`Index::next_block`] have already been indexed and are skipped.
+    pub fn index_block(&mut self, block: &Block) -> anyhow::Result<()> {
+        if block.number < self.next_block()? {
+            return Ok(());
+        }
+        let tx = self.conn.transaction()?;
+        let mut supply: Word = tx.query_row("SELECT total FROM supply", [], |row| row.get(0))?;
+        for solution in &block.solutions {
+            let ca = essential_hash::content_addr(solution);
+            for event in events(&self.predicates, solution)? {
+                let (from, to, amount) = event.columns();
+                tx.execute(
+                    "INSERT INTO events (block, solution, kind, from_key, to_key, amount)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        block.number,
+                        ca.0.to_vec(),
+                        event.kind(),
+                        from.map(key_bytes),
+                        to.map(key_bytes),
+                        amount
+                    ],
+                )?;
+                supply = supply
+                    .checked_add(event.supply_change()?)
+                    .ok_or_else(|| anyhow::anyhow!("Total supply overflow"))?;
+                let amount = amount.unwrap_or_default();
+                let taken = amount
+                    .checked_neg()
+                    .ok_or_else(|| anyhow::anyhow!("Invalid amount {}", amount))?;
+                for (account, delta) in [(from, taken), (to, amount)] {
+                    let Some(account) = account else {
+                        continue;
+                    };
+                    tx.execute(
+                        "INSERT INTO balances (account, balance) VALUES (?1, ?2)
+                        ON CONFLICT (account) DO UPDATE SET balance = balance + ?2",
+                        params![key_bytes(account), delta],
+                    )?;
+                }
+            }
+        }
+        tx.execute("UPDATE supply SET total = ?1", params![supply])?;
+        tx.execute(
+            "UPDATE progress SET next_block = ?1",
+            params![block.number + 1],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Indexes all blocks the node has that aren't indexed yet.
+    ///
+    /// Returns the number of blocks indexed.
+    pub async fn sync(&mut self, node: &EssentialNodeClient) -> anyhow::Result<usize> {
+        let mut indexed = 0;
+        node.for_each_block(self.next_block()?, |block| {
+            self.index_block(block)?;
+            indexed += 1;
+            Ok(())
+        })
+        .await?;
+        Ok(indexed)
+    }
+
+    /// The events involving an account, oldest first.
+    pub fn history(&self, account: [Word; 4]) -> anyhow::Result<Vec<HistoryEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT block, solution, kind, from_key, to_key, amount FROM events
+            WHERE from_key = ?1 OR to_key = ?1
+            ORDER BY id",
+        )?;
+        let rows = stmt.query_map(params![key_bytes(account)], |row| {
+            Ok((
+                row.get::<_, Word>(0)?,
+                row.get::<_, Vec<u8>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<Vec<u8>>>(3)?,
+                row.get::<_, Option<Vec<u8>>>(4)?,
+                row.get::<_, Option<Word>>(5)?,
+            ))
+        })?;
+        rows.map(|row| {
+            let (block, solution, kind, from, to, amount) = row?;
+            Ok(HistoryEntry {
+                block,
+                solution: ContentAddress(bytes_32(solution)?),
+                event: Event::from_columns(
+                    &kind,
+                    from.map(bytes_key).transpose()?,
+                    to.map(bytes_key).transpose()?,
+                    amount,
+                )?,
+            })
+        })
+        .collect()
+    }
+
+    /// The total supply of the token: all indexed mints less all indexed burns.
+    pub fn total_supply(&self) -> anyhow::Result<Word> {
+        Ok(self
+            .conn
+            .query_row("SELECT total FROM supply", [], |row| row.get(0))?)
+    }
+
+    /// The accounts with a positive balance, largest first.
+    ///
+    /// Balances are summed from the indexed events, so they don't include any
+    /// balances the node started with in its genesis state.
+    pub fn holders(&self) -> anyhow::Result<Vec<([Word; 4], Word)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT account, balance FROM balances
+            WHERE balance > 0
+            ORDER BY balance DESC, account",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Word>(1)?))
+        })?;
+        rows.map(|row| {
+            let (account, balance) = row?;
+            Ok((bytes_key(account)?, balance))
+        })
+        .collect()
+    }
+}
+
+fn key_bytes(key: [Word; 4]) -> Vec<u8> {
+    u8_32_from_word_4(key).to_vec()
+}
+
+fn bytes_key(bytes: Vec<u8>) -> anyhow::Result<[Word; 4]> {
+    Ok(word_4_from_u8_32(bytes_32(bytes)?))
+}
+
+fn bytes_32(bytes: Vec<u8>) -> anyhow::Result<[u8; 32]> {
+    bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| anyhow::anyhow!("Expected 32 bytes, got {}", b.len()))
+}
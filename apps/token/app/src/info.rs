@@ -0,0 +1,152 @@
+//! # Info
+//! Contains functionality for reading the token's metadata and supply.
+//!
+//! The contract only stores hashes of the name and symbol, so they can be
+//! recovered by matching against candidate strings.
+
+use anyhow::ensure;
+use essential_app_utils::state::Typed;
+use essential_types::{
+    convert::word_4_from_u8_32,
+    solution::{Solution, SolutionData},
+    Block, Word,
+};
+
+use crate::{indexer::Predicates, Query};
+
+/// A name or symbol hash read from state. Nil reads as all zeros.
+pub type QueryHash = Typed<[Word; 4]>;
+
+/// The metadata state read from the token contract.
+pub struct Init {
+    /// The hash of the token's name.
+    pub token_name: QueryHash,
+    /// The hash of the token's symbol.
+    pub token_symbol: QueryHash,
+    /// The number of decimals of the token.
+    pub decimals: Query,
+}
+
+/// The token's metadata, set once by the mint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    /// The hash of the token's name.
+    pub name_hash: [Word; 4],
+    /// The hash of the token's symbol.
+    pub symbol_hash: [Word; 4],
+    /// The number of decimals of the token.
+    pub decimals: Word,
+}
+
+/// Reads the token's metadata, or `None` if the token hasn't been minted yet.
+pub fn metadata(state: Init) -> anyhow::Result<Option<Metadata>> {
+    let Init {
+        token_name,
+        token_symbol,
+        decimals,
+    } = state;
+    if token_name.is_nil() {
+        return Ok(None);
+    }
+    Ok(Some(Metadata {
+        name_hash: token_name.get()?,
+        symbol_hash: token_symbol.get()?,
+        decimals: decimals.get()?,
+    }))
+}
+
+/// Hashes a name or symbol the same way the mint does.
+pub fn hash_str(s: &str) -> [Word; 4] {
+    word_4_from_u8_32(essential_hash::hash(&s))
+}
+
+/// Finds the candidate whose hash matches, if any.
+pub fn match_hash<'a>(hash: &[Word; 4], candidates: &[&'a str]) -> Option<&'a str> {
+    candidates.iter().copied().find(|c| hash_str(c) == *hash)
+}
+
+impl Metadata {
+    /// The token's name, if it is one of the candidates.
+    pub fn name<'a>(&self, candidates: &[&'a str]) -> Option<&'a str> {
+        match_hash(&self.name_hash, candidates)
+    }
+
+    /// The token's symbol, if it is one of the candidates.
+    pub fn symbol<'a>(&self, candidates: &[&'a str]) -> Option<&'a str> {
+        match_hash(&self.symbol_hash, candidates)
+    }
+
+    /// Parses an amount such as `1.5` or `1.5 ALC` into raw units.
+    ///
+    /// A symbol, if given, must match the token's symbol hash.
+    pub fn parse_amount(&self, input: &str) -> anyhow::Result<Word> {
+        let (amount, symbol) = crate::amount::split_symbol(input);
+        if let Some(symbol) = symbol {
+            ensure!(
+                self.symbol(&[symbol]).is_some(),
+                "{} is not this token's symbol",
+                symbol
+            );
+        }
+        crate::amount::parse(amount, self.decimals)
+    }
+
+    /// Formats raw units as a decimal amount.
+    pub fn format_amount(&self, raw: Word) -> anyhow::Result<String> {
+        crate::amount::format(raw, self.decimals)
+    }
+}
+
+/// The total supply of the token as of the given blocks.
+///
+/// Supply isn't stored by the contract, so this is the sum of all mints less
+/// all burns. The blocks must cover the token's full history.
+pub fn total_supply<'a>(
+    predicates: &Predicates,
+    blocks: impl IntoIterator<Item = &'a Block>,
+) -> anyhow::Result<Word> {
+    blocks
+        .into_iter()
+        .flat_map(|block| &block.solutions)
+        .try_fold(0, |supply: Word, solution| {
+            let change = supply_change(predicates, solution)?;
+            supply
+                .checked_add(change)
+                .ok_or_else(|| anyhow::anyhow!("Total supply overflow"))
+        })
+}
+
+/// The change in supply from the mints and burns in a solution.
+pub fn supply_change(predicates: &Predicates, solution: &Solution) -> anyhow::Result<Word> {
+    let mut change: Word = 0;
+    for data in &solution.data {
+        let delta = if data.predicate_to_solve == predicates.mint {
+            pub_vars::<super::token::Mint::PubVars>(data)?.amount
+        } else if data.predicate_to_solve == predicates.burn {
+            let amount = pub_vars::<super::token::Burn::PubVars>(data)?.amount;
+            amount
+                .checked_neg()
+                .ok_or_else(|| anyhow::anyhow!("Invalid burn amount {}", amount))?
+        } else {
+            continue;
+        };
+        change = change
+            .checked_add(delta)
+            .ok_or_else(|| anyhow::anyhow!("Supply change overflow"))?;
+    }
+    Ok(change)
+}
+
+/// Decodes a predicate's public variables from its transient data.
+pub(crate) fn pub_vars<T: pint_abi::Decode>(data: &SolutionData) -> anyhow::Result<T>
+where
+    T::Error: std::fmt::Display,
+{
+    let mut transient = data.transient_data.iter().collect::<Vec<_>>();
+    transient.sort_by(|a, b| a.key.cmp(&b.key));
+    let words: Vec<Word> = transient
+        .into_iter()
+        .flat_map(|m| m.value.iter().copied())
+        .collect();
+    pint_abi::decode(&words).map_err(|e| anyhow::anyhow!("Failed to decode public vars: {}", e))
+}
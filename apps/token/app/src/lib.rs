@@ -2,8 +2,12 @@
 //! # Token
 //! Taken contract front end implementation
 
-use anyhow::bail;
-use essential_types::{Key, Value, Word};
+use essential_app_utils::compile::ContractAddresses;
+use essential_types::{
+    convert::word_4_from_u8_32,
+    solution::{Solution, SolutionData},
+    Key, PredicateAddress, Word,
+};
 
 /// Module containing the token contract ABI.
 #[allow(missing_docs)]
@@ -14,12 +18,28 @@ pub mod token {
     }
 }
 
+pub mod airdrop;
+pub mod amount;
 pub mod burn;
+pub mod cancel;
+pub mod config;
+pub mod indexer;
+pub mod info;
 pub mod mint;
+pub mod offline;
 pub mod transfer;
 
-/// Represents a query result, which may or may not contain a value.
-pub struct Query(pub Option<Value>);
+/// A balance or nonce read from state. Nil reads as zero.
+pub type Query = essential_app_utils::state::Typed<Word>;
+
+/// Parses a hashed public key encoded as hex.
+pub fn parse_hashed_key(hex_key: &str) -> anyhow::Result<[Word; 4]> {
+    Ok(word_4_from_u8_32(
+        hex::decode(hex_key)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Hashed key must be 32 bytes"))?,
+    ))
+}
 
 /// Generates the key for querying an account's balance.
 pub fn balance_key(hashed_key: [Word; 4]) -> Key {
@@ -37,28 +57,81 @@ pub fn nonce_key(hashed_key: [Word; 4]) -> Key {
     keys.into_iter().next().expect("Must be a key")
 }
 
+/// Generates the key for querying the hash of the token's name.
+pub fn token_name_key() -> Key {
+    let keys: Vec<_> = token::storage::keys::keys().token_name().into();
+    keys.into_iter().next().expect("Must be a key")
+}
+
+/// Generates the key for querying the hash of the token's symbol.
+pub fn token_symbol_key() -> Key {
+    let keys: Vec<_> = token::storage::keys::keys().token_symbol().into();
+    keys.into_iter().next().expect("Must be a key")
+}
+
+/// Generates the key for querying the token's decimals.
+pub fn decimals_key() -> Key {
+    let keys: Vec<_> = token::storage::keys::keys().decimals().into();
+    keys.into_iter().next().expect("Must be a key")
+}
+
+/// The hashed key of the account controlled by a predicate.
+///
+/// Tokens sent to this account can be moved with `Predicate` authorization
+/// by solutions that also solve the given predicate.
+pub fn predicate_account(predicate: &PredicateAddress) -> [Word; 4] {
+    essential_app_utils::addresses::predicate_owned_key(predicate)
+}
+
+/// Points a solution built for the token contract at another token contract.
+///
+/// The builders target the contract this crate was compiled against. Tokens
+/// created with different [`config`]s are separate contracts, so their
+/// solutions must solve the predicates of the same name in that contract.
+pub fn retarget(mut solution: Solution, addresses: &ContractAddresses) -> anyhow::Result<Solution> {
+    let predicates = [
+        ("Burn", token::Burn::ADDRESS),
+        ("Cancel", token::Cancel::ADDRESS),
+        ("Mint", token::Mint::ADDRESS),
+        ("Transfer", token::Transfer::ADDRESS),
+    ];
+    for data in &mut solution.data {
+        if let Some((name, _)) = predicates
+            .iter()
+            .find(|(_, address)| *address == data.predicate_to_solve)
+        {
+            data.predicate_to_solve = addresses.predicate(name)?;
+        }
+    }
+    Ok(solution)
+}
+
+/// The path of the token's solution data in a solution built with predicate authorization.
+pub const TOKEN_PATH: Word = 0;
+
+/// The path of the authorizing predicate's solution data in a solution built
+/// with predicate authorization.
+///
+/// The token contract checks that the owning predicate is solved at this path.
+pub const AUTHORIZER_PATH: Word = 1;
+
+/// Places the token's solution data at [`TOKEN_PATH`], followed by the data
+/// of the predicate authorizing it at [`AUTHORIZER_PATH`], which is given the
+/// token's path.
+fn with_authorizer(token: SolutionData, authorize: impl FnOnce(Word) -> SolutionData) -> Solution {
+    Solution {
+        data: vec![token, authorize(TOKEN_PATH)],
+    }
+}
+
 /// Extracts the nonce from a Query result.
+#[deprecated(note = "use `Query::get`")]
 pub fn nonce(nonce: Query) -> anyhow::Result<Word> {
-    let r = match nonce.0 {
-        Some(nonce) => match &nonce[..] {
-            [] => 0,
-            [nonce] => *nonce,
-            _ => bail!("Expected single word, got: {:?}", nonce),
-        },
-        None => 0,
-    };
-    Ok(r)
+    nonce.get()
 }
 
 /// Extracts the balance from a Query result.
+#[deprecated(note = "use `Query::get`")]
 pub fn balance(balance: Query) -> anyhow::Result<Word> {
-    let r = match balance.0 {
-        Some(balance) => match &balance[..] {
-            [] => 0,
-            [balance] => *balance,
-            _ => bail!("Expected single word, got: {:?}", balance),
-        },
-        None => 0,
-    };
-    Ok(r)
+    balance.get()
 }
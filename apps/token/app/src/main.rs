@@ -2,23 +2,32 @@
 
 use anyhow::bail;
 use clap::{Args, Parser, Subcommand};
-use essential_app_utils::compile::compile_pint_project;
+use essential_app_utils::{
+    compile::ContractAddresses,
+    credentials::Credentials,
+    deploy::{remote::deploy_project, DeployOutcome, Deployment, MANIFEST_FILE},
+    nonce::NonceManager,
+    signer::{AccountSigner, Signer},
+};
 use essential_rest_client::{
     builder_client::EssentialBuilderClient, node_client::EssentialNodeClient,
 };
-use essential_signer::Signature;
-use essential_types::{convert::word_4_from_u8_32, ContentAddress, PredicateAddress, Word};
-use essential_wallet::Wallet;
-use std::path::PathBuf;
-use token::Query;
+use essential_types::{convert::u8_32_from_word_4, ContentAddress, Key, PredicateAddress, Word};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use token::{transfer::SignMode, Query};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
-    /// Essential wallet directory.
-    /// If not set then a sensible default will be used (like ~/.essential-wallet).
-    #[arg(short, long)]
-    wallet: Option<PathBuf>,
+    #[command(flatten)]
+    credentials: Credentials,
+    /// Deployment manifest to read contract addresses from instead of compiling.
+    #[arg(long, global = true)]
+    manifest: Option<PathBuf>,
     #[command(subcommand)]
     command: Command,
 }
@@ -27,12 +36,15 @@ struct Cli {
 struct Mint {
     /// The account to mint from.
     account: String,
-    /// The amount of token to mint.
-    amount: Word,
+    /// The amount of token to mint, such as `1.5` or `1.5 ALC`.
+    amount: String,
     /// The name of the token.
     token_name: String,
     /// The symbol of the token.
     token_symbol: String,
+    /// The number of decimals of the token.
+    #[arg(long, default_value_t = 18)]
+    decimals: Word,
     /// The address of the node to connect to.
     node_api: String,
     /// The address of the builder to connect to.
@@ -41,6 +53,30 @@ struct Mint {
     pint_directory: PathBuf,
 }
 
+#[derive(Args)]
+struct New {
+    /// The name of the token.
+    #[arg(long)]
+    name: String,
+    /// The symbol of the token.
+    #[arg(long)]
+    symbol: String,
+    /// The account allowed to mint the token.
+    #[arg(long)]
+    minter: String,
+    /// The address of the node to connect to.
+    node_api: String,
+    /// The address of the builder to connect to.
+    builder_api: String,
+    /// The directory of the pint token contract to copy.
+    pint_directory: PathBuf,
+    /// Where to create the new token's pint project.
+    /// Defaults to a directory named after the symbol next to `pint_directory`.
+    /// A project previously created here for the same token is reused.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
 #[derive(Args)]
 struct Transfer {
     /// The account to transfer from.
@@ -48,22 +84,88 @@ struct Transfer {
     /// The account to transfer to.
     /// Hashed key as hex.
     to_account: String,
-    /// The amount of token to mint.
-    amount: Word,
+    /// The amount of token to transfer, such as `1.5` or `1.5 ALC`.
+    amount: String,
     /// The address of the node to connect to.
     node_api: String,
     /// The address of the builder to connect to.
     builder_api: String,
     /// The directory of the pint token contract.
     pint_directory: PathBuf,
+    /// Which fields the signature covers: all, key, key-to or key-amount.
+    #[arg(long, default_value = "all")]
+    sign_mode: SignMode,
+}
+
+#[derive(Args)]
+struct TransferMany {
+    /// The account to transfer from.
+    from_account: String,
+    /// The address of the node to connect to.
+    node_api: String,
+    /// The address of the builder to connect to.
+    builder_api: String,
+    /// The directory of the pint token contract.
+    pint_directory: PathBuf,
+    /// The transfers to queue, in order.
+    /// Each is the hashed key as hex and the amount, such as `1.5`, separated by a colon.
+    #[arg(required = true, value_name = "TO:AMOUNT")]
+    transfers: Vec<String>,
+    /// Which fields the signature covers: all, key, key-to or key-amount.
+    #[arg(long, default_value = "all")]
+    sign_mode: SignMode,
+}
+
+#[derive(Args)]
+struct Airdrop {
+    /// The CSV of recipients.
+    /// Each line is a hashed key as hex and an amount, such as `1.5`, separated by a comma.
+    csv: PathBuf,
+    /// The account to send from.
+    from_account: String,
+    /// The address of the node to connect to.
+    node_api: String,
+    /// The address of the builder to connect to.
+    builder_api: String,
+    /// The directory of the pint token contract.
+    pint_directory: PathBuf,
+    /// Where to record progress.
+    /// An existing report for the same CSV is resumed, skipping transfers already included.
+    #[arg(long, default_value = token::airdrop::DEFAULT_REPORT)]
+    report: PathBuf,
+    /// The most transfers awaiting inclusion at once.
+    #[arg(long, default_value_t = 16)]
+    max_pending: usize,
+    /// Seconds between checks for included transfers.
+    #[arg(long, default_value_t = 2)]
+    poll_interval: u64,
+    /// Seconds to wait for transfers to be included before marking them failed.
+    /// A timed out transfer is recorded as included if it's seen in a block when resuming.
+    #[arg(long, default_value_t = 300)]
+    timeout: u64,
+    /// Which fields the signature covers: all, key, key-to or key-amount.
+    #[arg(long, default_value = "all")]
+    sign_mode: SignMode,
 }
 
 #[derive(Args)]
 struct Burn {
     /// The account to burn from.
     account: String,
-    /// The amount of token to mint.
-    amount: Word,
+    /// The amount of token to burn, such as `1.5` or `1.5 ALC`.
+    amount: String,
+    /// The address of the node to connect to.
+    node_api: String,
+    /// The address of the builder to connect to.
+    builder_api: String,
+    /// The directory of the pint token contract.
+    pint_directory: PathBuf,
+}
+
+#[derive(Args)]
+struct Cancel {
+    /// The account to cancel pending transfers and burns for.
+    account: String,
     /// The address of the node to connect to.
     node_api: String,
     /// The address of the builder to connect to.
@@ -93,50 +195,265 @@ struct ExternalBalance {
     pint_directory: PathBuf,
 }
 
+#[derive(Args)]
+struct Info {
+    /// The address of the node to connect to.
+    node_api: String,
+    /// The directory of the pint token contract.
+    pint_directory: PathBuf,
+    /// A name to match against the stored name hash.
+    /// Can be given more than once.
+    #[arg(long = "name")]
+    names: Vec<String>,
+    /// A symbol to match against the stored symbol hash.
+    /// Can be given more than once.
+    #[arg(long = "symbol")]
+    symbols: Vec<String>,
+    /// The index database the supply is read from, which is brought up to date first.
+    #[arg(long, default_value = token::indexer::DEFAULT_DB)]
+    db: PathBuf,
+}
+
+#[derive(Args)]
+struct History {
+    /// The account hashed public key to get the history of.
+    /// Encoded as hex.
+    account: String,
+    /// The address of the node to connect to.
+    node_api: String,
+    /// The directory of the pint token contract.
+    pint_directory: PathBuf,
+    /// The index database, which is brought up to date before reading.
+    #[arg(long, default_value = token::indexer::DEFAULT_DB)]
+    db: PathBuf,
+}
+
+#[derive(Args)]
+struct Holders {
+    /// The address of the node to connect to.
+    node_api: String,
+    /// The directory of the pint token contract.
+    pint_directory: PathBuf,
+    /// The index database, which is brought up to date before reading.
+    #[arg(long, default_value = token::indexer::DEFAULT_DB)]
+    db: PathBuf,
+}
+
+#[derive(Args)]
+struct Prepare {
+    #[command(subcommand)]
+    operation: PrepareOperation,
+}
+
+#[derive(Subcommand)]
+enum PrepareOperation {
+    Mint {
+        /// The account to mint to.
+        /// Hashed key as hex.
+        account: String,
+        /// The amount of token to mint, such as `1.5` or `1.5 ALC`.
+        amount: String,
+        /// The name of the token.
+        token_name: String,
+        /// The symbol of the token.
+        token_symbol: String,
+        /// The number of decimals of the token.
+        #[arg(long, default_value_t = 18)]
+        decimals: Word,
+        #[command(flatten)]
+        shared: PrepareShared,
+    },
+    Burn {
+        /// The account to burn from.
+        /// Hashed key as hex.
+        account: String,
+        /// The amount of token to burn, such as `1.5` or `1.5 ALC`.
+        amount: String,
+        #[command(flatten)]
+        shared: PrepareShared,
+    },
+    Transfer {
+        /// The account to transfer from.
+        /// Hashed key as hex.
+        from_account: String,
+        /// The account to transfer to.
+        /// Hashed key as hex.
+        to_account: String,
+        /// The amount of token to transfer, such as `1.5` or `1.5 ALC`.
+        amount: String,
+        /// Which fields the signature covers: all, key, key-to or key-amount.
+        #[arg(long, default_value = "all")]
+        sign_mode: SignMode,
+        #[command(flatten)]
+        shared: PrepareShared,
+    },
+}
+
+#[derive(Args, Clone)]
+struct PrepareShared {
+    /// The address of the node to connect to.
+    node_api: String,
+    /// The directory of the pint token contract.
+    pint_directory: PathBuf,
+    /// Where to write the prepared operation.
+    #[arg(long, default_value = "prepared.json")]
+    out: PathBuf,
+}
+
+#[derive(Args)]
+struct Sign {
+    /// The prepared operation to sign.
+    prepared: PathBuf,
+    /// The account to sign with.
+    account: String,
+    /// Where to write the signed operation.
+    #[arg(long, default_value = "signed.json")]
+    out: PathBuf,
+}
+
+#[derive(Args)]
+struct Submit {
+    /// The signed operation to submit.
+    signed: PathBuf,
+    /// The address of the node to connect to.
+    node_api: String,
+    /// The address of the builder to connect to.
+    builder_api: String,
+    /// The directory of the pint token contract.
+    pint_directory: PathBuf,
+}
+
+#[derive(Args)]
+struct PredicateAccount {
+    /// The address of the contract containing the predicate.
+    /// Encoded as hex.
+    contract: String,
+    /// The address of the predicate that controls the account.
+    /// Encoded as hex.
+    predicate: String,
+}
+
 #[derive(Subcommand)]
 enum Command {
+    /// Create and deploy a new token contract.
+    New(New),
     Mint(Mint),
     Burn(Burn),
+    /// Transfer tokens to another account.
+    ///
+    /// The transfer doesn't require any other predicate to be solved alongside it.
+    /// Such a requirement wouldn't be covered by the signature, so whoever submits
+    /// the transfer could add or remove it.
     Transfer(Transfer),
+    /// Queue several transfers from one account without waiting for blocks.
+    TransferMany(TransferMany),
+    /// Transfer to every recipient in a CSV, recording progress in a resumable report.
+    Airdrop(Airdrop),
+    /// Bump the account's nonce so that pending transfers and burns fail.
+    Cancel(Cancel),
     Balance(Balance),
     ExternalBalance(ExternalBalance),
+    /// Print the token's name, symbol, decimals and total supply.
+    Info(Info),
+    /// Print the mints, transfers, burns and cancels involving an account.
+    History(History),
+    /// Print the accounts holding the token, largest balance first.
+    ///
+    /// Balances are replayed from the token's events, so balances set in the
+    /// node's genesis state aren't included.
+    Holders(Holders),
+    /// Read the state for a mint, burn or transfer and write it to be signed offline.
+    Prepare(Prepare),
+    /// Sign a prepared operation. This doesn't connect to a node.
+    Sign(Sign),
+    /// Submit a signed operation, provided the state it was prepared against is unchanged.
+    Submit(Submit),
+    /// Print the hashed key of the account controlled by a predicate.
+    PredicateAccount(PredicateAccount),
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let args = Cli::parse();
-    if let Err(err) = run(args).await {
-        eprintln!("Command failed because: {}", err);
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Command failed because: {}", err);
+            ExitCode::FAILURE
+        }
     }
 }
 
-async fn run(cli: Cli) -> anyhow::Result<()> {
-    let Cli { wallet, command } = cli;
-    let wallet = match &command {
-        Command::ExternalBalance(_) => None,
-        _ => {
-            let pass = rpassword::prompt_password("Enter password to unlock wallet: ")?;
-            let wallet = match wallet {
-                Some(path) => essential_wallet::Wallet::new(&pass, path)?,
-                None => essential_wallet::Wallet::with_default_path(&pass)?,
-            };
-            Some(wallet)
+impl Command {
+    /// The account the command signs or reads keys with, if any.
+    fn account(&self) -> Option<&str> {
+        match self {
+            Command::New(args) => Some(&args.minter),
+            Command::Mint(args) => Some(&args.account),
+            Command::Burn(args) => Some(&args.account),
+            Command::Transfer(args) => Some(&args.from_account),
+            Command::TransferMany(args) => Some(&args.from_account),
+            Command::Airdrop(args) => Some(&args.from_account),
+            Command::Cancel(args) => Some(&args.account),
+            Command::Balance(args) => Some(&args.account),
+            Command::Sign(args) => Some(&args.account),
+            Command::ExternalBalance(_)
+            | Command::Info(_)
+            | Command::History(_)
+            | Command::Holders(_)
+            | Command::Prepare(_)
+            | Command::Submit(_)
+            | Command::PredicateAccount(_) => None,
         }
-    };
+    }
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    let Cli {
+        credentials,
+        manifest,
+        command,
+    } = cli;
+    let manifest = manifest.as_deref();
+    let signer = command
+        .account()
+        .map(|account| credentials.open_signer(account))
+        .transpose()?;
     match command {
+        Command::New(args) => {
+            println!(
+                "creating token: {}, symbol: {}, minter: {}",
+                args.name, args.symbol, args.minter
+            );
+            let signer = signer.unwrap();
+            let manifest = manifest.unwrap_or(Path::new(MANIFEST_FILE));
+            let (dir, (outcome, deployment)) = new_token(signer, args, manifest).await?;
+            println!("created token project at: {}", dir.display());
+            match outcome {
+                DeployOutcome::Submitted(_) => {
+                    println!("deployed token contract: {}", deployment.contract)
+                }
+                DeployOutcome::AlreadyDeployed => {
+                    println!(
+                        "token contract was already deployed: {}",
+                        deployment.contract
+                    )
+                }
+            }
+        }
         Command::Mint(args) => {
             println!(
                 "minting {} for account: {}, token name: {}, token symbol: {}",
                 args.amount, args.account, args.token_name, args.token_symbol
             );
-            let wallet = wallet.unwrap();
-            let addr = mint(wallet, args).await?;
+            let signer = signer.unwrap();
+            let addr = mint(signer, args, manifest).await?;
             println!("sent mint solution: {}", addr);
         }
         Command::Burn(args) => {
             println!("burning {} for account: {}", args.amount, args.account);
-            let wallet = wallet.unwrap();
-            let addr = burn(wallet, args).await?;
+            let signer = signer.unwrap();
+            let addr = burn(signer, args, manifest).await?;
             println!("sent burn solution: {}", addr);
         }
         Command::Transfer(args) => {
@@ -144,10 +461,50 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
                 "transferring {} from account: {} to account: {}",
                 args.amount, args.from_account, args.to_account
             );
-            let wallet = wallet.unwrap();
-            let addr = transfer(wallet, args).await?;
+            let signer = signer.unwrap();
+            let addr = transfer(signer, args, manifest).await?;
             println!("sent transfer solution: {}", addr);
         }
+        Command::TransferMany(args) => {
+            println!(
+                "queueing {} transfers from account: {}",
+                args.transfers.len(),
+                args.from_account
+            );
+            let signer = signer.unwrap();
+            for addr in transfer_many(signer, args, manifest).await? {
+                println!("sent transfer solution: {}", addr);
+            }
+        }
+        Command::Airdrop(args) => {
+            println!(
+                "airdropping from account: {} to recipients in: {}",
+                args.from_account,
+                args.csv.display()
+            );
+            let signer = signer.unwrap();
+            let (report, path) = airdrop(signer, args, manifest).await?;
+            let (included, in_flight, unsent) = report.counts();
+            println!(
+                "{} included, {} awaiting inclusion, {} not sent",
+                included, in_flight, unsent
+            );
+            for entry in &report.entries {
+                if let token::airdrop::Status::Failed { reason, .. } = &entry.status {
+                    println!("line {} failed: {}", entry.line, reason);
+                }
+            }
+            println!("wrote report to: {}", path.display());
+            if unsent > 0 {
+                bail!("Not every transfer was included, run again to resume");
+            }
+        }
+        Command::Cancel(args) => {
+            println!("cancelling pending solutions for account: {}", args.account);
+            let signer = signer.unwrap();
+            let addr = cancel(signer, args, manifest).await?;
+            println!("sent cancel solution: {}", addr);
+        }
         Command::Balance(args) => {
             let Balance {
                 account,
@@ -155,9 +512,8 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
                 pint_directory,
             } = args;
             println!("getting balance for account: {}", account);
-            let mut wallet = wallet.unwrap();
-            let hashed_key = hash_key(&mut wallet, &account);
-            let balance = get_balance(hashed_key, node_api, pint_directory).await?;
+            let hashed_key = signer.unwrap().hashed_key()?;
+            let balance = get_balance(hashed_key, node_api, pint_directory, manifest).await?;
             println!("balance is {}", balance);
         }
         Command::ExternalBalance(args) => {
@@ -167,40 +523,131 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
                 pint_directory,
             } = args;
             println!("getting balance for account: {}", account);
-            let hashed_key = word_4_from_u8_32(
-                hex::decode(account)?
-                    .try_into()
-                    .map_err(|_| anyhow::anyhow!("To key too large"))?,
-            );
-            let balance = get_balance(hashed_key, node_api, pint_directory).await?;
+            let hashed_key = token::parse_hashed_key(&account)?;
+            let balance = get_balance(hashed_key, node_api, pint_directory, manifest).await?;
             println!("balance is {}", balance);
         }
+        Command::Info(args) => {
+            println!("getting token info");
+            info(args, manifest).await?;
+        }
+        Command::History(args) => {
+            println!("getting history for account: {}", args.account);
+            history(args, manifest).await?;
+        }
+        Command::Holders(args) => {
+            println!("getting token holders");
+            println!("note: balances from genesis state are not included");
+            holders(args, manifest).await?;
+        }
+        Command::Prepare(args) => {
+            let out = prepare(args, manifest).await?;
+            println!("wrote prepared operation to: {}", out.display());
+        }
+        Command::Sign(args) => {
+            println!(
+                "signing {} with account: {}",
+                args.prepared.display(),
+                args.account
+            );
+            let signer = signer.unwrap();
+            let out = sign(signer, args).await?;
+            println!("wrote signed operation to: {}", out.display());
+        }
+        Command::Submit(args) => {
+            println!("submitting {}", args.signed.display());
+            let addr = submit(args, manifest).await?;
+            println!("sent solution: {}", addr);
+        }
+        Command::PredicateAccount(args) => {
+            let PredicateAccount {
+                contract,
+                predicate,
+            } = args;
+            let predicate = PredicateAddress {
+                contract: contract.parse()?,
+                predicate: predicate.parse()?,
+            };
+            let hashed_key = token::predicate_account(&predicate);
+            println!(
+                "predicate account is {}",
+                hex::encode_upper(u8_32_from_word_4(hashed_key))
+            );
+        }
     }
     Ok(())
 }
 
-/// Hashes the public key for an account.
-fn hash_key(wallet: &mut Wallet, account_name: &str) -> [Word; 4] {
-    let public_key = wallet.get_public_key(account_name).unwrap();
-    let essential_signer::PublicKey::Secp256k1(public_key) = public_key else {
-        panic!("Invalid public key")
+async fn new_token(
+    mut signer: AccountSigner,
+    args: New,
+    manifest: &Path,
+) -> anyhow::Result<(PathBuf, (DeployOutcome, Deployment))> {
+    let New {
+        name,
+        symbol,
+        minter: _,
+        node_api,
+        builder_api,
+        pint_directory,
+        output,
+    } = args;
+    let package = package_for_symbol(&symbol)?;
+    let dest = match output {
+        Some(output) => output,
+        None => pint_directory
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(&package),
     };
-    let encoded = essential_sign::encode::public_key(&public_key);
-    word_4_from_u8_32(essential_hash::hash_words(&encoded))
+    let mint_key = signer.hashed_key()?;
+    let config = token::config::TokenConfig {
+        name,
+        symbol,
+        mint_key,
+    };
+    if !token::config::new_project(&pint_directory, &dest, &package, &config).await? {
+        println!("resuming existing token project at: {}", dest.display());
+    }
+
+    let node = EssentialNodeClient::new(node_api)?;
+    let builder = EssentialBuilderClient::new(builder_api)?;
+    let deployment = deploy_project(&node, &builder, dest.clone(), manifest).await?;
+    Ok((dest, deployment))
 }
 
-async fn mint(mut wallet: Wallet, args: Mint) -> anyhow::Result<ContentAddress> {
+/// The pint package name for a token, which is also its key in the deployments manifest.
+fn package_for_symbol(symbol: &str) -> anyhow::Result<String> {
+    if symbol.is_empty() || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+        bail!("Symbol must be ascii letters and digits, got: {}", symbol);
+    }
+    Ok(format!("token_{}", symbol.to_lowercase()))
+}
+
+async fn mint(
+    mut signer: AccountSigner,
+    args: Mint,
+    manifest: Option<&Path>,
+) -> anyhow::Result<ContentAddress> {
     let Mint {
-        account,
+        account: _,
         amount,
         token_name,
         token_symbol,
+        decimals,
         node_api,
         builder_api,
         pint_directory,
     } = args;
-    let address = compile_address(pint_directory).await?;
-    let hashed_key = hash_key(&mut wallet, &account);
+    let (amount, symbol) = token::amount::split_symbol(&amount);
+    if let Some(symbol) = symbol {
+        if symbol != token_symbol {
+            bail!("{} is not this token's symbol", symbol);
+        }
+    }
+    let amount = token::amount::parse(amount, decimals)?;
+    let address = ContractAddresses::resolve(pint_directory, manifest).await?;
+    let hashed_key = signer.hashed_key()?;
     let node = EssentialNodeClient::new(node_api)?;
     let builder = EssentialBuilderClient::new(builder_api)?;
 
@@ -211,21 +658,18 @@ async fn mint(mut wallet: Wallet, args: Mint) -> anyhow::Result<ContentAddress>
     let init = token::mint::Init {
         hashed_key,
         amount,
-        decimals: 18,
-        nonce: token::Query(nonce),
+        decimals,
+        nonce: token::Query::new(nonce),
     };
     let to_sign = token::mint::data_to_sign(init)?;
-    let sig = wallet.sign_words(&to_sign.to_words(), &account)?;
-    let Signature::Secp256k1(sig) = sig else {
-        bail!("Invalid signature")
-    };
+    let sig = signer.sign(&to_sign.to_words())?;
     let balance_key = token::balance_key(hashed_key);
     let balance = node
         .query_state(address.contract.clone(), balance_key)
         .await?;
     let build_solution = token::mint::BuildSolution {
         new_nonce: to_sign.new_nonce,
-        current_balance: Query(balance),
+        current_balance: Query::new(balance),
         hashed_key,
         amount: to_sign.amount,
         decimals: to_sign.decimals,
@@ -233,23 +677,30 @@ async fn mint(mut wallet: Wallet, args: Mint) -> anyhow::Result<ContentAddress>
         token_name,
         token_symbol,
     };
-    let solution = token::mint::build_solution(build_solution)?;
+    let solution = token::retarget(token::mint::build_solution(build_solution)?, &address)?;
     let ca = builder.submit_solution(&solution).await?;
     Ok(ca)
 }
 
-async fn burn(mut wallet: Wallet, args: Burn) -> anyhow::Result<ContentAddress> {
+async fn burn(
+    mut signer: AccountSigner,
+    args: Burn,
+    manifest: Option<&Path>,
+) -> anyhow::Result<ContentAddress> {
     let Burn {
-        account,
+        account: _,
         amount,
         node_api,
         builder_api,
         pint_directory,
     } = args;
-    let address = compile_address(pint_directory).await?;
-    let hashed_key = hash_key(&mut wallet, &account);
+    let address = ContractAddresses::resolve(pint_directory, manifest).await?;
+    let hashed_key = signer.hashed_key()?;
     let node = EssentialNodeClient::new(node_api)?;
     let builder = EssentialBuilderClient::new(builder_api)?;
+    let amount = minted_metadata(&node, &address.contract)
+        .await?
+        .parse_amount(&amount)?;
 
     let nonce_key = token::nonce_key(hashed_key);
     let nonce = node
@@ -258,47 +709,84 @@ async fn burn(mut wallet: Wallet, args: Burn) -> anyhow::Result<ContentAddress>
     let init = token::burn::Init {
         hashed_key,
         amount,
-        nonce: token::Query(nonce),
+        nonce: token::Query::new(nonce),
     };
     let to_sign = token::burn::data_to_sign(init)?;
-    let sig = wallet.sign_words(&to_sign.to_words(), &account)?;
-    let Signature::Secp256k1(sig) = sig else {
-        bail!("Invalid signature")
-    };
+    let sig = signer.sign(&to_sign.to_words())?;
     let balance_key = token::balance_key(hashed_key);
     let balance = node
         .query_state(address.contract.clone(), balance_key)
         .await?;
     let build_solution = token::burn::BuildSolution {
         new_nonce: to_sign.new_nonce,
-        current_balance: Query(balance),
+        current_balance: Query::new(balance),
         hashed_key,
         amount: to_sign.amount,
         signature: sig,
     };
-    let solution = token::burn::build_solution(build_solution)?;
+    let solution = token::retarget(token::burn::build_solution(build_solution)?, &address)?;
     let ca = builder.submit_solution(&solution).await?;
     Ok(ca)
 }
 
-async fn transfer(mut wallet: Wallet, args: Transfer) -> anyhow::Result<ContentAddress> {
+async fn cancel(
+    mut signer: AccountSigner,
+    args: Cancel,
+    manifest: Option<&Path>,
+) -> anyhow::Result<ContentAddress> {
+    let Cancel {
+        account: _,
+        node_api,
+        builder_api,
+        pint_directory,
+    } = args;
+    let address = ContractAddresses::resolve(pint_directory, manifest).await?;
+    let hashed_key = signer.hashed_key()?;
+    let node = EssentialNodeClient::new(node_api)?;
+    let builder = EssentialBuilderClient::new(builder_api)?;
+
+    let nonce_key = token::nonce_key(hashed_key);
+    let nonce = node
+        .query_state(address.contract.clone(), nonce_key)
+        .await?;
+    let init = token::cancel::Init {
+        hashed_key,
+        nonce: Query::new(nonce),
+    };
+    let to_sign = token::cancel::data_to_sign(init)?;
+    let sig = signer.sign(&to_sign.to_words())?;
+    let build_solution = token::cancel::BuildSolution {
+        new_nonce: to_sign.new_nonce,
+        hashed_key,
+        signature: sig,
+    };
+    let solution = token::retarget(token::cancel::build_solution(build_solution)?, &address)?;
+    let ca = builder.submit_solution(&solution).await?;
+    Ok(ca)
+}
+
+async fn transfer(
+    mut signer: AccountSigner,
+    args: Transfer,
+    manifest: Option<&Path>,
+) -> anyhow::Result<ContentAddress> {
     let Transfer {
         amount,
         node_api,
         builder_api,
         pint_directory,
-        from_account,
+        from_account: _,
         to_account,
+        sign_mode,
     } = args;
-    let address = compile_address(pint_directory).await?;
-    let hashed_from_key = hash_key(&mut wallet, &from_account);
-    let hashed_to_key = word_4_from_u8_32(
-        hex::decode(to_account)?
-            .try_into()
-            .map_err(|_| anyhow::anyhow!("To key too large"))?,
-    );
+    let address = ContractAddresses::resolve(pint_directory, manifest).await?;
+    let hashed_from_key = signer.hashed_key()?;
+    let hashed_to_key = token::parse_hashed_key(&to_account)?;
     let node = EssentialNodeClient::new(node_api)?;
     let builder = EssentialBuilderClient::new(builder_api)?;
+    let amount = minted_metadata(&node, &address.contract)
+        .await?
+        .parse_amount(&amount)?;
 
     let nonce_key = token::nonce_key(hashed_from_key);
     let nonce = node
@@ -306,15 +794,13 @@ async fn transfer(mut wallet: Wallet, args: Transfer) -> anyhow::Result<ContentA
         .await?;
     let init = token::transfer::Init {
         amount,
-        nonce: token::Query(nonce),
+        nonce: token::Query::new(nonce),
         hashed_from_key,
         hashed_to_key,
+        mode: sign_mode,
     };
     let to_sign = token::transfer::data_to_sign(init)?;
-    let sig = wallet.sign_words(&to_sign.to_words(), &from_account)?;
-    let Signature::Secp256k1(sig) = sig else {
-        bail!("Invalid signature")
-    };
+    let sig = signer.sign(&to_sign.to_words())?;
     let balance_key = token::balance_key(hashed_from_key);
     let from_balance = node
         .query_state(address.contract.clone(), balance_key)
@@ -325,41 +811,525 @@ async fn transfer(mut wallet: Wallet, args: Transfer) -> anyhow::Result<ContentA
         .await?;
     let build_solution = token::transfer::BuildSolution {
         new_nonce: to_sign.new_nonce,
-        current_from_balance: Query(from_balance),
-        current_to_balance: Query(to_balance),
+        current_from_balance: Query::new(from_balance),
+        current_to_balance: Query::new(to_balance),
         hashed_from_key,
         hashed_to_key,
         amount: to_sign.amount,
         signature: sig,
+        mode: sign_mode,
+        extra: None,
     };
-    let solution = token::transfer::build_solution(build_solution)?;
+    let solution = token::retarget(token::transfer::build_solution(build_solution)?, &address)?;
     let ca = builder.submit_solution(&solution).await?;
     Ok(ca)
 }
 
+async fn transfer_many(
+    mut signer: AccountSigner,
+    args: TransferMany,
+    manifest: Option<&Path>,
+) -> anyhow::Result<Vec<ContentAddress>> {
+    let TransferMany {
+        from_account: _,
+        node_api,
+        builder_api,
+        pint_directory,
+        transfers,
+        sign_mode,
+    } = args;
+    let address = ContractAddresses::resolve(pint_directory, manifest).await?;
+    let contract = address.contract.clone();
+    let hashed_from_key = signer.hashed_key()?;
+    let node = EssentialNodeClient::new(node_api)?;
+    let builder = EssentialBuilderClient::new(builder_api)?;
+    let metadata = minted_metadata(&node, &contract).await?;
+    let transfers = transfers
+        .iter()
+        .map(|t| {
+            let (to, amount) = t
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Expected TO:AMOUNT, got: {}", t))?;
+            Ok((token::parse_hashed_key(to)?, metadata.parse_amount(amount)?))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let nonce_key = token::nonce_key(hashed_from_key);
+    let nonce = Query::new(
+        node.query_state(contract.clone(), nonce_key.clone())
+            .await?,
+    )
+    .get()?;
+    let mut balances = HashMap::new();
+    for key in std::iter::once(hashed_from_key).chain(transfers.iter().map(|(to, _)| *to)) {
+        if let Entry::Vacant(entry) = balances.entry(key) {
+            let balance = node
+                .query_state(contract.clone(), token::balance_key(key))
+                .await?;
+            entry.insert(Query::new(balance).get()?);
+        }
+    }
+    let nonces = NonceManager::new();
+    let queued = token::transfer::QueuedNonces {
+        manager: &nonces,
+        contract: &contract,
+        on_chain: nonce,
+    };
+    let signed = token::transfer::sign_queued(
+        hashed_from_key,
+        queued,
+        balances,
+        &transfers,
+        sign_mode,
+        &mut signer,
+    )?;
+
+    // Later transfers can't be included once one is missing, so stop at the
+    // first failure and release its nonce and those after it.
+    let mut submitted = vec![];
+    for (nonce, solution) in signed {
+        let result = async {
+            let solution = token::retarget(solution, &address)?;
+            builder.submit_solution(&solution).await
+        }
+        .await;
+        match result {
+            Ok(ca) => submitted.push(ca),
+            Err(e) => {
+                nonces.rollback(&contract, &nonce_key, nonce);
+                bail!(
+                    "Transfer {} failed after {} were submitted: {}",
+                    submitted.len() + 1,
+                    submitted.len(),
+                    e
+                )
+            }
+        }
+    }
+    Ok(submitted)
+}
+
+async fn airdrop(
+    mut signer: AccountSigner,
+    args: Airdrop,
+    manifest: Option<&Path>,
+) -> anyhow::Result<(token::airdrop::Report, PathBuf)> {
+    use token::airdrop::Report;
+
+    let Airdrop {
+        csv,
+        from_account: _,
+        node_api,
+        builder_api,
+        pint_directory,
+        report: path,
+        max_pending,
+        poll_interval,
+        timeout,
+        sign_mode,
+    } = args;
+    anyhow::ensure!(max_pending > 0, "--max-pending must be at least 1");
+    let address = ContractAddresses::resolve(pint_directory, manifest).await?;
+    let contract = address.contract.clone();
+    let hashed_from_key = signer.hashed_key()?;
+    let node = EssentialNodeClient::new(node_api)?;
+    let builder = EssentialBuilderClient::new(builder_api)?;
+    let metadata = minted_metadata(&node, &contract).await?;
+    let rows = token::airdrop::parse_csv(&tokio::fs::read_to_string(&csv).await?, |amount| {
+        metadata.parse_amount(amount)
+    })?;
+
+    let mut report = if path.exists() {
+        let report: Report = serde_json::from_slice(&tokio::fs::read(&path).await?)?;
+        report.ensure_matches(&contract, hashed_from_key, &rows)?;
+        println!("resuming from report: {}", path.display());
+        report
+    } else {
+        Report::new(
+            contract.clone(),
+            hashed_from_key,
+            rows,
+            next_block_number(&node).await?,
+        )
+    };
+    let nonces = NonceManager::new();
+    let sender = token::airdrop::AirdropSender {
+        node: &node,
+        builder: &builder,
+        addresses: &address,
+        nonces: &nonces,
+        report_path: &path,
+        max_pending,
+        poll_interval: std::time::Duration::from_secs(poll_interval),
+        timeout: std::time::Duration::from_secs(timeout),
+    };
+
+    // Transfers left awaiting inclusion by an earlier run hold the next nonces,
+    // so they must settle before the rest are signed.
+    sender.send(&mut report, vec![]).await?;
+    if report.unsent().is_empty() {
+        return Ok((report, path));
+    }
+
+    let nonce_key = token::nonce_key(hashed_from_key);
+    let nonce = Query::new(node.query_state(contract.clone(), nonce_key).await?).get()?;
+    let mut balances = HashMap::new();
+    for key in report.balance_keys() {
+        let balance = node
+            .query_state(contract.clone(), token::balance_key(key))
+            .await?;
+        balances.insert(key, Query::new(balance).get()?);
+    }
+    let queued = token::transfer::QueuedNonces {
+        manager: &nonces,
+        contract: &contract,
+        on_chain: nonce,
+    };
+    let transfers = report.sign_unsent(queued, balances, sign_mode, &mut signer)?;
+    println!("signed {} transfers", transfers.len());
+    sender.send(&mut report, transfers).await?;
+    Ok((report, path))
+}
+
+/// The number of the block after the node's latest block.
+///
+/// The node's state can lag behind its blocks, in which case this is an
+/// earlier block, which is still safe to start looking for new blocks from.
+async fn next_block_number(node: &EssentialNodeClient) -> anyhow::Result<Word> {
+    Ok(node.latest_block_number().await?.map_or(0, |n| n + 1))
+}
+
+async fn info(args: Info, manifest: Option<&Path>) -> anyhow::Result<()> {
+    let Info {
+        node_api,
+        pint_directory,
+        names,
+        symbols,
+        db,
+    } = args;
+    let address = ContractAddresses::resolve(pint_directory, manifest).await?;
+    let contract = address.contract.clone();
+    let node = EssentialNodeClient::new(node_api)?;
+
+    let Some(metadata) = query_metadata(&node, &contract).await? else {
+        println!("token has not been minted");
+        return Ok(());
+    };
+    let names: Vec<_> = names.iter().map(String::as_str).collect();
+    let symbols: Vec<_> = symbols.iter().map(String::as_str).collect();
+    let unmatched =
+        |hash: [Word; 4]| format!("unknown ({})", hex::encode_upper(u8_32_from_word_4(hash)));
+    println!(
+        "name: {}",
+        metadata
+            .name(&names)
+            .map_or_else(|| unmatched(metadata.name_hash), str::to_string)
+    );
+    println!(
+        "symbol: {}",
+        metadata
+            .symbol(&symbols)
+            .map_or_else(|| unmatched(metadata.symbol_hash), str::to_string)
+    );
+    println!("decimals: {}", metadata.decimals);
+
+    // The supply isn't stored so it is summed by the index.
+    let supply = synced_index(&node, &address, &db).await?.total_supply()?;
+    println!("total supply: {}", format_amount(supply, Some(metadata))?);
+    Ok(())
+}
+
+async fn prepare(args: Prepare, manifest: Option<&Path>) -> anyhow::Result<PathBuf> {
+    let (hashed_key, shared) = match &args.operation {
+        PrepareOperation::Mint {
+            account, shared, ..
+        }
+        | PrepareOperation::Burn {
+            account, shared, ..
+        } => (token::parse_hashed_key(account)?, shared.clone()),
+        PrepareOperation::Transfer {
+            from_account,
+            shared,
+            ..
+        } => (token::parse_hashed_key(from_account)?, shared.clone()),
+    };
+    let address = ContractAddresses::resolve(shared.pint_directory.clone(), manifest).await?;
+    let contract = address.contract;
+    let node = EssentialNodeClient::new(shared.node_api.clone())?;
+
+    let nonce_key = token::nonce_key(hashed_key);
+    let nonce = node
+        .query_state(contract.clone(), nonce_key.clone())
+        .await?;
+    let operation = match args.operation {
+        PrepareOperation::Mint {
+            amount,
+            token_name,
+            token_symbol,
+            decimals,
+            ..
+        } => {
+            let (amount, symbol) = token::amount::split_symbol(&amount);
+            if let Some(symbol) = symbol {
+                if symbol != token_symbol {
+                    bail!("{} is not this token's symbol", symbol);
+                }
+            }
+            let to_sign = token::mint::data_to_sign(token::mint::Init {
+                hashed_key,
+                amount: token::amount::parse(amount, decimals)?,
+                decimals,
+                nonce: Query::new(nonce.clone()),
+            })?;
+            token::offline::Operation::Mint {
+                hashed_key,
+                amount: to_sign.amount,
+                decimals,
+                new_nonce: to_sign.new_nonce,
+                token_name,
+                token_symbol,
+            }
+        }
+        PrepareOperation::Burn { amount, .. } => {
+            let amount = minted_metadata(&node, &contract)
+                .await?
+                .parse_amount(&amount)?;
+            let to_sign = token::burn::data_to_sign(token::burn::Init {
+                hashed_key,
+                amount,
+                nonce: Query::new(nonce.clone()),
+            })?;
+            token::offline::Operation::Burn {
+                hashed_key,
+                amount,
+                new_nonce: to_sign.new_nonce,
+            }
+        }
+        PrepareOperation::Transfer {
+            to_account,
+            amount,
+            sign_mode,
+            ..
+        } => {
+            let hashed_to_key = token::parse_hashed_key(&to_account)?;
+            let amount = minted_metadata(&node, &contract)
+                .await?
+                .parse_amount(&amount)?;
+            let to_sign = token::transfer::data_to_sign(token::transfer::Init {
+                hashed_from_key: hashed_key,
+                hashed_to_key,
+                amount,
+                nonce: Query::new(nonce.clone()),
+                mode: sign_mode,
+            })?;
+            token::offline::Operation::Transfer {
+                hashed_from_key: hashed_key,
+                hashed_to_key,
+                amount,
+                new_nonce: to_sign.new_nonce,
+                mode: sign_mode,
+            }
+        }
+    };
+
+    // The nonce the operation was built from, then the balances it changes.
+    let mut state = vec![token::offline::StateRead {
+        key: nonce_key,
+        value: nonce,
+    }];
+    state.extend(read_state(&node, &contract, &operation.state_keys()[1..]).await?);
+    let prepared = token::offline::Prepared::new(contract, operation, state)?;
+    tokio::fs::write(&shared.out, serde_json::to_string_pretty(&prepared)?).await?;
+    Ok(shared.out)
+}
+
+async fn sign(mut signer: AccountSigner, args: Sign) -> anyhow::Result<PathBuf> {
+    let Sign {
+        prepared,
+        account: _,
+        out,
+    } = args;
+    let prepared: token::offline::Prepared =
+        serde_json::from_slice(&tokio::fs::read(prepared).await?)?;
+    let signed = prepared.sign(&mut signer)?;
+    tokio::fs::write(&out, serde_json::to_string_pretty(&signed)?).await?;
+    Ok(out)
+}
+
+async fn submit(args: Submit, manifest: Option<&Path>) -> anyhow::Result<ContentAddress> {
+    let Submit {
+        signed,
+        node_api,
+        builder_api,
+        pint_directory,
+    } = args;
+    let signed: token::offline::Signed = serde_json::from_slice(&tokio::fs::read(signed).await?)?;
+    let address = ContractAddresses::resolve(pint_directory, manifest).await?;
+    if signed.prepared.contract != address.contract {
+        bail!(
+            "Operation was prepared for contract {}, not {}",
+            signed.prepared.contract,
+            address.contract
+        );
+    }
+    let node = EssentialNodeClient::new(node_api)?;
+    let builder = EssentialBuilderClient::new(builder_api)?;
+
+    let current = read_state(
+        &node,
+        &address.contract,
+        &signed.prepared.operation.state_keys(),
+    )
+    .await?;
+    let solution = token::retarget(signed.build_solution(&current)?, &address)?;
+    let ca = builder.submit_solution(&solution).await?;
+    Ok(ca)
+}
+
+async fn read_state(
+    node: &EssentialNodeClient,
+    contract: &ContentAddress,
+    keys: &[Key],
+) -> anyhow::Result<Vec<token::offline::StateRead>> {
+    let mut state = vec![];
+    for key in keys {
+        let value = node.query_state(contract.clone(), key.clone()).await?;
+        state.push(token::offline::StateRead {
+            key: key.clone(),
+            value,
+        });
+    }
+    Ok(state)
+}
+
+/// Opens the index at `db` and brings it up to date with the node.
+async fn synced_index(
+    node: &EssentialNodeClient,
+    address: &ContractAddresses,
+    db: &Path,
+) -> anyhow::Result<token::indexer::Index> {
+    let predicates = token::indexer::Predicates::resolve(address)?;
+    let mut index = token::indexer::Index::open(db, predicates)?;
+    let indexed = index.sync(node).await?;
+    println!("indexed {} new blocks", indexed);
+    Ok(index)
+}
+
+async fn history(args: History, manifest: Option<&Path>) -> anyhow::Result<()> {
+    let History {
+        account,
+        node_api,
+        pint_directory,
+        db,
+    } = args;
+    let hashed_key = token::parse_hashed_key(&account)?;
+    let address = ContractAddresses::resolve(pint_directory, manifest).await?;
+    let node = EssentialNodeClient::new(node_api)?;
+    let index = synced_index(&node, &address, &db).await?;
+    let metadata = query_metadata(&node, &address.contract).await?;
+
+    let hex_key = |key: [Word; 4]| hex::encode_upper(u8_32_from_word_4(key));
+    let amount = |raw| format_amount(raw, metadata.clone());
+    for entry in index.history(hashed_key)? {
+        let description = match entry.event {
+            token::indexer::Event::Mint { amount: raw, .. } => format!("minted {}", amount(raw)?),
+            token::indexer::Event::Burn { amount: raw, .. } => format!("burnt {}", amount(raw)?),
+            token::indexer::Event::Transfer {
+                from,
+                to,
+                amount: raw,
+            } if from == hashed_key => {
+                format!("sent {} to {}", amount(raw)?, hex_key(to))
+            }
+            token::indexer::Event::Transfer {
+                from, amount: raw, ..
+            } => {
+                format!("received {} from {}", amount(raw)?, hex_key(from))
+            }
+            token::indexer::Event::Cancel { .. } => "cancelled pending solutions".to_string(),
+        };
+        println!(
+            "block {}: {} (solution {})",
+            entry.block, description, entry.solution
+        );
+    }
+    Ok(())
+}
+
+async fn holders(args: Holders, manifest: Option<&Path>) -> anyhow::Result<()> {
+    let Holders {
+        node_api,
+        pint_directory,
+        db,
+    } = args;
+    let address = ContractAddresses::resolve(pint_directory, manifest).await?;
+    let node = EssentialNodeClient::new(node_api)?;
+    let index = synced_index(&node, &address, &db).await?;
+    let metadata = query_metadata(&node, &address.contract).await?;
+    for (account, balance) in index.holders()? {
+        println!(
+            "{}: {}",
+            hex::encode_upper(u8_32_from_word_4(account)),
+            format_amount(balance, metadata.clone())?
+        );
+    }
+    Ok(())
+}
+
+/// Reads the balance, formatted as raw units and, once minted, as a decimal amount.
 async fn get_balance(
     hashed_key: [Word; 4],
     node_api: String,
     pint_directory: PathBuf,
-) -> anyhow::Result<Word> {
-    let address = compile_address(pint_directory).await?;
+    manifest: Option<&Path>,
+) -> anyhow::Result<String> {
+    let address = ContractAddresses::resolve(pint_directory, manifest).await?;
     let node = EssentialNodeClient::new(node_api)?;
 
     let balance_key = token::balance_key(hashed_key);
     let balance = node
         .query_state(address.contract.clone(), balance_key)
         .await?;
-    token::balance(Query(balance))
+    let balance = Query::new(balance).get()?;
+    format_amount(balance, query_metadata(&node, &address.contract).await?)
+}
+
+/// Formats raw units, along with the decimal amount if the token has been minted.
+fn format_amount(raw: Word, metadata: Option<token::info::Metadata>) -> anyhow::Result<String> {
+    match metadata {
+        Some(metadata) => Ok(format!("{} ({})", raw, metadata.format_amount(raw)?)),
+        None => Ok(raw.to_string()),
+    }
 }
 
-/// Compiles the contract and returns its address.
-async fn compile_address(pint_directory: PathBuf) -> Result<PredicateAddress, anyhow::Error> {
-    let counter = compile_pint_project(pint_directory).await?;
-    let contract_address = essential_hash::contract_addr::from_contract(&counter);
-    let predicate_address = essential_hash::content_addr(&counter.predicates[0]);
-    let predicate_address = PredicateAddress {
-        contract: contract_address,
-        predicate: predicate_address,
+/// Reads the token's metadata, or `None` if it hasn't been minted.
+async fn query_metadata(
+    node: &EssentialNodeClient,
+    contract: &ContentAddress,
+) -> anyhow::Result<Option<token::info::Metadata>> {
+    let state = token::info::Init {
+        token_name: token::info::QueryHash::new(
+            node.query_state(contract.clone(), token::token_name_key())
+                .await?,
+        ),
+        token_symbol: token::info::QueryHash::new(
+            node.query_state(contract.clone(), token::token_symbol_key())
+                .await?,
+        ),
+        decimals: Query::new(
+            node.query_state(contract.clone(), token::decimals_key())
+                .await?,
+        ),
     };
-    Ok(predicate_address)
+    token::info::metadata(state)
+}
+
+/// Reads the token's metadata, failing if it hasn't been minted.
+async fn minted_metadata(
+    node: &EssentialNodeClient,
+    contract: &ContentAddress,
+) -> anyhow::Result<token::info::Metadata> {
+    query_metadata(node, contract)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Token has not been minted"))
 }
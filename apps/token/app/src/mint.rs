@@ -1,15 +1,15 @@
 //! # Mint
 //! Contains functionality for minting new tokens in the token contract.
 
-use essential_app_utils::inputs::Encode;
+use essential_app_utils::inputs::{Encode, WriteDecVars};
 use essential_sign::secp256k1::ecdsa::RecoverableSignature;
 use essential_types::{
     convert::word_4_from_u8_32,
     solution::{Solution, SolutionData},
-    Word,
+    PredicateAddress, Word,
 };
 
-use crate::{balance, nonce, Query};
+use crate::Query;
 
 /// Represents the initial data required for minting tokens.
 pub struct Init {
@@ -24,6 +24,7 @@ pub struct Init {
 }
 
 /// Represents the data to be signed for a mint solution.
+#[derive(WriteDecVars)]
 pub struct ToSign {
     /// The hashed key of the account.
     pub hashed_key: [Word; 4],
@@ -55,18 +56,30 @@ pub struct BuildSolution {
     pub token_symbol: String,
 }
 
+/// Contains all necessary information to build a mint to an account owned by a predicate.
+///
+/// The contract only allows minting to its mint key, so the owner's account must be that key.
+pub struct BuildPredicateSolution {
+    /// The predicate that owns the account.
+    pub owner: PredicateAddress,
+    /// The current nonce of the account.
+    pub nonce: Query,
+    /// The current balance of the account.
+    pub current_balance: Query,
+    /// The amount of tokens to mint.
+    pub amount: Word,
+    /// The number of decimals of the token.
+    pub decimals: Word,
+    /// The name of the token.
+    pub token_name: String,
+    /// The symbol of the token.
+    pub token_symbol: String,
+}
+
 impl ToSign {
     /// Converts the ToSign struct to a vector of Words for signing.
     pub fn to_words(&self) -> Vec<Word> {
-        vec![
-            self.hashed_key[0],
-            self.hashed_key[1],
-            self.hashed_key[2],
-            self.hashed_key[3],
-            self.amount,
-            self.decimals,
-            self.new_nonce,
-        ]
+        essential_app_utils::inputs::to_words(self)
     }
 }
 
@@ -78,7 +91,7 @@ pub fn data_to_sign(account: Init) -> anyhow::Result<ToSign> {
         amount,
         decimals,
     } = account;
-    let new_nonce = increment_nonce(nonce(current_nonce)?);
+    let new_nonce = increment_nonce(current_nonce.get()?);
     Ok(ToSign {
         hashed_key,
         amount,
@@ -99,30 +112,98 @@ pub fn build_solution(build: BuildSolution) -> anyhow::Result<Solution> {
         token_name,
         token_symbol,
     } = build;
-    let balance = calculate_new_balance(balance(current_balance)?, amount)?;
+    let auth = super::token::MintAuth::Signed(signature.encode());
+    let solution = solution_data(
+        hashed_key,
+        Supply {
+            amount,
+            decimals,
+            token_name,
+            token_symbol,
+        },
+        new_nonce,
+        current_balance,
+        auth,
+    )?;
+    Ok(Solution {
+        data: vec![solution],
+    })
+}
+
+/// Builds a mint to the account owned by a predicate.
+///
+/// `authorize` is given the path of the mint in the solution and returns the
+/// owning predicate's solution data.
+pub fn build_predicate_solution(
+    build: BuildPredicateSolution,
+    authorize: impl FnOnce(Word) -> SolutionData,
+) -> anyhow::Result<Solution> {
+    let BuildPredicateSolution {
+        owner,
+        nonce,
+        current_balance,
+        amount,
+        decimals,
+        token_name,
+        token_symbol,
+    } = build;
+    let hashed_key = crate::predicate_account(&owner);
+    let new_nonce = increment_nonce(nonce.get()?);
+    let auth = super::token::MintAuth::Predicate((owner.encode(), crate::AUTHORIZER_PATH));
+    let solution = solution_data(
+        hashed_key,
+        Supply {
+            amount,
+            decimals,
+            token_name,
+            token_symbol,
+        },
+        new_nonce,
+        current_balance,
+        auth,
+    )?;
+    Ok(crate::with_authorizer(solution, authorize))
+}
+
+/// What is being minted.
+struct Supply {
+    amount: Word,
+    decimals: Word,
+    token_name: String,
+    token_symbol: String,
+}
+
+fn solution_data(
+    hashed_key: [Word; 4],
+    supply: Supply,
+    new_nonce: Word,
+    current_balance: Query,
+    auth: super::token::MintAuth,
+) -> anyhow::Result<SolutionData> {
+    let Supply {
+        amount,
+        decimals,
+        token_name,
+        token_symbol,
+    } = supply;
+    let balance = calculate_new_balance(current_balance.get()?, amount)?;
     let pub_vars = super::token::Mint::PubVars {
         key: hashed_key,
         amount,
         decimals,
     };
-    let signature = signature.encode();
-    let vars = super::token::Mint::Vars {
-        auth: super::token::MintAuth::Signed(signature),
-    };
+    let vars = super::token::Mint::Vars { auth };
     let mutations = super::token::storage::mutations()
         .balances(|map| map.entry(hashed_key, balance))
         .token_name(word_4_from_u8_32(essential_hash::hash(&token_name)))
         .token_symbol(word_4_from_u8_32(essential_hash::hash(&token_symbol)))
         .decimals(decimals)
         .nonce(|nonces| nonces.entry(hashed_key, new_nonce));
-    let solution = SolutionData {
+    Ok(SolutionData {
         predicate_to_solve: super::token::Mint::ADDRESS,
         decision_variables: vars.into(),
         transient_data: pub_vars.into(),
         state_mutations: mutations.into(),
-    };
-    Ok(Solution {
-        data: vec![solution],
     })
 }
 
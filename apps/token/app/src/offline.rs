@@ -0,0 +1,337 @@
+//! # Offline
+//! Contains functionality for signing token operations away from the node.
+//!
+//! An operation is prepared against the current state on a connected machine,
+//! signed on a machine that only has the wallet, then submitted from a
+//! connected machine once the state is confirmed to be unchanged.
+
+use anyhow::{bail, ensure};
+use essential_app_utils::signer::Signer;
+use essential_sign::secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use essential_types::{solution::Solution, ContentAddress, Key, Value, Word};
+use serde::{Deserialize, Serialize};
+
+use crate::{transfer::SignMode, Query};
+
+/// A token operation that can be signed offline.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Operation {
+    /// Mint tokens to the minting account.
+    Mint {
+        /// The hashed key of the account.
+        hashed_key: [Word; 4],
+        /// The amount of tokens to mint.
+        amount: Word,
+        /// The number of decimals of the token.
+        decimals: Word,
+        /// The new nonce of the account.
+        new_nonce: Word,
+        /// The name of the token.
+        token_name: String,
+        /// The symbol of the token.
+        token_symbol: String,
+    },
+    /// Burn tokens from an account.
+    Burn {
+        /// The hashed key of the account.
+        hashed_key: [Word; 4],
+        /// The amount of tokens to burn.
+        amount: Word,
+        /// The new nonce of the account.
+        new_nonce: Word,
+    },
+    /// Transfer tokens between accounts.
+    Transfer {
+        /// The hashed key of the sender.
+        hashed_from_key: [Word; 4],
+        /// The hashed key of the recipient.
+        hashed_to_key: [Word; 4],
+        /// The amount of tokens to transfer.
+        amount: Word,
+        /// The new nonce of the sender.
+        new_nonce: Word,
+        /// Which fields the signature covers.
+        mode: SignMode,
+    },
+}
+
+/// A state value as read when the operation was prepared.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateRead {
+    /// The key in the token contract.
+    pub key: Key,
+    /// The value, or `None` if nil.
+    pub value: Option<Value>,
+}
+
+/// An operation ready to be signed, along with the state it was prepared against.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Prepared {
+    /// The token contract the operation is for.
+    pub contract: ContentAddress,
+    /// The operation.
+    pub operation: Operation,
+    /// The words the signer signs.
+    pub to_sign: Vec<Word>,
+    /// The state the operation depends on, in the order of [`Operation::state_keys`].
+    pub state: Vec<StateRead>,
+}
+
+/// A prepared operation with its signature.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signed {
+    /// The prepared operation.
+    pub prepared: Prepared,
+    /// The compact signature followed by its recovery id, encoded as hex.
+    pub signature: String,
+}
+
+impl Operation {
+    /// The hashed key of the account that must sign the operation.
+    pub fn signer_key(&self) -> [Word; 4] {
+        match self {
+            Operation::Mint { hashed_key, .. } | Operation::Burn { hashed_key, .. } => *hashed_key,
+            Operation::Transfer {
+                hashed_from_key, ..
+            } => *hashed_from_key,
+        }
+    }
+
+    fn new_nonce(&self) -> Word {
+        match self {
+            Operation::Mint { new_nonce, .. }
+            | Operation::Burn { new_nonce, .. }
+            | Operation::Transfer { new_nonce, .. } => *new_nonce,
+        }
+    }
+
+    /// The state the operation depends on: the signer's nonce, then each balance it changes.
+    pub fn state_keys(&self) -> Vec<Key> {
+        let mut keys = vec![crate::nonce_key(self.signer_key())];
+        match self {
+            Operation::Mint { hashed_key, .. } | Operation::Burn { hashed_key, .. } => {
+                keys.push(crate::balance_key(*hashed_key));
+            }
+            Operation::Transfer {
+                hashed_from_key,
+                hashed_to_key,
+                ..
+            } => {
+                keys.push(crate::balance_key(*hashed_from_key));
+                keys.push(crate::balance_key(*hashed_to_key));
+            }
+        }
+        keys
+    }
+
+    /// The words to sign for the operation.
+    pub fn to_words(&self) -> Vec<Word> {
+        match self.clone() {
+            Operation::Mint {
+                hashed_key,
+                amount,
+                decimals,
+                new_nonce,
+                ..
+            } => crate::mint::ToSign {
+                hashed_key,
+                amount,
+                decimals,
+                new_nonce,
+            }
+            .to_words(),
+            Operation::Burn {
+                hashed_key,
+                amount,
+                new_nonce,
+            } => crate::burn::ToSign {
+                hashed_key,
+                amount,
+                new_nonce,
+            }
+            .to_words(),
+            Operation::Transfer {
+                hashed_from_key,
+                hashed_to_key,
+                amount,
+                new_nonce,
+                mode,
+            } => crate::transfer::ToSign {
+                hashed_from_key,
+                hashed_to_key,
+                amount,
+                new_nonce,
+                mode,
+            }
+            .to_words(),
+        }
+    }
+
+    /// Builds the operation's solution from the state it was prepared against.
+    fn build_solution(
+        self,
+        state: &[StateRead],
+        signature: RecoverableSignature,
+    ) -> anyhow::Result<Solution> {
+        let balance = |i: usize| Query::new(state[i].value.clone());
+        match self {
+            Operation::Mint {
+                hashed_key,
+                amount,
+                decimals,
+                new_nonce,
+                token_name,
+                token_symbol,
+            } => crate::mint::build_solution(crate::mint::BuildSolution {
+                new_nonce,
+                current_balance: balance(1),
+                hashed_key,
+                amount,
+                signature,
+                decimals,
+                token_name,
+                token_symbol,
+            }),
+            Operation::Burn {
+                hashed_key,
+                amount,
+                new_nonce,
+            } => crate::burn::build_solution(crate::burn::BuildSolution {
+                new_nonce,
+                current_balance: balance(1),
+                hashed_key,
+                amount,
+                signature,
+            }),
+            Operation::Transfer {
+                hashed_from_key,
+                hashed_to_key,
+                amount,
+                new_nonce,
+                mode,
+            } => crate::transfer::build_solution(crate::transfer::BuildSolution {
+                hashed_from_key,
+                hashed_to_key,
+                new_nonce,
+                amount,
+                current_from_balance: balance(1),
+                current_to_balance: balance(2),
+                signature,
+                mode,
+                extra: None,
+            }),
+        }
+    }
+}
+
+impl Prepared {
+    /// Prepares an operation against the state read for its [`Operation::state_keys`].
+    pub fn new(
+        contract: ContentAddress,
+        operation: Operation,
+        state: Vec<StateRead>,
+    ) -> anyhow::Result<Self> {
+        let prepared = Self {
+            contract,
+            to_sign: operation.to_words(),
+            operation,
+            state,
+        };
+        prepared.check()?;
+        Ok(prepared)
+    }
+
+    /// Checks that the words to sign and the state match the operation.
+    ///
+    /// Prepared operations are read from files, so this is checked again
+    /// before signing and submitting.
+    pub fn check(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.to_sign == self.operation.to_words(),
+            "The words to sign don't match the operation"
+        );
+        let keys: Vec<_> = self.state.iter().map(|read| &read.key).collect();
+        ensure!(
+            keys.iter().copied().eq(self.operation.state_keys().iter()),
+            "The prepared state doesn't match the operation"
+        );
+        let nonce: Word = Query::new(self.state[0].value.clone()).get()?;
+        ensure!(
+            nonce.checked_add(1) == Some(self.operation.new_nonce()),
+            "The new nonce doesn't follow the prepared nonce"
+        );
+        Ok(())
+    }
+
+    /// Signs the operation, which must be for the signer's account.
+    pub fn sign(self, signer: &mut impl Signer) -> anyhow::Result<Signed> {
+        self.check()?;
+        ensure!(
+            signer.hashed_key()? == self.operation.signer_key(),
+            "The operation is for a different account"
+        );
+        let signature = signer.sign(&self.to_sign)?;
+        Ok(Signed {
+            prepared: self,
+            signature: encode_signature(&signature),
+        })
+    }
+}
+
+impl Signed {
+    /// Builds the solution, provided the state is unchanged since the operation was prepared.
+    ///
+    /// `current` must be read for the same keys as [`Prepared::state`].
+    pub fn build_solution(self, current: &[StateRead]) -> anyhow::Result<Solution> {
+        let Signed {
+            prepared,
+            signature,
+        } = self;
+        prepared.check()?;
+        ensure!(
+            current.len() == prepared.state.len(),
+            "Expected {} state reads, got {}",
+            prepared.state.len(),
+            current.len()
+        );
+        for (prepared, current) in prepared.state.iter().zip(current) {
+            ensure!(prepared.key == current.key, "State read for the wrong key");
+            let nil = |value: &Option<Value>| value.as_ref().is_none_or(|v| v.is_empty());
+            if prepared.value != current.value && !(nil(&prepared.value) && nil(&current.value)) {
+                bail!(
+                    "State at key {:?} changed from {:?} to {:?} since the operation was prepared",
+                    current.key,
+                    prepared.value,
+                    current.value
+                );
+            }
+        }
+        prepared
+            .operation
+            .build_solution(&prepared.state, decode_signature(&signature)?)
+    }
+}
+
+/// Encodes a signature as hex of its compact form followed by the recovery id.
+pub fn encode_signature(signature: &RecoverableSignature) -> String {
+    let (id, compact) = signature.serialize_compact();
+    let mut bytes = compact.to_vec();
+    bytes.push(id.to_i32() as u8);
+    hex::encode_upper(bytes)
+}
+
+/// Decodes a signature encoded with [`encode_signature`].
+pub fn decode_signature(signature: &str) -> anyhow::Result<RecoverableSignature> {
+    let bytes = hex::decode(signature)?;
+    let Some((id, compact)) = bytes.split_last() else {
+        bail!("Empty signature");
+    };
+    ensure!(
+        compact.len() == 64,
+        "Expected 65 signature bytes, got {}",
+        bytes.len()
+    );
+    let id = RecoveryId::from_i32(*id as i32)?;
+    Ok(RecoverableSignature::from_compact(compact, id)?)
+}
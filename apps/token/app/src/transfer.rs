@@ -1,14 +1,42 @@
 //! # Transfer
 //! Contains functionality for transferring tokens between accounts in the token contract.
+//!
+//! A transfer can require another predicate to be solved alongside it, but
+//! the sender's signature doesn't cover that requirement.
 
-use essential_app_utils::inputs::Encode;
+use essential_app_utils::{
+    inputs::{to_words, Encode, Instance, WriteDecVars},
+    nonce::NonceManager,
+    signer::Signer,
+};
 use essential_sign::secp256k1::ecdsa::RecoverableSignature;
 use essential_types::{
     solution::{Solution, SolutionData},
-    Word,
+    ContentAddress, PredicateAddress, Word,
 };
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, str::FromStr};
+
+use crate::Query;
 
-use crate::{balance, nonce, Query};
+/// Which parts of a transfer the sender's signature covers.
+///
+/// Fields that aren't signed can be filled in by whoever submits the solution,
+/// for instance to pay a solver whose key is unknown at sign-time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Encode, Serialize, Deserialize)]
+#[encode(into = "super::token::TransferSignedMode")]
+#[serde(rename_all = "kebab-case")]
+pub enum SignMode {
+    /// Sign the sender, recipient, amount and nonce.
+    #[default]
+    All,
+    /// Sign only the sender and nonce.
+    Key,
+    /// Sign the sender, recipient and nonce.
+    KeyTo,
+    /// Sign the sender, amount and nonce.
+    KeyAmount,
+}
 
 /// Represents the initial data required for transferring tokens.
 pub struct Init {
@@ -20,9 +48,13 @@ pub struct Init {
     pub amount: Word,
     /// The current nonce of the sender.
     pub nonce: Query,
+    /// Which fields the signature covers.
+    pub mode: SignMode,
 }
 
 /// Represents the data to be signed for a transfer solution.
+///
+/// Only the fields covered by `mode` are included in the signed words.
 pub struct ToSign {
     /// The hashed key of the sender.
     pub hashed_from_key: [Word; 4],
@@ -32,6 +64,8 @@ pub struct ToSign {
     pub amount: Word,
     /// The new nonce of the sender.
     pub new_nonce: Word,
+    /// Which fields the signature covers.
+    pub mode: SignMode,
 }
 
 /// Contains all necessary information to build a transfer solution.
@@ -50,23 +84,103 @@ pub struct BuildSolution {
     pub current_to_balance: Query,
     /// The signature over the data.
     pub signature: RecoverableSignature,
+    /// Which fields the signature covers.
+    pub mode: SignMode,
+    /// A predicate that must also be solved at the given path, if any.
+    ///
+    /// This isn't covered by the signature, so whoever builds the solution
+    /// from a signed transfer can add or remove it.
+    pub extra: Option<Instance>,
+}
+
+/// Contains all necessary information to build a transfer from an account owned by a predicate.
+pub struct BuildPredicateSolution {
+    /// The predicate that owns the sending account.
+    pub owner: PredicateAddress,
+    /// The hashed key of the recipient.
+    pub hashed_to_key: [Word; 4],
+    /// The amount of tokens to transfer.
+    pub amount: Word,
+    /// The current nonce of the sender.
+    pub nonce: Query,
+    /// The current balance of the sender.
+    pub current_from_balance: Query,
+    /// The current balance of the recipient.
+    pub current_to_balance: Query,
+    /// A predicate that must also be solved at the given path, if any.
+    pub extra: Option<Instance>,
 }
 
 impl ToSign {
     /// Converts the ToSign struct to a vector of Words for signing.
     pub fn to_words(&self) -> Vec<Word> {
-        vec![
-            self.hashed_from_key[0],
-            self.hashed_from_key[1],
-            self.hashed_from_key[2],
-            self.hashed_from_key[3],
-            self.hashed_to_key[0],
-            self.hashed_to_key[1],
-            self.hashed_to_key[2],
-            self.hashed_to_key[3],
-            self.amount,
-            self.new_nonce,
-        ]
+        let Self {
+            hashed_from_key: key,
+            hashed_to_key: to,
+            amount,
+            new_nonce: nonce,
+            mode,
+        } = *self;
+        match mode {
+            SignMode::All => to_words(&SignedAll {
+                key,
+                to,
+                amount,
+                nonce,
+            }),
+            SignMode::Key => to_words(&SignedKey { key, nonce }),
+            SignMode::KeyTo => to_words(&SignedKeyTo { key, to, nonce }),
+            SignMode::KeyAmount => to_words(&SignedKeyAmount { key, amount, nonce }),
+        }
+    }
+}
+
+/// The signed words for [`SignMode::All`].
+#[derive(WriteDecVars)]
+struct SignedAll {
+    key: [Word; 4],
+    to: [Word; 4],
+    amount: Word,
+    nonce: Word,
+}
+
+/// The signed words for [`SignMode::Key`].
+#[derive(WriteDecVars)]
+struct SignedKey {
+    key: [Word; 4],
+    nonce: Word,
+}
+
+/// The signed words for [`SignMode::KeyTo`].
+#[derive(WriteDecVars)]
+struct SignedKeyTo {
+    key: [Word; 4],
+    to: [Word; 4],
+    nonce: Word,
+}
+
+/// The signed words for [`SignMode::KeyAmount`].
+#[derive(WriteDecVars)]
+struct SignedKeyAmount {
+    key: [Word; 4],
+    amount: Word,
+    nonce: Word,
+}
+
+impl FromStr for SignMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            "key" => Ok(Self::Key),
+            "key-to" => Ok(Self::KeyTo),
+            "key-amount" => Ok(Self::KeyAmount),
+            _ => anyhow::bail!(
+                "Unknown sign mode: {}. Expected all, key, key-to or key-amount",
+                s
+            ),
+        }
     }
 }
 
@@ -77,13 +191,15 @@ pub fn data_to_sign(account: Init) -> anyhow::Result<ToSign> {
         hashed_to_key,
         amount,
         nonce: current_nonce,
+        mode,
     } = account;
-    let new_nonce = increment_nonce(nonce(current_nonce)?);
+    let new_nonce = increment_nonce(current_nonce.get()?);
     Ok(ToSign {
         amount,
         new_nonce,
         hashed_from_key,
         hashed_to_key,
+        mode,
     })
 }
 
@@ -97,35 +213,160 @@ pub fn build_solution(build: BuildSolution) -> anyhow::Result<Solution> {
         current_from_balance,
         current_to_balance,
         signature,
+        mode,
+        extra,
     } = build;
-    let from_balance = calculate_from_balance(balance(current_from_balance)?, amount)?;
-    let to_balance = calculate_to_balance(balance(current_to_balance)?, amount)?;
+    let auth = super::token::TransferAuthMode::Signed((signature.encode(), mode.encode()));
+    let solution = solution_data(
+        (hashed_from_key, current_from_balance),
+        (hashed_to_key, current_to_balance),
+        amount,
+        new_nonce,
+        (auth, extra),
+    )?;
+    Ok(Solution {
+        data: vec![solution],
+    })
+}
+
+/// Where the nonces for queued transfers come from.
+#[derive(Clone, Copy)]
+pub struct QueuedNonces<'a> {
+    /// Hands out the nonces and takes them back if signing fails.
+    pub manager: &'a NonceManager,
+    /// The token contract the transfers are sent to.
+    pub contract: &'a ContentAddress,
+    /// The sender's nonce currently stored on chain.
+    pub on_chain: Word,
+}
+
+/// Signs transfers from one account that are queued to be included in order.
+///
+/// Each transfer is signed with the next pending nonce from `nonces` and is
+/// built on the balances left by the ones before it. `balances` holds the
+/// current balance of the sender and recipients. Missing accounts are empty.
+/// If any transfer can't be signed, the nonces handed out are rolled back.
+///
+/// Returns each transfer's nonce and solution. The solutions are for the token
+/// contract this crate was compiled against.
+pub fn sign_queued(
+    hashed_from_key: [Word; 4],
+    nonces: QueuedNonces,
+    mut balances: HashMap<[Word; 4], Word>,
+    transfers: &[([Word; 4], Word)],
+    mode: SignMode,
+    signer: &mut impl Signer,
+) -> anyhow::Result<Vec<(Word, Solution)>> {
+    let nonce_key = crate::nonce_key(hashed_from_key);
+    let mut first = None;
+    let mut sign = || {
+        let mut signed = vec![];
+        for (i, &(hashed_to_key, amount)) in transfers.iter().enumerate() {
+            let new_nonce = nonces
+                .manager
+                .next(nonces.contract, &nonce_key, nonces.on_chain)?;
+            first.get_or_insert(new_nonce);
+            let to_sign = ToSign {
+                hashed_from_key,
+                hashed_to_key,
+                amount,
+                new_nonce,
+                mode,
+            };
+            let balance =
+                |key| Query::new(Some(vec![balances.get(key).copied().unwrap_or_default()]));
+            let solution = build_solution(BuildSolution {
+                hashed_from_key,
+                hashed_to_key,
+                new_nonce,
+                amount,
+                current_from_balance: balance(&hashed_from_key),
+                current_to_balance: balance(&hashed_to_key),
+                signature: signer.sign(&to_sign.to_words())?,
+                mode,
+                extra: None,
+            })
+            .map_err(|e| anyhow::anyhow!("Transfer {}: {}", i + 1, e))?;
+            *balances.entry(hashed_from_key).or_default() -= amount;
+            *balances.entry(hashed_to_key).or_default() += amount;
+            signed.push((new_nonce, solution));
+        }
+        anyhow::Ok(signed)
+    };
+    let result = sign();
+    if let (Err(_), Some(first)) = (&result, first) {
+        nonces.manager.rollback(nonces.contract, &nonce_key, first);
+    }
+    result
+}
+
+/// Builds a transfer from the account owned by a predicate.
+///
+/// `authorize` is given the path of the transfer in the solution and returns
+/// the owning predicate's solution data.
+pub fn build_predicate_solution(
+    build: BuildPredicateSolution,
+    authorize: impl FnOnce(Word) -> SolutionData,
+) -> anyhow::Result<Solution> {
+    let BuildPredicateSolution {
+        owner,
+        hashed_to_key,
+        amount,
+        nonce,
+        current_from_balance,
+        current_to_balance,
+        extra,
+    } = build;
+    let hashed_from_key = crate::predicate_account(&owner);
+    let new_nonce = increment_nonce(nonce.get()?);
+    let auth = super::token::TransferAuthMode::Predicate((owner.encode(), crate::AUTHORIZER_PATH));
+    let solution = solution_data(
+        (hashed_from_key, current_from_balance),
+        (hashed_to_key, current_to_balance),
+        amount,
+        new_nonce,
+        (auth, extra),
+    )?;
+    Ok(crate::with_authorizer(solution, authorize))
+}
+
+/// The transfer's solution data, given each account's hashed key and current balance.
+fn solution_data(
+    (hashed_from_key, current_from_balance): ([Word; 4], Query),
+    (hashed_to_key, current_to_balance): ([Word; 4], Query),
+    amount: Word,
+    new_nonce: Word,
+    (auth, extra): (super::token::TransferAuthMode, Option<Instance>),
+) -> anyhow::Result<SolutionData> {
+    let from_balance = calculate_from_balance(current_from_balance.get()?, amount)?;
+    let to_balance = calculate_to_balance(current_to_balance.get()?, amount)?;
     let pub_vars = super::token::Transfer::PubVars {
         key: hashed_from_key,
         to: hashed_to_key,
         amount,
     };
-    let signature = signature.encode();
-    let auth =
-        super::token::TransferAuthMode::Signed((signature, super::token::TransferSignedMode::All));
     let vars = super::token::Transfer::Vars {
-        auth: (auth, super::token::ExtraConstraints::None),
+        auth: (auth, extra_constraints(extra)),
     };
     let mutations = super::token::storage::mutations()
         .balances(|map| map.entry(hashed_from_key, from_balance))
         .balances(|map| map.entry(hashed_to_key, to_balance))
         .nonce(|nonces| nonces.entry(hashed_from_key, new_nonce));
-    let solution = SolutionData {
+    Ok(SolutionData {
         predicate_to_solve: super::token::Transfer::ADDRESS,
         decision_variables: vars.into(),
         transient_data: pub_vars.into(),
         state_mutations: mutations.into(),
-    };
-    Ok(Solution {
-        data: vec![solution],
     })
 }
 
+fn extra_constraints(extra: Option<Instance>) -> super::token::ExtraConstraints {
+    match extra {
+        Some(instance) => super::token::ExtraConstraints::Extra(instance.encode()),
+        None => super::token::ExtraConstraints::None,
+    }
+}
+
 /// Increments the nonce by 1.
 fn increment_nonce(nonce: Word) -> Word {
     nonce + 1
@@ -1,8 +1,17 @@
-use essential_app_utils::{self as utils, compile::compile_pint_project};
-use essential_signer::Signature;
-use essential_types::{convert::word_4_from_u8_32, Word};
-use essential_wallet::Wallet;
-use token::Query;
+use essential_app_utils::{
+    self as utils,
+    bundle::SolutionBundle,
+    compile::{compile_pint_project, ContractAddresses},
+    inputs::{Decode, Encode, WordSize, WriteDecVars},
+    nonce::NonceManager,
+    signer::{Signer, WalletSigner},
+};
+use essential_sign::secp256k1::{ecdsa::RecoverableSignature, Message, Secp256k1, SecretKey};
+use essential_types::{ContentAddress, PredicateAddress, Value, Word};
+use token::{
+    transfer::{QueuedNonces, SignMode},
+    Query,
+};
 
 // Constants for the test
 
@@ -15,15 +24,12 @@ const TOKEN_SYMBOL: &str = "ALC";
 
 #[tokio::test]
 async fn mint_and_transfer() {
+    let labels = token_labels().await;
     // Initialize tracing for better debugging
     tracing_subscriber::fmt::init();
 
     // Compile the token contract
-    // This requires `pint` be available on PATH
-    let transfer =
-        compile_pint_project(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/token").into())
-            .await
-            .unwrap();
+    let transfer = compile_token().await;
 
     // Create a temporary wallet for testing
     let mut wallet = essential_wallet::Wallet::temp().unwrap();
@@ -42,15 +48,10 @@ async fn mint_and_transfer() {
 
     // Set the initial mint amount and get Alice's hashed key
     let first_mint_amount = 1000000;
-    let alice_hashed_key = hash_key(&mut wallet, alice);
-
-    // Create new databases for testing
-    let dbs = utils::db::new_dbs().await;
+    let alice_hashed_key = WalletSigner::new(&mut wallet, alice).hashed_key().unwrap();
 
-    // Deploy the token contract
-    essential_app_utils::deploy::deploy_contract(&dbs.builder, &transfer)
-        .await
-        .unwrap();
+    // Create new databases for testing and deploy the token contract
+    let dbs = new_token_dbs(&[], &[&transfer]).await;
 
     // Get Alice's nonce key
     let alice_nonce_key = token::nonce_key(alice_hashed_key);
@@ -63,13 +64,12 @@ async fn mint_and_transfer() {
         hashed_key: alice_hashed_key,
         amount: first_mint_amount,
         decimals: 18,
-        nonce: Query(nonce),
+        nonce: Query::new(nonce),
     };
     let to_sign = token::mint::data_to_sign(init).unwrap();
-    let sig = wallet.sign_words(&to_sign.to_words(), alice).unwrap();
-    let Signature::Secp256k1(sig) = sig else {
-        panic!("Invalid signature")
-    };
+    let sig = WalletSigner::new(&mut wallet, alice)
+        .sign(&to_sign.to_words())
+        .unwrap();
 
     // Get Alice's balance key
     let alice_balance_key = token::balance_key(alice_hashed_key);
@@ -81,7 +81,7 @@ async fn mint_and_transfer() {
     // Build the mint solution
     let build_solution = token::mint::BuildSolution {
         new_nonce: to_sign.new_nonce,
-        current_balance: Query(balance),
+        current_balance: Query::new(balance),
         hashed_key: alice_hashed_key,
         amount: first_mint_amount,
         decimals: 18,
@@ -103,14 +103,19 @@ async fn mint_and_transfer() {
 
     // Build a block
     let o = utils::builder::build_default(&dbs).await.unwrap();
-    assert!(o.failed.is_empty(), "{:?}", o.failed);
+    assert!(
+        o.failed.is_empty(),
+        "{:?}\n{}",
+        o.failed,
+        labels.solution(&solution)
+    );
 
     // Verify Alice's balance after minting
     let balance =
         utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_balance_key)
             .await
             .unwrap();
-    assert_eq!(token::balance(Query(balance)).unwrap(), first_mint_amount);
+    assert_eq!(Query::new(balance).get().unwrap(), first_mint_amount);
 
     // Set up Bob's account
     let bob = "bob";
@@ -119,7 +124,7 @@ async fn mint_and_transfer() {
         .unwrap();
 
     // Get Bob's hashed key
-    let bob_hashed_key = hash_key(&mut wallet, bob);
+    let bob_hashed_key = WalletSigner::new(&mut wallet, bob).hashed_key().unwrap();
 
     // Prepare the transfer solution
     let nonce = utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_nonce_key)
@@ -129,15 +134,15 @@ async fn mint_and_transfer() {
         hashed_from_key: alice_hashed_key,
         hashed_to_key: bob_hashed_key,
         amount: 500,
-        nonce: Query(nonce),
+        nonce: Query::new(nonce),
+        mode: SignMode::All,
     };
 
     // Sign the transfer solution
     let to_sign = token::transfer::data_to_sign(init).unwrap();
-    let sig = wallet.sign_words(&to_sign.to_words(), alice).unwrap();
-    let Signature::Secp256k1(sig) = sig else {
-        panic!("Invalid signature")
-    };
+    let sig = WalletSigner::new(&mut wallet, alice)
+        .sign(&to_sign.to_words())
+        .unwrap();
 
     // Get current balances for Alice and Bob
     let from_balance =
@@ -157,9 +162,11 @@ async fn mint_and_transfer() {
         hashed_to_key: bob_hashed_key,
         new_nonce: to_sign.new_nonce,
         amount: 500,
-        current_from_balance: Query(from_balance),
-        current_to_balance: Query(to_balance),
+        current_from_balance: Query::new(from_balance),
+        current_to_balance: Query::new(to_balance),
         signature: sig,
+        mode: SignMode::All,
+        extra: None,
     };
     let solution = token::transfer::build_solution(solution).unwrap();
 
@@ -173,17 +180,19 @@ async fn mint_and_transfer() {
         .await
         .unwrap();
     let o = utils::builder::build_default(&dbs).await.unwrap();
-    assert!(o.failed.is_empty(), "{:?}", o.failed);
+    assert!(
+        o.failed.is_empty(),
+        "{:?}\n{}",
+        o.failed,
+        labels.solution(&solution)
+    );
 
     // Verify Alice's balance after transfer
     let balance =
         utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_balance_key)
             .await
             .unwrap();
-    assert_eq!(
-        token::balance(Query(balance)).unwrap(),
-        first_mint_amount - 500
-    );
+    assert_eq!(Query::new(balance).get().unwrap(), first_mint_amount - 500);
 
     // Verify Bob's balance after transfer
     let balance =
@@ -191,15 +200,1918 @@ async fn mint_and_transfer() {
             .await
             .unwrap();
 
-    assert_eq!(token::balance(Query(balance)).unwrap(), 500);
+    assert_eq!(Query::new(balance).get().unwrap(), 500);
+}
+
+#[tokio::test]
+async fn transfer_from_genesis_balance() {
+    let labels = token_labels().await;
+    // Compile the token contract
+    let contract = compile_token().await;
+
+    // Create a temporary wallet with Alice and Bob
+    let mut wallet = essential_wallet::Wallet::temp().unwrap();
+    let alice = "alice";
+    let bob = "bob";
+    wallet
+        .new_key_pair(alice, essential_wallet::Scheme::Secp256k1)
+        .unwrap();
+    wallet
+        .new_key_pair(bob, essential_wallet::Scheme::Secp256k1)
+        .unwrap();
+    let alice_hashed_key = WalletSigner::new(&mut wallet, alice).hashed_key().unwrap();
+    let bob_hashed_key = WalletSigner::new(&mut wallet, bob).hashed_key().unwrap();
+    let alice_balance_key = token::balance_key(alice_hashed_key);
+    let bob_balance_key = token::balance_key(bob_hashed_key);
+
+    // Seed Alice's balance from a TOML fixture instead of minting
+    let fixture = format!(
+        "\"{}\" = [{{ key = {:?}, value = [1000] }}]",
+        token::token::ADDRESS,
+        alice_balance_key,
+    );
+    let state = utils::db::GenesisState::from_toml_str(&fixture).unwrap();
+    let genesis = utils::db::Genesis::default().with_state(state);
+    let dbs = utils::db::new_dbs_with_genesis(&genesis).await;
+
+    // The seeded balance is readable before any block is built
+    let balance =
+        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_balance_key)
+            .await
+            .unwrap();
+    assert_eq!(Query::new(balance).get().unwrap(), 1000);
+
+    // Deploy the token contract
+    essential_app_utils::deploy::deploy_contract(&dbs, &contract)
+        .await
+        .unwrap();
+
+    // Transfer from the seeded balance
+    let nonce = utils::node::query_state_head(
+        &dbs.node,
+        &token::token::ADDRESS,
+        &token::nonce_key(alice_hashed_key),
+    )
+    .await
+    .unwrap();
+    let to_sign = token::transfer::data_to_sign(token::transfer::Init {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: bob_hashed_key,
+        amount: 400,
+        nonce: Query::new(nonce),
+        mode: SignMode::All,
+    })
+    .unwrap();
+    let sig = WalletSigner::new(&mut wallet, alice)
+        .sign(&to_sign.to_words())
+        .unwrap();
+    let from_balance =
+        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_balance_key)
+            .await
+            .unwrap();
+    let to_balance =
+        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &bob_balance_key)
+            .await
+            .unwrap();
+    let solution = token::transfer::build_solution(token::transfer::BuildSolution {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: bob_hashed_key,
+        new_nonce: to_sign.new_nonce,
+        amount: 400,
+        current_from_balance: Query::new(from_balance),
+        current_to_balance: Query::new(to_balance),
+        signature: sig,
+        mode: SignMode::All,
+        extra: None,
+    })
+    .unwrap();
+    utils::builder::submit(&dbs.builder, solution.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(
+        o.failed.is_empty(),
+        "{:?}\n{}",
+        o.failed,
+        labels.solution(&solution)
+    );
+
+    // Verify both balances
+    let balance =
+        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &alice_balance_key)
+            .await
+            .unwrap();
+    assert_eq!(Query::new(balance).get().unwrap(), 600);
+    let balance =
+        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &bob_balance_key)
+            .await
+            .unwrap();
+    assert_eq!(Query::new(balance).get().unwrap(), 400);
+}
+
+#[tokio::test]
+async fn queued_transfers() {
+    let contract = compile_token().await;
+    let mut wallet = essential_wallet::Wallet::temp().unwrap();
+    wallet
+        .new_key_pair("alice", essential_wallet::Scheme::Secp256k1)
+        .unwrap();
+    wallet
+        .new_key_pair("bob", essential_wallet::Scheme::Secp256k1)
+        .unwrap();
+    let mut alice = WalletSigner::new(&mut wallet, "alice");
+    let alice_hashed_key = alice.hashed_key().unwrap();
+    let bob_hashed_key = WalletSigner::new(alice.wallet, "bob").hashed_key().unwrap();
+
+    let dbs = new_token_dbs(&[(alice_hashed_key, 1000)], &[&contract]).await;
+
+    // Queue two transfers before either is included.
+    let balances = [(alice_hashed_key, 1000)].into_iter().collect();
+    let transfers = [(bob_hashed_key, 100), (bob_hashed_key, 200)];
+    let nonces = NonceManager::new();
+    let queued = QueuedNonces {
+        manager: &nonces,
+        contract: &token::token::ADDRESS,
+        on_chain: 0,
+    };
+    let signed = token::transfer::sign_queued(
+        alice_hashed_key,
+        queued,
+        balances,
+        &transfers,
+        SignMode::All,
+        &mut alice,
+    )
+    .unwrap();
+    assert_eq!(signed.iter().map(|(n, _)| *n).collect::<Vec<_>>(), [1, 2]);
+    for (_, solution) in signed {
+        utils::builder::submit(&dbs.builder, solution)
+            .await
+            .unwrap();
+    }
+
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(o.failed.is_empty(), "{:?}", o.failed);
+
+    let nonce_key = token::nonce_key(alice_hashed_key);
+    let nonce = utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &nonce_key)
+        .await
+        .unwrap();
+    assert_eq!(Query::new(nonce).get().unwrap(), 2);
+
+    let balance = utils::node::query_state_head(
+        &dbs.node,
+        &token::token::ADDRESS,
+        &token::balance_key(bob_hashed_key),
+    )
+    .await
+    .unwrap();
+    assert_eq!(Query::new(balance).get().unwrap(), 300);
+}
+
+#[tokio::test]
+async fn bundle_payment_with_counter_increment() {
+    let labels = token_labels()
+        .await
+        .load(concat!(env!("CARGO_MANIFEST_DIR"), "/../../counter/pint").into())
+        .await
+        .unwrap();
+    let token_contract = compile_token().await;
+    let counter_contract =
+        compile_pint_project(concat!(env!("CARGO_MANIFEST_DIR"), "/../../counter/pint").into())
+            .await
+            .unwrap();
+    let counter = PredicateAddress {
+        contract: essential_hash::contract_addr::from_contract(&counter_contract),
+        predicate: essential_hash::content_addr(&counter_contract.predicates[0]),
+    };
+
+    let mut alice = alice_key();
+    let alice_hashed_key = alice.hashed_key().unwrap();
+    let solver_hashed_key = [7; 4];
+    let dbs = new_token_dbs(
+        &[(alice_hashed_key, 1000)],
+        &[&token_contract, &counter_contract],
+    )
+    .await;
+    utils::builder::build_default(&dbs).await.unwrap();
+
+    // Pay the solver and increment the counter in one solution.
+    let transfer = |alice: &mut SecretKey, new_nonce| {
+        let to_sign = token::transfer::ToSign {
+            hashed_from_key: alice_hashed_key,
+            hashed_to_key: solver_hashed_key,
+            amount: 10,
+            new_nonce,
+            mode: SignMode::All,
+        };
+        token::transfer::build_solution(token::transfer::BuildSolution {
+            hashed_from_key: alice_hashed_key,
+            hashed_to_key: solver_hashed_key,
+            new_nonce,
+            amount: 10,
+            current_from_balance: Query::new(Some(vec![1000])),
+            current_to_balance: Query::new(None),
+            signature: alice.sign(&to_sign.to_words()).unwrap(),
+            mode: SignMode::All,
+            extra: None,
+        })
+        .unwrap()
+    };
+    let (increment, _) =
+        counter_app::incremented_solution(counter.clone(), counter_app::QueryCount::new(None))
+            .unwrap();
+    let mut bundle = SolutionBundle::new();
+    let payment = bundle.extend(transfer(&mut alice, 1));
+    let counted = bundle.extend(increment);
+    assert_eq!(payment[0].path, 0);
+    assert_eq!(counted[0].path, 1);
+    assert_eq!(counted[0].address, counter);
+
+    // A second payment from the same account touches the same balance and nonce.
+    let mut conflicting = bundle.clone();
+    conflicting.extend(transfer(&mut alice, 2));
+    assert!(conflicting.build().is_err());
+
+    let solution = bundle.build().unwrap();
+    utils::builder::submit(&dbs.builder, solution.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.succeeded.len(), 2);
+    assert!(
+        o.failed.is_empty(),
+        "{:?}\n{}",
+        o.failed,
+        labels.solution(&solution)
+    );
+
+    let balance = utils::node::query_state_head(
+        &dbs.node,
+        &token::token::ADDRESS,
+        &token::balance_key(solver_hashed_key),
+    )
+    .await
+    .unwrap();
+    assert_eq!(Query::new(balance).get().unwrap(), 10);
+    let count =
+        utils::node::query_state_head(&dbs.node, &counter.contract, &counter_app::counter_key().0)
+            .await
+            .unwrap();
+    assert_eq!(counter_app::QueryCount::new(count).get().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn transfer_requires_extra_predicate() {
+    let labels = token_labels()
+        .await
+        .load(concat!(env!("CARGO_MANIFEST_DIR"), "/../../counter/pint").into())
+        .await
+        .unwrap();
+    let token_contract = compile_token().await;
+    let counter_path: std::path::PathBuf =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../../counter/pint").into();
+    let counter_contract = compile_pint_project(counter_path.clone()).await.unwrap();
+    let counter = ContractAddresses::compile(counter_path)
+        .await
+        .unwrap()
+        .predicate("Increment")
+        .unwrap();
+
+    let mut alice = alice_key();
+    let alice_hashed_key = alice.hashed_key().unwrap();
+    let bob_hashed_key = [9; 4];
+    let dbs = new_token_dbs(
+        &[(alice_hashed_key, 1000)],
+        &[&token_contract, &counter_contract],
+    )
+    .await;
+    utils::builder::build_default(&dbs).await.unwrap();
+
+    // The transfer only goes through if the counter is incremented alongside it.
+    let mut bundle = SolutionBundle::new();
+    let extra = utils::inputs::Instance {
+        address: counter.clone(),
+        path: 1,
+    };
+    let to_sign = token::transfer::data_to_sign(token::transfer::Init {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: bob_hashed_key,
+        amount: 100,
+        nonce: Query::new(None),
+        mode: SignMode::All,
+    })
+    .unwrap();
+    let transfer = token::transfer::build_solution(token::transfer::BuildSolution {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: bob_hashed_key,
+        new_nonce: to_sign.new_nonce,
+        amount: 100,
+        current_from_balance: Query::new(Some(vec![1000])),
+        current_to_balance: Query::new(None),
+        signature: alice.sign(&to_sign.to_words()).unwrap(),
+        mode: SignMode::All,
+        extra: Some(extra),
+    })
+    .unwrap();
+
+    // On its own it fails.
+    utils::builder::submit(&dbs.builder, transfer.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.failed.len(), 1, "{}", labels.solution(&transfer));
+    assert_eq!(read_balance(&dbs, bob_hashed_key).await, 0);
+
+    bundle.extend(transfer);
+    let (increment, _) =
+        counter_app::incremented_solution(counter.clone(), counter_app::QueryCount::new(None))
+            .unwrap();
+    let counted = bundle.extend(increment);
+    assert_eq!(counted[0].path, 1);
+    let solution = bundle.build().unwrap();
+    utils::builder::submit(&dbs.builder, solution.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(
+        o.failed.is_empty(),
+        "{:?}\n{}",
+        o.failed,
+        labels.solution(&solution)
+    );
+    assert_eq!(read_balance(&dbs, bob_hashed_key).await, 100);
+}
+
+#[tokio::test]
+async fn token_info_after_mint_and_burn() {
+    let contract = compile_token().await;
+    let mut alice = alice_key();
+    let alice_hashed_key = alice.hashed_key().unwrap();
+    let dbs = new_token_dbs(&[], &[&contract]).await;
+
+    assert_eq!(read_metadata(&dbs).await, None);
+
+    // Mint
+    let to_sign = token::mint::data_to_sign(token::mint::Init {
+        hashed_key: alice_hashed_key,
+        amount: 1000,
+        decimals: 2,
+        nonce: Query::new(None),
+    })
+    .unwrap();
+    let mint = token::mint::build_solution(token::mint::BuildSolution {
+        new_nonce: to_sign.new_nonce,
+        current_balance: Query::new(None),
+        hashed_key: alice_hashed_key,
+        amount: 1000,
+        decimals: 2,
+        signature: alice.sign(&to_sign.to_words()).unwrap(),
+        token_name: TOKEN_NAME.to_string(),
+        token_symbol: TOKEN_SYMBOL.to_string(),
+    })
+    .unwrap();
+    utils::builder::submit(&dbs.builder, mint.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(o.failed.is_empty(), "{:?}", o.failed);
+
+    let metadata = read_metadata(&dbs).await.unwrap();
+    assert_eq!(metadata.decimals, 2);
+    assert_eq!(metadata.name(&["bob coin", TOKEN_NAME]), Some(TOKEN_NAME));
+    assert_eq!(metadata.symbol(&["BOB"]), None);
+    assert_eq!(metadata.symbol(&[TOKEN_SYMBOL]), Some(TOKEN_SYMBOL));
+
+    // Burn
+    let to_sign = token::burn::data_to_sign(token::burn::Init {
+        hashed_key: alice_hashed_key,
+        amount: 300,
+        nonce: Query::new(Some(vec![1])),
+    })
+    .unwrap();
+    let burn = token::burn::build_solution(token::burn::BuildSolution {
+        new_nonce: to_sign.new_nonce,
+        current_balance: Query::new(Some(vec![1000])),
+        hashed_key: alice_hashed_key,
+        amount: 300,
+        signature: alice.sign(&to_sign.to_words()).unwrap(),
+    })
+    .unwrap();
+    utils::builder::submit(&dbs.builder, burn.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(o.failed.is_empty(), "{:?}", o.failed);
+    assert_eq!(read_balance(&dbs, alice_hashed_key).await, 700);
+
+    // Only mints and burns change the supply.
+    let predicates = token::indexer::Predicates::default();
+    let block = |number, solutions| essential_types::Block {
+        number,
+        timestamp: Default::default(),
+        solutions,
+    };
+    let blocks = [
+        block(
+            0,
+            vec![utils::deploy::deploy_contract_solution(&contract).unwrap()],
+        ),
+        block(1, vec![mint]),
+        block(2, vec![burn]),
+    ];
+    assert_eq!(
+        token::info::total_supply(&predicates, &blocks[..2]).unwrap(),
+        1000
+    );
+    assert_eq!(
+        token::info::total_supply(&predicates, &blocks).unwrap(),
+        700
+    );
+
+    // Another token's mints and burns don't count.
+    let other = token::indexer::Predicates {
+        mint: PredicateAddress {
+            contract: ContentAddress([0; 32]),
+            predicate: ContentAddress([1; 32]),
+        },
+        burn: PredicateAddress {
+            contract: ContentAddress([0; 32]),
+            predicate: ContentAddress([2; 32]),
+        },
+        ..predicates
+    };
+    assert_eq!(token::info::total_supply(&other, &blocks).unwrap(), 0);
+}
+
+#[tokio::test]
+async fn new_token_is_its_own_contract() {
+    let dir = std::env::temp_dir().join(format!("token-new-{}", std::process::id()));
+    let manifest = dir.join(utils::deploy::MANIFEST_FILE);
+    let project = dir.join("token_bob");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut alice = alice_key();
+    let alice_hashed_key = alice.hashed_key().unwrap();
+    let config = token::config::TokenConfig {
+        name: "bob coin".to_string(),
+        symbol: "BOB".to_string(),
+        mint_key: alice_hashed_key,
+    };
+    let template: &std::path::Path = concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/token").as_ref();
+    assert!(
+        token::config::new_project(template, &project, "token_bob", &config)
+            .await
+            .unwrap()
+    );
+    // Creating the same token again resumes the existing project.
+    assert!(
+        !token::config::new_project(template, &project, "token_bob", &config)
+            .await
+            .unwrap()
+    );
+    // A different token can't reuse the directory.
+    let other = token::config::TokenConfig {
+        name: "carol coin".to_string(),
+        symbol: config.symbol.clone(),
+        mint_key: config.mint_key,
+    };
+    assert!(
+        token::config::new_project(template, &project, "token_bob", &other)
+            .await
+            .is_err()
+    );
+    assert!(
+        token::config::new_project(template, &project, "token_carol", &config)
+            .await
+            .is_err()
+    );
+
+    let dbs = utils::db::new_dbs().await;
+    let (outcome, _) = utils::deploy::deploy_project(&dbs, project.clone(), &manifest)
+        .await
+        .unwrap();
+    assert!(matches!(
+        outcome,
+        utils::deploy::DeployOutcome::Submitted(_)
+    ));
+    let addresses = ContractAddresses::resolve(project, Some(&manifest))
+        .await
+        .unwrap();
+    assert_ne!(addresses.contract, token::token::ADDRESS);
+
+    let mint = |alice: &mut SecretKey, token_name: &str| {
+        let to_sign = token::mint::data_to_sign(token::mint::Init {
+            hashed_key: alice_hashed_key,
+            amount: 500,
+            decimals: 2,
+            nonce: Query::new(None),
+        })
+        .unwrap();
+        let solution = token::mint::build_solution(token::mint::BuildSolution {
+            new_nonce: to_sign.new_nonce,
+            current_balance: Query::new(None),
+            hashed_key: alice_hashed_key,
+            amount: 500,
+            decimals: 2,
+            signature: alice.sign(&to_sign.to_words()).unwrap(),
+            token_name: token_name.to_string(),
+            token_symbol: "BOB".to_string(),
+        })
+        .unwrap();
+        token::retarget(solution, &addresses).unwrap()
+    };
+
+    // The new contract only mints its own name.
+    let solution = mint(&mut alice, TOKEN_NAME);
+    assert_eq!(
+        solution.data[0].predicate_to_solve,
+        addresses.predicate("Mint").unwrap()
+    );
+    utils::builder::submit(&dbs.builder, solution)
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.failed.len(), 1);
+
+    utils::builder::submit(&dbs.builder, mint(&mut alice, "bob coin"))
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(o.failed.is_empty(), "{:?}", o.failed);
+
+    let balance = utils::node::query_state_head(
+        &dbs.node,
+        &addresses.contract,
+        &token::balance_key(alice_hashed_key),
+    )
+    .await
+    .unwrap();
+    assert_eq!(Query::new(balance).get().unwrap(), 500);
+    let name =
+        utils::node::query_state_head(&dbs.node, &addresses.contract, &token::token_name_key())
+            .await
+            .unwrap();
+    let name = token::info::QueryHash::new(name).get().unwrap();
+    assert_eq!(
+        token::info::match_hash(&name, &["bob coin"]),
+        Some("bob coin")
+    );
+    // The original token is untouched.
+    assert_eq!(read_balance(&dbs, alice_hashed_key).await, 0);
+
+    // The supply is read from the new contract's predicates.
+    let blocks = dbs.node.list_blocks(0..100).await.unwrap();
+    let predicates = token::indexer::Predicates::resolve(&addresses).unwrap();
+    assert_eq!(
+        token::info::total_supply(&predicates, &blocks).unwrap(),
+        500
+    );
+    assert_eq!(
+        token::info::total_supply(&token::indexer::Predicates::default(), &blocks).unwrap(),
+        0
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn new_project_copies_nested_sources() {
+    let dir = std::env::temp_dir().join(format!("token-template-{}", std::process::id()));
+    let template = dir.join("template");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(template.join("src/lib")).unwrap();
+    std::fs::write(
+        template.join("pint.toml"),
+        "[package]\nname = \"token\"\nkind = \"contract\"\n",
+    )
+    .unwrap();
+    std::fs::write(template.join("src/contract.pnt"), "use lib::helper;\n").unwrap();
+    std::fs::write(template.join("src/lib/helper.pnt"), "const HELPER = 1;\n").unwrap();
+
+    let config = token::config::TokenConfig {
+        name: "bob coin".to_string(),
+        symbol: "BOB".to_string(),
+        mint_key: [1; 4],
+    };
+    let project = dir.join("token_bob");
+    assert!(
+        token::config::new_project(&template, &project, "token_bob", &config)
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        std::fs::read_to_string(project.join("src/lib/helper.pnt")).unwrap(),
+        "const HELPER = 1;\n"
+    );
+    assert!(project.join(token::config::CONFIG_FILE).is_file());
+
+    // A newline in the name would end the comment it is written into.
+    let injected = token::config::TokenConfig {
+        name: "bob\nconst MINT_KEY = 0x00;".to_string(),
+        ..config
+    };
+    assert!(injected.to_pint().is_err());
+    let project = dir.join("token_injected");
+    assert!(
+        token::config::new_project(&template, &project, "token_injected", &injected)
+            .await
+            .is_err()
+    );
+    assert!(!project.exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn remote_deploy_finds_registration_block() {
+    let dir = std::env::temp_dir().join(format!("token-remote-deploy-{}", std::process::id()));
+    let manifest = dir.join(utils::deploy::MANIFEST_FILE);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let pint = |name: &str| {
+        std::path::Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint")).join(name)
+    };
+
+    // Register another contract first so the token isn't in the first block.
+    let dbs = utils::db::new_dbs().await;
+    utils::deploy::deploy_project(&dbs, pint("escrow"), &manifest)
+        .await
+        .unwrap();
+    let (_, local) = utils::deploy::deploy_project(&dbs, pint("token"), &manifest)
+        .await
+        .unwrap();
+    let head = utils::node::head_block_number(&dbs.node).await.unwrap();
+    assert_eq!(local.block, head);
+    assert!(local.block > 1);
+
+    // The node API reads state derived from the blocks.
+    let _derive = derive_state(&dbs).await;
+    let node = serve_node(&dbs).await;
+    assert_eq!(node.latest_block_number().await.unwrap(), Some(head));
+
+    // The contract is already registered, so the builder is never used.
+    let builder = essential_rest_client::builder_client::EssentialBuilderClient::new(
+        "http://127.0.0.1:1/".to_string(),
+    )
+    .unwrap();
+    let deploy =
+        || utils::deploy::remote::deploy_project(&node, &builder, pint("token"), &manifest);
+    let (outcome, remote) = deploy().await.unwrap();
+    assert_eq!(outcome, utils::deploy::DeployOutcome::AlreadyDeployed);
+    assert_eq!(remote, local);
+
+    // Without a manifest to start from, every block is searched.
+    std::fs::remove_file(&manifest).unwrap();
+    let (_, remote) = deploy().await.unwrap();
+    assert_eq!(remote.block, local.block);
+    std::fs::remove_dir_all(&dir).unwrap();
 }
 
-// Helper function to hash a public key
-fn hash_key(wallet: &mut Wallet, account_name: &str) -> [Word; 4] {
-    let public_key = wallet.get_public_key(account_name).unwrap();
-    let essential_signer::PublicKey::Secp256k1(public_key) = public_key else {
-        panic!("Invalid public key")
+#[test]
+fn index_account_history() {
+    use token::indexer::{Event, Index, Predicates};
+
+    let mut alice = alice_key();
+    let alice_hashed_key = alice.hashed_key().unwrap();
+    let bob_hashed_key = [9; 4];
+    let signature = alice.sign(&[0]).unwrap();
+
+    let mint = token::mint::build_solution(token::mint::BuildSolution {
+        new_nonce: 1,
+        current_balance: Query::new(None),
+        hashed_key: alice_hashed_key,
+        amount: 1000,
+        decimals: 2,
+        signature,
+        token_name: TOKEN_NAME.to_string(),
+        token_symbol: TOKEN_SYMBOL.to_string(),
+    })
+    .unwrap();
+    let transfer = token::transfer::build_solution(token::transfer::BuildSolution {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: bob_hashed_key,
+        new_nonce: 2,
+        amount: 300,
+        current_from_balance: Query::new(Some(vec![1000])),
+        current_to_balance: Query::new(None),
+        signature,
+        mode: SignMode::All,
+        extra: None,
+    })
+    .unwrap();
+    let burn = token::burn::build_solution(token::burn::BuildSolution {
+        new_nonce: 3,
+        current_balance: Query::new(Some(vec![700])),
+        hashed_key: alice_hashed_key,
+        amount: 100,
+        signature,
+    })
+    .unwrap();
+    let cancel = token::cancel::build_solution(token::cancel::BuildSolution {
+        new_nonce: 4,
+        hashed_key: alice_hashed_key,
+        signature,
+    })
+    .unwrap();
+    let block = |number, solutions| essential_types::Block {
+        number,
+        timestamp: Default::default(),
+        solutions,
     };
-    let encoded = essential_sign::encode::public_key(&public_key);
-    word_4_from_u8_32(essential_hash::hash_words(&encoded))
+    let blocks = [
+        block(1, vec![mint.clone()]),
+        block(2, vec![transfer.clone()]),
+        block(3, vec![burn, cancel.clone()]),
+    ];
+
+    let path = std::env::temp_dir().join(format!("token-index-{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut index = Index::open(&path, Predicates::default()).unwrap();
+    assert_eq!(index.next_block().unwrap(), 0);
+    index.index_block(&blocks[0]).unwrap();
+    index.index_block(&blocks[1]).unwrap();
+    assert_eq!(index.total_supply().unwrap(), 1000);
+    drop(index);
+
+    // Reopening resumes where it left off and already indexed blocks are skipped.
+    let mut index = Index::open(&path, Predicates::default()).unwrap();
+    assert_eq!(index.next_block().unwrap(), 3);
+    for block in &blocks {
+        index.index_block(block).unwrap();
+    }
+    assert_eq!(index.next_block().unwrap(), 4);
+
+    let history = index.history(alice_hashed_key).unwrap();
+    let events: Vec<_> = history.iter().map(|e| e.event.clone()).collect();
+    assert_eq!(
+        events,
+        vec![
+            Event::Mint {
+                key: alice_hashed_key,
+                amount: 1000
+            },
+            Event::Transfer {
+                from: alice_hashed_key,
+                to: bob_hashed_key,
+                amount: 300
+            },
+            Event::Burn {
+                key: alice_hashed_key,
+                amount: 100
+            },
+            Event::Cancel {
+                key: alice_hashed_key
+            },
+        ]
+    );
+    assert_eq!(history[0].block, 1);
+    assert_eq!(history[1].solution, essential_hash::content_addr(&transfer));
+    assert_eq!(history[3].solution, essential_hash::content_addr(&cancel));
+    assert_eq!(index.history(bob_hashed_key).unwrap().len(), 1);
+    assert_eq!(
+        index.holders().unwrap(),
+        vec![(alice_hashed_key, 600), (bob_hashed_key, 300)]
+    );
+    assert_eq!(index.total_supply().unwrap(), 900);
+
+    // A malformed burn amount is an error rather than an overflow.
+    let malformed = Event::Burn {
+        key: alice_hashed_key,
+        amount: Word::MIN,
+    };
+    assert!(malformed.supply_change().is_err());
+
+    // Solutions for another token contract aren't recognised.
+    let other = Predicates {
+        mint: PredicateAddress {
+            contract: ContentAddress([1; 32]),
+            predicate: ContentAddress([2; 32]),
+        },
+        ..Predicates::default()
+    };
+    assert!(Index::open(&path, other.clone()).is_err());
+    let mut other_index = Index::in_memory(other).unwrap();
+    other_index.index_block(&blocks[0]).unwrap();
+    assert!(other_index.holders().unwrap().is_empty());
+    assert_eq!(other_index.total_supply().unwrap(), 0);
+    assert_eq!(
+        token::indexer::events(&Predicates::default(), &mint)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn sync_index_from_node() {
+    use token::indexer::{Event, Index, Predicates};
+
+    let contract = compile_token().await;
+    let mut alice = alice_key();
+    let alice_hashed_key = alice.hashed_key().unwrap();
+    let bob_hashed_key = [9; 4];
+    let dbs = new_token_dbs(&[], &[&contract]).await;
+    utils::builder::build_default(&dbs).await.unwrap();
+
+    let to_sign = token::mint::data_to_sign(token::mint::Init {
+        hashed_key: alice_hashed_key,
+        amount: 1000,
+        decimals: 2,
+        nonce: Query::new(None),
+    })
+    .unwrap();
+    let mint = token::mint::build_solution(token::mint::BuildSolution {
+        new_nonce: to_sign.new_nonce,
+        current_balance: Query::new(None),
+        hashed_key: alice_hashed_key,
+        amount: 1000,
+        decimals: 2,
+        signature: alice.sign(&to_sign.to_words()).unwrap(),
+        token_name: TOKEN_NAME.to_string(),
+        token_symbol: TOKEN_SYMBOL.to_string(),
+    })
+    .unwrap();
+    utils::builder::submit(&dbs.builder, mint).await.unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(o.failed.is_empty(), "{:?}", o.failed);
+
+    let node = serve_node(&dbs).await;
+    let mut index = Index::in_memory(Predicates::default()).unwrap();
+    let synced = index.sync(&node).await.unwrap();
+    assert!(synced > 0);
+    assert_eq!(index.next_block().unwrap(), synced as Word);
+    assert_eq!(index.holders().unwrap(), vec![(alice_hashed_key, 1000)]);
+    assert_eq!(index.total_supply().unwrap(), 1000);
+
+    // Include a transfer in each of more than a page of blocks.
+    let page = essential_rest_client::node_client::BLOCK_PAGE;
+    let queued = QueuedNonces {
+        manager: &NonceManager::new(),
+        contract: &token::token::ADDRESS,
+        on_chain: 1,
+    };
+    let transfers = token::transfer::sign_queued(
+        alice_hashed_key,
+        queued,
+        [(alice_hashed_key, 1000)].into(),
+        &vec![(bob_hashed_key, 1); page as usize + 1],
+        SignMode::All,
+        &mut alice,
+    )
+    .unwrap();
+    let mut last = None;
+    for (_, transfer) in transfers {
+        last = Some(
+            utils::builder::submit(&dbs.builder, transfer)
+                .await
+                .unwrap(),
+        );
+        let o = utils::builder::build_default(&dbs).await.unwrap();
+        assert!(o.failed.is_empty(), "{:?}", o.failed);
+    }
+
+    assert_eq!(index.sync(&node).await.unwrap(), page as usize + 1);
+    let last_block = synced as Word + page;
+    assert_eq!(index.next_block().unwrap(), last_block + 1);
+    let history = index.history(bob_hashed_key).unwrap();
+    assert_eq!(history.len(), page as usize + 1);
+    let entry = history.last().unwrap();
+    assert_eq!(entry.block, last_block);
+    assert_eq!(Some(&entry.solution), last.as_ref());
+    assert_eq!(
+        entry.event,
+        Event::Transfer {
+            from: alice_hashed_key,
+            to: bob_hashed_key,
+            amount: 1,
+        }
+    );
+    assert_eq!(
+        index.holders().unwrap(),
+        vec![(alice_hashed_key, 999 - page), (bob_hashed_key, page + 1)]
+    );
+
+    // Nothing new to index.
+    assert_eq!(index.sync(&node).await.unwrap(), 0);
+}
+
+#[test]
+fn decimal_amounts() {
+    use token::amount::{format, parse, split_symbol};
+
+    assert_eq!(parse("1.5", 18).unwrap(), 1_500_000_000_000_000_000);
+    assert_eq!(parse("1", 2).unwrap(), 100);
+    assert_eq!(parse("0.01", 2).unwrap(), 1);
+    assert_eq!(parse("1.50", 1).unwrap(), 15);
+    assert_eq!(parse("42", 0).unwrap(), 42);
+    // Over-precision, bad input and overflow are rejected.
+    assert!(parse("0.001", 2).is_err());
+    assert!(parse("1.5", 0).is_err());
+    for bad in ["", ".5", "1.", "-1", "+1", "1.2.3", "1e3", "one"] {
+        assert!(parse(bad, 2).is_err(), "{}", bad);
+    }
+    assert!(parse("10", 18).is_err());
+    assert!(parse("1", 19).is_err());
+
+    assert_eq!(format(1_500_000_000_000_000_000, 18).unwrap(), "1.5");
+    assert_eq!(format(1, 2).unwrap(), "0.01");
+    assert_eq!(format(100, 2).unwrap(), "1");
+    assert_eq!(format(-150, 2).unwrap(), "-1.5");
+    assert_eq!(format(42, 0).unwrap(), "42");
+    assert_eq!(format(Word::MAX, 18).unwrap(), "9.223372036854775807");
+
+    assert_eq!(split_symbol(" 1.5 ALC "), ("1.5", Some("ALC")));
+    assert_eq!(split_symbol("1.5"), ("1.5", None));
+
+    let metadata = token::info::Metadata {
+        name_hash: token::info::hash_str(TOKEN_NAME),
+        symbol_hash: token::info::hash_str(TOKEN_SYMBOL),
+        decimals: 2,
+    };
+    assert_eq!(metadata.parse_amount("1.5 ALC").unwrap(), 150);
+    assert_eq!(metadata.parse_amount("1.5").unwrap(), 150);
+    assert!(metadata.parse_amount("1.5 BOB").is_err());
+    assert_eq!(metadata.format_amount(150).unwrap(), "1.5");
+}
+
+/// Mirrors the contract's `BurnAuth` union to check the derived layout and encoding.
+#[derive(Clone, Debug, PartialEq, WriteDecVars, Encode, Decode)]
+#[encode(into = "token::token::BurnAuth")]
+enum BurnAuth {
+    Signed(RecoverableSignature),
+    Predicate(PredicateAuth),
+}
+
+/// Mirrors the contract's `PredicateAuth` type.
+#[derive(Clone, Debug, PartialEq, WriteDecVars, Encode, Decode)]
+struct PredicateAuth {
+    addr: PredicateAddress,
+    path: Word,
+}
+
+#[test]
+fn derived_union_round_trips_abi() {
+    let secp = Secp256k1::new();
+    let secret_key = alice_key();
+    let message = Message::from_digest([7; 32]);
+    let signature = secp.sign_ecdsa_recoverable(&message, &secret_key);
+    let address = PredicateAddress {
+        contract: ContentAddress([1; 32]),
+        predicate: ContentAddress([2; 32]),
+    };
+
+    let predicate = PredicateAuth {
+        addr: address,
+        path: token::AUTHORIZER_PATH,
+    };
+    for auth in [BurnAuth::Signed(signature), BurnAuth::Predicate(predicate)] {
+        let mut derived = Vec::new();
+        auth.write_dec_var(&mut derived);
+        let abi: Vec<Value> = token::token::Burn::Vars {
+            auth: auth.encode(),
+        }
+        .into();
+        assert_eq!(derived, abi);
+        assert_eq!(derived[0].len(), <BurnAuth as WordSize>::WORD_SIZE);
+        assert_eq!(BurnAuth::decode(&abi[0]).unwrap(), auth);
+    }
+}
+
+#[tokio::test]
+async fn contract_addresses_match_abi() {
+    let path = std::path::PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/token"));
+    let compiled = ContractAddresses::compile(path.clone()).await.unwrap();
+    let loaded = ContractAddresses::load(path).await.unwrap();
+
+    assert_eq!(compiled.contract, token::token::ADDRESS);
+    assert_eq!(loaded.contract, compiled.contract);
+    assert_eq!(loaded.predicates, compiled.predicates);
+    assert_eq!(
+        compiled.predicate("Transfer").unwrap(),
+        token::token::Transfer::ADDRESS
+    );
+    assert_eq!(
+        compiled.predicate("::burn").unwrap(),
+        token::token::Burn::ADDRESS
+    );
+    assert!(compiled.predicate("Missing").is_err());
+}
+
+#[tokio::test]
+async fn burn_from_predicate_account() {
+    let labels = token_labels().await;
+    let contract = compile_token().await;
+    let counter_contract =
+        compile_pint_project(concat!(env!("CARGO_MANIFEST_DIR"), "/../../counter/pint").into())
+            .await
+            .unwrap();
+
+    let mut wallet = essential_wallet::Wallet::temp().unwrap();
+    let alice = "alice";
+    wallet
+        .new_key_pair(alice, essential_wallet::Scheme::Secp256k1)
+        .unwrap();
+    let alice_hashed_key = WalletSigner::new(&mut wallet, alice).hashed_key().unwrap();
+
+    // The account controlled by the counter's increment predicate
+    let owner = ContractAddresses::compile(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../../counter/pint").into(),
+    )
+    .await
+    .unwrap()
+    .predicate("Increment")
+    .unwrap();
+    let owned_key = token::predicate_account(&owner);
+    assert_eq!(owned_key, utils::addresses::predicate_owned_key(&owner));
+    let increment_to = |count| counter_app::create_solution(owner.clone(), count).data[0].clone();
+
+    let dbs = new_token_dbs(&[(alice_hashed_key, 1000)], &[&contract, &counter_contract]).await;
+
+    // Fund the predicate account from Alice
+    let to_sign = token::transfer::data_to_sign(token::transfer::Init {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: owned_key,
+        amount: 300,
+        nonce: Query::new(None),
+        mode: SignMode::All,
+    })
+    .unwrap();
+    let signature = WalletSigner::new(&mut wallet, alice)
+        .sign(&to_sign.to_words())
+        .unwrap();
+    let solution = token::transfer::build_solution(token::transfer::BuildSolution {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: owned_key,
+        new_nonce: to_sign.new_nonce,
+        amount: 300,
+        current_from_balance: Query::new(Some(vec![1000])),
+        current_to_balance: Query::new(None),
+        signature,
+        mode: SignMode::All,
+        extra: None,
+    })
+    .unwrap();
+    utils::builder::submit(&dbs.builder, solution.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(
+        o.failed.is_empty(),
+        "{:?}\n{}",
+        o.failed,
+        labels.solution(&solution)
+    );
+    assert_eq!(read_balance(&dbs, owned_key).await, 300);
+
+    let burn = token::burn::build_predicate_solution(
+        token::burn::BuildPredicateSolution {
+            owner: owner.clone(),
+            nonce: Query::new(None),
+            current_balance: Query::new(Some(vec![300])),
+            amount: 100,
+        },
+        |_| increment_to(1),
+    )
+    .unwrap();
+    assert_eq!(burn.data[1].predicate_to_solve, owner);
+
+    // The owner must be solved alongside the burn
+    let mut unauthorized = burn.clone();
+    unauthorized.data.truncate(1);
+    utils::builder::submit(&dbs.builder, unauthorized)
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.failed.len(), 1);
+    assert_eq!(read_balance(&dbs, owned_key).await, 300);
+
+    // A different predicate doesn't own the account
+    let not_owner = PredicateAddress {
+        contract: owner.contract.clone(),
+        predicate: ContentAddress([0; 32]),
+    };
+    let solution = predicate_burn(owned_key, not_owner, increment_to(1), 100, 300);
+    utils::builder::submit(&dbs.builder, solution.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.failed.len(), 1);
+    assert_eq!(read_balance(&dbs, owned_key).await, 300);
+
+    // The owning predicate does
+    utils::builder::submit(&dbs.builder, burn.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(
+        o.failed.is_empty(),
+        "{:?}\n{}",
+        o.failed,
+        labels.solution(&burn)
+    );
+    assert_eq!(read_balance(&dbs, owned_key).await, 200);
+
+    // The owner can also cancel
+    let cancel = token::cancel::build_predicate_solution(
+        token::cancel::BuildPredicateSolution {
+            owner: owner.clone(),
+            nonce: Query::new(Some(vec![1])),
+        },
+        |_| increment_to(2),
+    )
+    .unwrap();
+    let mut unauthorized = cancel.clone();
+    unauthorized.data.truncate(1);
+    utils::builder::submit(&dbs.builder, unauthorized)
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.failed.len(), 1);
+
+    utils::builder::submit(&dbs.builder, cancel.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(
+        o.failed.is_empty(),
+        "{:?}\n{}",
+        o.failed,
+        labels.solution(&cancel)
+    );
+    let nonce = utils::node::query_state_head(
+        &dbs.node,
+        &token::token::ADDRESS,
+        &token::nonce_key(owned_key),
+    )
+    .await
+    .unwrap();
+    assert_eq!(Query::new(nonce).get().unwrap(), 2);
+}
+
+#[tokio::test]
+async fn mint_to_predicate_account() {
+    let dir = std::env::temp_dir().join(format!("token-predicate-mint-{}", std::process::id()));
+    let manifest = dir.join(utils::deploy::MANIFEST_FILE);
+    let project = dir.join("token_owned");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let counter_contract =
+        compile_pint_project(concat!(env!("CARGO_MANIFEST_DIR"), "/../../counter/pint").into())
+            .await
+            .unwrap();
+    let owner = ContractAddresses::compile(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../../counter/pint").into(),
+    )
+    .await
+    .unwrap()
+    .predicate("Increment")
+    .unwrap();
+    let owned_key = token::predicate_account(&owner);
+
+    // A token that only the counter's increment predicate can mint
+    let config = token::config::TokenConfig {
+        name: "owned coin".to_string(),
+        symbol: "OWN".to_string(),
+        mint_key: owned_key,
+    };
+    token::config::new_project(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/token").as_ref(),
+        &project,
+        "token_owned",
+        &config,
+    )
+    .await
+    .unwrap();
+    let dbs = utils::db::new_dbs().await;
+    utils::deploy::deploy_project(&dbs, project.clone(), &manifest)
+        .await
+        .unwrap();
+    utils::deploy::deploy_contract(&dbs, &counter_contract)
+        .await
+        .unwrap();
+    utils::builder::build_default(&dbs).await.unwrap();
+    let addresses = ContractAddresses::resolve(project, Some(&manifest))
+        .await
+        .unwrap();
+
+    let mint = token::mint::build_predicate_solution(
+        token::mint::BuildPredicateSolution {
+            owner: owner.clone(),
+            nonce: Query::new(None),
+            current_balance: Query::new(None),
+            amount: 1000,
+            decimals: 2,
+            token_name: config.name.clone(),
+            token_symbol: config.symbol.clone(),
+        },
+        |_| counter_app::create_solution(owner.clone(), 1).data[0].clone(),
+    )
+    .unwrap();
+    let mint = token::retarget(mint, &addresses).unwrap();
+
+    // The owner must be solved alongside the mint
+    let mut unauthorized = mint.clone();
+    unauthorized.data.truncate(1);
+    utils::builder::submit(&dbs.builder, unauthorized)
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.failed.len(), 1);
+
+    utils::builder::submit(&dbs.builder, mint).await.unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(o.failed.is_empty(), "{:?}", o.failed);
+    let balance = utils::node::query_state_head(
+        &dbs.node,
+        &addresses.contract,
+        &token::balance_key(owned_key),
+    )
+    .await
+    .unwrap();
+    assert_eq!(Query::new(balance).get().unwrap(), 1000);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn cancel_invalidates_signed_transfer() {
+    let labels = token_labels().await;
+    let contract = compile_token().await;
+
+    let mut alice = alice_key();
+    let alice_hashed_key = alice.hashed_key().unwrap();
+    let bob_hashed_key = [9; 4];
+    let dbs = new_token_dbs(&[(alice_hashed_key, 1000)], &[&contract]).await;
+    utils::builder::build_default(&dbs).await.unwrap();
+
+    // Alice signs a transfer to Bob and hands it to a solver, who submits it
+    let to_sign = token::transfer::data_to_sign(token::transfer::Init {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: bob_hashed_key,
+        amount: 250,
+        nonce: Query::new(None),
+        mode: SignMode::All,
+    })
+    .unwrap();
+    let signature = alice.sign(&to_sign.to_words()).unwrap();
+    let transfer = token::transfer::build_solution(token::transfer::BuildSolution {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: bob_hashed_key,
+        new_nonce: to_sign.new_nonce,
+        amount: 250,
+        current_from_balance: Query::new(Some(vec![1000])),
+        current_to_balance: Query::new(None),
+        signature,
+        mode: SignMode::All,
+        extra: None,
+    })
+    .unwrap();
+
+    // Alice changes her mind and cancels
+    let to_sign = token::cancel::data_to_sign(token::cancel::Init {
+        hashed_key: alice_hashed_key,
+        nonce: Query::new(None),
+    })
+    .unwrap();
+    let signature = alice.sign(&to_sign.to_words()).unwrap();
+    let solution = token::cancel::build_solution(token::cancel::BuildSolution {
+        new_nonce: to_sign.new_nonce,
+        hashed_key: alice_hashed_key,
+        signature,
+    })
+    .unwrap();
+    // Both are pending; the builder attempts the cancel first
+    utils::builder::submit(&dbs.builder, transfer.clone())
+        .await
+        .unwrap();
+    utils::builder::submit(&dbs.builder, solution.clone())
+        .await
+        .unwrap();
+    let cancel_ca = essential_hash::content_addr(&solution);
+    let strategy = utils::builder::PriorityByFee(|s: &essential_types::solution::Solution| {
+        Word::from(essential_hash::content_addr(s) == cancel_ca)
+    });
+    let o = utils::builder::build_with(&dbs, &utils::builder::default_config(&dbs), &strategy)
+        .await
+        .unwrap();
+    assert!(
+        o.succeeded.iter().any(|(ca, _)| *ca == cancel_ca),
+        "{:?}\n{}",
+        o.failed,
+        labels.solution(&solution)
+    );
+
+    // The signed transfer can no longer be included
+    assert_eq!(o.failed.len(), 1);
+    assert_eq!(o.failed[0].0, essential_hash::content_addr(&transfer));
+    let nonce = utils::node::query_state_head(
+        &dbs.node,
+        &token::token::ADDRESS,
+        &token::nonce_key(alice_hashed_key),
+    )
+    .await
+    .unwrap();
+    assert_eq!(Query::new(nonce).get().unwrap(), 1);
+    assert_eq!(read_balance(&dbs, alice_hashed_key).await, 1000);
+    assert_eq!(read_balance(&dbs, bob_hashed_key).await, 0);
+}
+
+#[test]
+fn failed_command_exits_with_failure() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_token"))
+        .args(["external-balance", "not-hex", "http://127.0.0.1:1", "."])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Command failed because"));
+}
+
+#[tokio::test]
+async fn transfer_sign_modes() {
+    let contract = compile_token().await;
+    let bob = [9; 4];
+    let carol = [10; 4];
+
+    // (mode, recipient, amount, accepted)
+    // Alice always signs a transfer of 100 to Bob.
+    let cases = [
+        (SignMode::All, bob, 100, true),
+        (SignMode::All, carol, 100, false),
+        (SignMode::All, bob, 50, false),
+        (SignMode::Key, bob, 100, true),
+        (SignMode::Key, carol, 50, true),
+        (SignMode::KeyTo, bob, 50, true),
+        (SignMode::KeyTo, carol, 100, false),
+        (SignMode::KeyAmount, carol, 100, true),
+        (SignMode::KeyAmount, bob, 50, false),
+    ];
+    for (mode, to, amount, accepted) in cases {
+        let outcome = signed_transfer(&contract, mode, to, amount).await;
+        assert_eq!(
+            outcome, accepted,
+            "{:?} transfer to {:?} of {}",
+            mode, to, amount
+        );
+    }
+}
+
+#[tokio::test]
+async fn escrow_releases_tokens_to_beneficiary() {
+    let labels = token_labels()
+        .await
+        .load(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/escrow").into())
+        .await
+        .unwrap();
+    let token_contract = compile_token().await;
+    let escrow_path: std::path::PathBuf =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/escrow").into();
+    let escrow_contract = compile_pint_project(escrow_path.clone()).await.unwrap();
+    let release = ContractAddresses::compile(escrow_path)
+        .await
+        .unwrap()
+        .predicate("Release")
+        .unwrap();
+
+    // The escrow holds tokens in the account owned by its release predicate.
+    let escrow_key = token::predicate_account(&release);
+    let beneficiary = [9; 4];
+    let dbs = new_token_dbs(&[(escrow_key, 500)], &[&token_contract, &escrow_contract]).await;
+
+    let authorize = |path| essential_types::solution::SolutionData {
+        predicate_to_solve: release.clone(),
+        decision_variables: vec![vec![path]],
+        transient_data: vec![],
+        state_mutations: vec![],
+    };
+    let release_to = |to, amount, nonce: Option<Value>, balance: Option<Value>| {
+        token::transfer::build_predicate_solution(
+            token::transfer::BuildPredicateSolution {
+                owner: release.clone(),
+                hashed_to_key: to,
+                amount,
+                nonce: Query::new(nonce),
+                current_from_balance: Query::new(balance),
+                current_to_balance: Query::new(None),
+                extra: None,
+            },
+            authorize,
+        )
+        .unwrap()
+    };
+
+    // The escrow won't release to anyone else.
+    let solution = release_to([7; 4], 200, None, Some(vec![500]));
+    assert_eq!(
+        solution.data[0].predicate_to_solve,
+        token::token::Transfer::ADDRESS
+    );
+    assert_eq!(
+        solution.data[1].decision_variables,
+        vec![vec![token::TOKEN_PATH]]
+    );
+    utils::builder::submit(&dbs.builder, solution.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.failed.len(), 1, "{}", labels.solution(&solution));
+    assert_eq!(read_balance(&dbs, escrow_key).await, 500);
+
+    // Nor can the escrow's account be drained without solving the release.
+    let mut solution = release_to([7; 4], 200, None, Some(vec![500]));
+    solution.data.truncate(1);
+    utils::builder::submit(&dbs.builder, solution.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert_eq!(o.failed.len(), 1, "{}", labels.solution(&solution));
+    assert_eq!(read_balance(&dbs, escrow_key).await, 500);
+
+    // Releasing to the beneficiary moves the tokens.
+    let solution = release_to(beneficiary, 200, None, Some(vec![500]));
+    utils::builder::submit(&dbs.builder, solution.clone())
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(
+        o.failed.is_empty(),
+        "{:?}\n{}",
+        o.failed,
+        labels.solution(&solution)
+    );
+    assert_eq!(read_balance(&dbs, escrow_key).await, 300);
+    assert_eq!(read_balance(&dbs, beneficiary).await, 200);
+}
+
+#[tokio::test]
+async fn offline_sign_rejects_changed_state() {
+    use token::offline::{Operation, Prepared, Signed, StateRead};
+
+    let contract = compile_token().await;
+    let mut alice = alice_key();
+    let alice_hashed_key = alice.hashed_key().unwrap();
+    let bob_hashed_key = [9; 4];
+    let dbs = new_token_dbs(&[(alice_hashed_key, 1000)], &[&contract]).await;
+    utils::builder::build_default(&dbs).await.unwrap();
+
+    let read_state = |keys: Vec<essential_types::Key>| {
+        let dbs = &dbs;
+        async move {
+            let mut state = vec![];
+            for key in keys {
+                let value = utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &key)
+                    .await
+                    .unwrap();
+                state.push(StateRead { key, value });
+            }
+            state
+        }
+    };
+
+    // Prepare a transfer against the current state.
+    let operation = Operation::Transfer {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: bob_hashed_key,
+        amount: 100,
+        new_nonce: 1,
+        mode: SignMode::All,
+    };
+    let state = read_state(operation.state_keys()).await;
+    let prepared = Prepared::new(
+        token::token::ADDRESS.clone(),
+        operation.clone(),
+        state.clone(),
+    )
+    .unwrap();
+
+    // Only the account's own key can sign it.
+    let mut mallory = SecretKey::from_slice(&[7; 32]).unwrap();
+    assert!(prepared.clone().sign(&mut mallory).is_err());
+
+    // The operation survives being written to and read from a file.
+    let json = serde_json::to_string(&prepared).unwrap();
+    let prepared: Prepared = serde_json::from_str(&json).unwrap();
+    let signed = prepared.sign(&mut alice).unwrap();
+    let json = serde_json::to_string(&signed).unwrap();
+    let signed: Signed = serde_json::from_str(&json).unwrap();
+
+    // The signer's balance changes before submission, so it's rejected.
+    let mut changed = state.clone();
+    changed[1].value = Some(vec![900]);
+    assert!(signed.clone().build_solution(&changed).is_err());
+
+    // A prepared nonce that doesn't lead to the new nonce is rejected.
+    let mut tampered = signed.clone();
+    tampered.prepared.state[0].value = Some(vec![5]);
+    assert!(tampered.build_solution(&state).is_err());
+
+    // With the state unchanged the transfer goes through.
+    let current = read_state(operation.state_keys()).await;
+    let solution = signed.build_solution(&current).unwrap();
+    utils::builder::submit(&dbs.builder, solution)
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(o.failed.is_empty(), "{:?}", o.failed);
+    assert_eq!(read_balance(&dbs, alice_hashed_key).await, 900);
+    assert_eq!(read_balance(&dbs, bob_hashed_key).await, 100);
+}
+
+#[tokio::test]
+async fn airdrop_resumes_after_failure() {
+    use token::airdrop::{parse_csv, Report, Status};
+
+    let contract = compile_token().await;
+    let mut alice = alice_key();
+    let alice_hashed_key = alice.hashed_key().unwrap();
+    let recipients = [[9; 4], [8; 4], [7; 4]];
+    let dbs = new_token_dbs(&[(alice_hashed_key, 1000)], &[&contract]).await;
+    utils::builder::build_default(&dbs).await.unwrap();
+
+    let parse_amount = |amount: &str| token::amount::parse(amount, 2);
+    let hex_key = |key| hex::encode_upper(essential_types::convert::u8_32_from_word_4(key));
+    let csv = format!(
+        "recipient,amount\n{},1\n\n{},2.5\n{},0.5\n",
+        hex_key(recipients[0]),
+        hex_key(recipients[1]),
+        hex_key(recipients[2]),
+    );
+    let rows = parse_csv(&csv, parse_amount).unwrap();
+    assert_eq!(
+        rows.iter().map(|r| (r.line, r.amount)).collect::<Vec<_>>(),
+        vec![(2, 100), (4, 250), (5, 50)]
+    );
+    let err = parse_csv("nothex,1\n", parse_amount).unwrap_err();
+    assert!(err.to_string().starts_with("Line 1:"), "{}", err);
+
+    let read_balances = |report: &Report| {
+        let keys = report.balance_keys();
+        let dbs = &dbs;
+        async move {
+            let mut balances = std::collections::HashMap::new();
+            for key in keys {
+                balances.insert(key, read_balance(dbs, key).await);
+            }
+            balances
+        }
+    };
+    let mut report = Report::new(
+        token::token::ADDRESS.clone(),
+        alice_hashed_key,
+        rows.clone(),
+        0,
+    );
+
+    let node = serve_node(&dbs).await;
+    let builder = LocalBuilder(&dbs);
+    let addresses =
+        ContractAddresses::compile(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/token").into())
+            .await
+            .unwrap();
+    let nonces = NonceManager::new();
+    let queued = |on_chain| QueuedNonces {
+        manager: &nonces,
+        contract: &addresses.contract,
+        on_chain,
+    };
+
+    // Sending more than the balance is refused before anything is signed.
+    let mut poor = read_balances(&report).await;
+    poor.insert(alice_hashed_key, 10);
+    assert!(report
+        .sign_unsent(queued(0), poor, SignMode::All, &mut alice)
+        .is_err());
+    let report_path =
+        std::env::temp_dir().join(format!("airdrop-report-{}.json", std::process::id()));
+    let mut sender = token::airdrop::AirdropSender {
+        node: &node,
+        builder: &builder,
+        addresses: &addresses,
+        nonces: &nonces,
+        report_path: &report_path,
+        max_pending: 1,
+        poll_interval: std::time::Duration::from_millis(10),
+        timeout: std::time::Duration::from_secs(10),
+    };
+    let auto_build = || {
+        utils::builder::AutoBuilder::spawn(
+            dbs.clone(),
+            utils::builder::Trigger::OnSubmission {
+                poll: std::time::Duration::from_millis(10),
+            },
+            utils::builder::default_config(&dbs),
+        )
+    };
+
+    // The second transfer is built on a wrong balance, so the builder fails it
+    // and the third is never submitted.
+    let mut balances = read_balances(&report).await;
+    balances.insert(recipients[1], 5);
+    let transfers = report
+        .sign_unsent(queued(0), balances, SignMode::All, &mut alice)
+        .unwrap();
+    assert_eq!(
+        transfers.iter().map(|t| t.nonce).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+    let auto = auto_build();
+    sender.send(&mut report, transfers).await.unwrap();
+    auto.shutdown().await.unwrap();
+    assert_eq!(report.counts(), (1, 0, 2));
+    assert!(matches!(report.entries[0].status, Status::Included { .. }));
+    assert!(matches!(
+        report.entries[1].status,
+        Status::Failed { nonce: None, .. }
+    ));
+    assert!(matches!(report.entries[2].status, Status::Failed { .. }));
+
+    // The report is saved and resumed for the same CSV only.
+    let mut report: Report = serde_json::from_slice(&std::fs::read(&report_path).unwrap()).unwrap();
+    report
+        .ensure_matches(&token::token::ADDRESS, alice_hashed_key, &rows)
+        .unwrap();
+    assert!(report
+        .ensure_matches(&token::token::ADDRESS, alice_hashed_key, &rows[..2])
+        .is_err());
+
+    // Resuming only sends the failed transfers. The failure released their
+    // nonces, so they are signed with the next on-chain nonces. Nothing builds
+    // blocks, so the first times out and the last isn't sent.
+    assert_eq!(report.unsent(), vec![1, 2]);
+    let read_nonce = || async {
+        Query::new(
+            utils::node::query_state_head(
+                &dbs.node,
+                &token::token::ADDRESS,
+                &token::nonce_key(alice_hashed_key),
+            )
+            .await
+            .unwrap(),
+        )
+        .get()
+        .unwrap()
+    };
+    assert_eq!(read_nonce().await, 1);
+    let balances = read_balances(&report).await;
+    let transfers = report
+        .sign_unsent(queued(1), balances, SignMode::All, &mut alice)
+        .unwrap();
+    assert_eq!(
+        transfers.iter().map(|t| t.nonce).collect::<Vec<_>>(),
+        vec![2, 3]
+    );
+    sender.timeout = std::time::Duration::from_millis(50);
+    sender.send(&mut report, transfers).await.unwrap();
+    assert_eq!(report.counts(), (1, 0, 2));
+    assert!(matches!(
+        report.entries[1].status,
+        Status::Failed { nonce: Some(2), .. }
+    ));
+
+    // The timed out transfer is included late and is recorded when resuming.
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    assert!(o.failed.is_empty(), "{:?}", o.failed);
+    sender.send(&mut report, vec![]).await.unwrap();
+    assert!(matches!(
+        report.entries[1].status,
+        Status::Included { nonce: 2, .. }
+    ));
+    assert_eq!(report.unsent(), vec![2]);
+
+    let nonce = read_nonce().await;
+    assert_eq!(nonce, 2);
+    let balances = read_balances(&report).await;
+    let transfers = report
+        .sign_unsent(queued(nonce), balances, SignMode::All, &mut alice)
+        .unwrap();
+    assert_eq!(
+        transfers.iter().map(|t| t.nonce).collect::<Vec<_>>(),
+        vec![3]
+    );
+    sender.timeout = std::time::Duration::from_secs(10);
+    let auto = auto_build();
+    sender.send(&mut report, transfers).await.unwrap();
+    auto.shutdown().await.unwrap();
+    assert_eq!(report.counts(), (3, 0, 0));
+    assert_eq!(read_balance(&dbs, alice_hashed_key).await, 600);
+    for (key, amount) in recipients.into_iter().zip([100, 250, 50]) {
+        assert_eq!(read_balance(&dbs, key).await, amount);
+    }
+    std::fs::remove_file(&report_path).unwrap();
+}
+
+fn predicate_burn(
+    key: [essential_types::Word; 4],
+    auth: PredicateAddress,
+    authorizer: essential_types::solution::SolutionData,
+    amount: essential_types::Word,
+    balance: essential_types::Word,
+) -> essential_types::solution::Solution {
+    let pub_vars = token::token::Burn::PubVars { key, amount };
+    let vars = token::token::Burn::Vars {
+        auth: token::token::BurnAuth::Predicate((auth.encode(), token::AUTHORIZER_PATH)),
+    };
+    let mutations = token::token::storage::mutations()
+        .balances(|map| map.entry(key, balance - amount))
+        .nonce(|nonces| nonces.entry(key, 1));
+    essential_types::solution::Solution {
+        data: vec![
+            essential_types::solution::SolutionData {
+                predicate_to_solve: token::token::Burn::ADDRESS,
+                decision_variables: vars.into(),
+                transient_data: pub_vars.into(),
+                state_mutations: mutations.into(),
+            },
+            authorizer,
+        ],
+    }
+}
+
+async fn read_balance(dbs: &utils::db::Dbs, key: [essential_types::Word; 4]) -> i64 {
+    let balance =
+        utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, &token::balance_key(key))
+            .await
+            .unwrap();
+    Query::new(balance).get().unwrap()
+}
+
+async fn read_metadata(dbs: &utils::db::Dbs) -> Option<token::info::Metadata> {
+    let query = |key| utils::node::query_state_head(&dbs.node, &token::token::ADDRESS, key);
+    token::info::metadata(token::info::Init {
+        token_name: token::info::QueryHash::new(query(&token::token_name_key()).await.unwrap()),
+        token_symbol: token::info::QueryHash::new(query(&token::token_symbol_key()).await.unwrap()),
+        decimals: Query::new(query(&token::decimals_key()).await.unwrap()),
+    })
+    .unwrap()
+}
+
+async fn token_labels() -> utils::print::Labels {
+    utils::print::Labels::default()
+        .load(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/token").into())
+        .await
+        .unwrap()
+}
+
+/// Serves the node DB over the node API for the REST client.
+async fn serve_node(
+    dbs: &utils::db::Dbs,
+) -> essential_rest_client::node_client::EssentialNodeClient {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let router = essential_node_api::router(essential_node_api::State {
+        conn_pool: dbs.node.clone(),
+        new_block: None,
+    });
+    tokio::spawn(async move {
+        essential_node_api::serve(
+            &router,
+            &listener,
+            essential_node_api::DEFAULT_CONNECTION_LIMIT,
+        )
+        .await
+    });
+    essential_rest_client::node_client::EssentialNodeClient::new(format!(
+        "http://127.0.0.1:{port}/"
+    ))
+    .unwrap()
+}
+
+/// Runs the node's state derivation over the blocks built so far, keeping it
+/// running while the handle is held.
+async fn derive_state(dbs: &utils::db::Dbs) -> essential_node::Handle {
+    let blocks = essential_node::BlockTx::new();
+    let handle = essential_node::run(
+        dbs.node.clone(),
+        essential_node::RunConfig {
+            relayer_source_endpoint: None,
+            run_state_derivation: true,
+            run_validation: false,
+        },
+        dbs.big_bang.contract_registry.contract.clone(),
+        blocks.clone(),
+    )
+    .unwrap();
+    blocks.notify();
+    let head = utils::node::head_block_number(&dbs.node).await.unwrap();
+    let block_state = dbs.big_bang.block_state.contract.clone();
+    while dbs
+        .node
+        .query_state(block_state.clone(), vec![0])
+        .await
+        .unwrap()
+        != Some(vec![head])
+    {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    handle
+}
+
+/// Alice signs a transfer of 100 to `[9; 4]` with `mode`, which is then
+/// submitted with the given recipient and amount.
+///
+/// Returns whether the transfer was included.
+async fn signed_transfer(
+    contract: &essential_types::contract::Contract,
+    mode: SignMode,
+    to: [essential_types::Word; 4],
+    amount: essential_types::Word,
+) -> bool {
+    let mut alice = alice_key();
+    let alice_hashed_key = alice.hashed_key().unwrap();
+    let dbs = new_token_dbs(&[(alice_hashed_key, 1000)], &[contract]).await;
+
+    let to_sign = token::transfer::data_to_sign(token::transfer::Init {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: [9; 4],
+        amount: 100,
+        nonce: Query::new(None),
+        mode,
+    })
+    .unwrap();
+    let signature = alice.sign(&to_sign.to_words()).unwrap();
+    let solution = token::transfer::build_solution(token::transfer::BuildSolution {
+        hashed_from_key: alice_hashed_key,
+        hashed_to_key: to,
+        new_nonce: to_sign.new_nonce,
+        amount,
+        current_from_balance: Query::new(Some(vec![1000])),
+        current_to_balance: Query::new(None),
+        signature,
+        mode,
+        extra: None,
+    })
+    .unwrap();
+    utils::builder::submit(&dbs.builder, solution)
+        .await
+        .unwrap();
+    let o = utils::builder::build_default(&dbs).await.unwrap();
+    let accepted = o.failed.is_empty();
+    assert_eq!(
+        read_balance(&dbs, to).await,
+        if accepted { amount } else { 0 }
+    );
+    accepted
+}
+
+/// Alice's secret key, from [`PRIV_KEY`].
+fn alice_key() -> SecretKey {
+    SecretKey::from_slice(&hex::decode(PRIV_KEY).unwrap()).unwrap()
+}
+
+/// Compiles the token contract.
+/// This requires `pint` be available on PATH.
+async fn compile_token() -> essential_types::contract::Contract {
+    compile_pint_project(concat!(env!("CARGO_MANIFEST_DIR"), "/../pint/token").into())
+        .await
+        .unwrap()
+}
+
+/// Creates new databases with the token balances seeded at genesis and the
+/// contracts deployed.
+async fn new_token_dbs(
+    balances: &[([Word; 4], Word)],
+    contracts: &[&essential_types::contract::Contract],
+) -> utils::db::Dbs {
+    let genesis =
+        balances
+            .iter()
+            .fold(utils::db::Genesis::default(), |genesis, &(key, balance)| {
+                genesis.insert(
+                    token::token::ADDRESS,
+                    token::balance_key(key),
+                    vec![balance],
+                )
+            });
+    let dbs = utils::db::new_dbs_with_genesis(&genesis).await;
+    for contract in contracts {
+        utils::deploy::deploy_contract(&dbs, contract)
+            .await
+            .unwrap();
+    }
+    dbs
+}
+
+/// Submits to the builder DB directly, standing in for the builder API.
+struct LocalBuilder<'a>(&'a utils::db::Dbs);
+
+impl token::airdrop::Builder for LocalBuilder<'_> {
+    async fn submit(
+        &self,
+        solution: &essential_types::solution::Solution,
+    ) -> anyhow::Result<ContentAddress> {
+        utils::builder::submit(&self.0.builder, solution.clone()).await
+    }
+
+    async fn latest_failure(&self, solution: &ContentAddress) -> anyhow::Result<Option<String>> {
+        let failures = self
+            .0
+            .builder
+            .latest_solution_failures(solution.clone(), 1)
+            .await?;
+        Ok(failures.first().map(|f| f.err_msg.to_string()))
+    }
 }
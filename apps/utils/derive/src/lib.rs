@@ -0,0 +1,329 @@
+//! Derive macros for the `essential_app_utils::inputs` traits.
+//!
+//! These are re-exported from `essential_app_utils::inputs` and should be used from there.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, LitStr, Path, Type,
+};
+
+/// Derive `WriteDecVars` and `WordSize`, matching Pint's layout for the equivalent type.
+///
+/// Structs and tuple structs flatten all of their fields, in order, into a single slot.
+///
+/// Enums are laid out like Pint unions: the variant index as a tag word, then
+/// the variant's fields, then zero padding up to the size of the largest variant.
+#[proc_macro_derive(WriteDecVars)]
+pub fn derive_write_dec_vars(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    write_dec_vars(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derive `Encode`, converting a type into its generated ABI equivalent.
+///
+/// Structs encode to a tuple of their encoded fields. A struct with a single
+/// field encodes to that field's encoding.
+///
+/// Enums must name the ABI type they encode into, which must have variants of
+/// the same names:
+///
+/// ```ignore
+/// #[derive(Encode)]
+/// #[encode(into = "token::BurnAuth")]
+/// enum BurnAuth {
+///     Signed(RecoverableSignature),
+///     Predicate(PredicateAddress),
+/// }
+/// ```
+#[proc_macro_derive(Encode, attributes(encode))]
+pub fn derive_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    encode(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derive `Decode`, the inverse of the `WriteDecVars` layout.
+///
+/// This relies on the `WordSize` impl generated by `#[derive(WriteDecVars)]`.
+/// Unknown enum tags and non-zero union padding are errors.
+#[proc_macro_derive(Decode)]
+pub fn derive_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    decode(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn write_dec_vars(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let inputs = quote!(::essential_app_utils::inputs);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let (write, size) = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, bindings) = destructure(&data.fields);
+            let tys = field_types(&data.fields);
+            let write = quote! {
+                let Self #pattern = self;
+                let mut slot = ::std::vec::Vec::new();
+                #(#inputs::WriteDecVars::write_dec_var(#bindings, &mut slot);)*
+                decision_variables.push(slot.into_iter().flatten().collect());
+            };
+            let size = quote!(0 #(+ <#tys as #inputs::WordSize>::WORD_SIZE)*);
+            (write, size)
+        }
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            let mut sizes = Vec::new();
+            for (tag, variant) in data.variants.iter().enumerate() {
+                let tag = tag as i64;
+                let ident = &variant.ident;
+                let (pattern, bindings) = destructure(&variant.fields);
+                let tys = field_types(&variant.fields);
+                arms.push(quote! {
+                    Self::#ident #pattern => {
+                        #(#inputs::WriteDecVars::write_dec_var(#bindings, &mut slot);)*
+                        #tag
+                    }
+                });
+                sizes.push(quote!(0 #(+ <#tys as #inputs::WordSize>::WORD_SIZE)*));
+            }
+            let write = quote! {
+                let mut slot = ::std::vec::Vec::new();
+                let tag: #inputs::__private::Word = match self {
+                    #(#arms)*
+                };
+                let mut words = ::std::vec![tag];
+                words.extend(slot.into_iter().flatten());
+                words.resize(<Self as #inputs::WordSize>::WORD_SIZE, 0);
+                decision_variables.push(words);
+            };
+            let size = quote!(1 + #inputs::max_word_size(&[#(#sizes),*]));
+            (write, size)
+        }
+        Data::Union(_) => {
+            return Err(Error::new(
+                input.span(),
+                "`WriteDecVars` cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics #inputs::WriteDecVars for #name #ty_generics #where_clause {
+            fn write_dec_var(
+                &self,
+                decision_variables: &mut ::std::vec::Vec<#inputs::__private::Value>,
+            ) {
+                #write
+            }
+        }
+
+        impl #impl_generics #inputs::WordSize for #name #ty_generics #where_clause {
+            const WORD_SIZE: usize = #size;
+        }
+    })
+}
+
+fn encode(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let inputs = quote!(::essential_app_utils::inputs);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let into = encode_into(&input)?;
+
+    let (output, body) = match (&input.data, into) {
+        (Data::Struct(data), None) => {
+            let (pattern, bindings) = destructure(&data.fields);
+            let tys = field_types(&data.fields);
+            let (output, encoded) = if tys.len() == 1 {
+                (
+                    quote!(<#(#tys)* as #inputs::Encode>::Output),
+                    quote!(#(#inputs::Encode::encode(#bindings))*),
+                )
+            } else {
+                (
+                    quote!((#(<#tys as #inputs::Encode>::Output,)*)),
+                    quote!((#(#inputs::Encode::encode(#bindings),)*)),
+                )
+            };
+            let body = quote! {
+                let Self #pattern = self;
+                #encoded
+            };
+            (output, body)
+        }
+        (Data::Struct(_), Some(_)) => {
+            return Err(Error::new(
+                input.span(),
+                "`#[encode(into = ..)]` is only supported on enums",
+            ))
+        }
+        (Data::Enum(data), Some(into)) => {
+            let arms = data.variants.iter().map(|variant| {
+                let ident = &variant.ident;
+                let (pattern, bindings) = destructure(&variant.fields);
+                let encoded = match bindings.len() {
+                    0 => quote!(#into::#ident),
+                    1 => quote!(#into::#ident(#(#inputs::Encode::encode(#bindings))*)),
+                    _ => quote!(#into::#ident((#(#inputs::Encode::encode(#bindings),)*))),
+                };
+                quote!(Self::#ident #pattern => #encoded,)
+            });
+            let body = quote! {
+                match self {
+                    #(#arms)*
+                }
+            };
+            (quote!(#into), body)
+        }
+        (Data::Enum(_), None) => {
+            return Err(Error::new(
+                input.span(),
+                "deriving `Encode` for an enum requires `#[encode(into = \"AbiType\")]`",
+            ))
+        }
+        (Data::Union(_), _) => {
+            return Err(Error::new(
+                input.span(),
+                "`Encode` cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics #inputs::Encode for #name #ty_generics #where_clause {
+            type Output = #output;
+
+            fn encode(&self) -> Self::Output {
+                #body
+            }
+        }
+    })
+}
+
+fn decode(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let inputs = quote!(::essential_app_utils::inputs);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let construct = construct(&data.fields);
+            quote! {
+                let mut words = words;
+                Ok(Self #construct)
+            }
+        }
+        Data::Enum(data) => {
+            let ty = name.to_string();
+            let arms = data.variants.iter().enumerate().map(|(tag, variant)| {
+                let tag = tag as i64;
+                let ident = &variant.ident;
+                let construct = construct(&variant.fields);
+                quote! {
+                    #tag => {
+                        let value = Self::#ident #construct;
+                        #inputs::check_padding(#ty, words)?;
+                        Ok(value)
+                    }
+                }
+            });
+            quote! {
+                let mut words = &words[1..];
+                match words_tag {
+                    #(#arms)*
+                    tag => Err(#inputs::unknown_tag(#ty, tag)),
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new(
+                input.span(),
+                "`Decode` cannot be derived for unions",
+            ))
+        }
+    };
+    let tag = match &input.data {
+        Data::Enum(_) => quote!(let words_tag = words[0];),
+        _ => quote!(),
+    };
+
+    Ok(quote! {
+        impl #impl_generics #inputs::Decode for #name #ty_generics #where_clause {
+            #[allow(unused_mut, unused_variables)]
+            fn decode(
+                words: &[#inputs::__private::Word],
+            ) -> ::std::result::Result<Self, #inputs::__private::Error> {
+                #inputs::check_word_size::<Self>(words)?;
+                #tag
+                #body
+            }
+        }
+    })
+}
+
+/// The fields of a struct or variant, each decoded in order from `words`.
+fn construct(fields: &Fields) -> TokenStream2 {
+    let inputs = quote!(::essential_app_utils::inputs);
+    match fields {
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|f| &f.ident);
+            quote!({ #(#names: #inputs::decode_next(&mut words)?),* })
+        }
+        Fields::Unnamed(fields) => {
+            let decoded = fields
+                .unnamed
+                .iter()
+                .map(|_| quote!(#inputs::decode_next(&mut words)?));
+            quote!(( #(#decoded),* ))
+        }
+        Fields::Unit => quote!(),
+    }
+}
+
+/// Parse the ABI type from `#[encode(into = "Path")]`, if present.
+fn encode_into(input: &DeriveInput) -> syn::Result<Option<Path>> {
+    let mut into = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("encode")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("into") {
+                let lit: LitStr = meta.value()?.parse()?;
+                into = Some(lit.parse::<Path>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `into`"))
+            }
+        })?;
+    }
+    Ok(into)
+}
+
+/// A pattern binding every field, along with the bound identifiers in field order.
+fn destructure(fields: &Fields) -> (TokenStream2, Vec<proc_macro2::Ident>) {
+    match fields {
+        Fields::Named(fields) => {
+            let names: Vec<_> = fields
+                .named
+                .iter()
+                .map(|f| f.ident.clone().expect("named field"))
+                .collect();
+            (quote!({ #(#names),* }), names)
+        }
+        Fields::Unnamed(fields) => {
+            let names: Vec<_> = (0..fields.unnamed.len())
+                .map(|i| format_ident!("f{}", i))
+                .collect();
+            (quote!(( #(#names),* )), names)
+        }
+        Fields::Unit => (quote!(), vec![]),
+    }
+}
+
+fn field_types(fields: &Fields) -> Vec<&Type> {
+    fields.iter().map(|f| &f.ty).collect()
+}
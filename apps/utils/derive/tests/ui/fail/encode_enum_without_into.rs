@@ -0,0 +1,9 @@
+use essential_app_utils::inputs::Encode;
+
+#[derive(Encode)]
+enum Auth {
+    None,
+    Signed(i64),
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use essential_app_utils::inputs::Encode;
+
+#[derive(Encode)]
+#[encode(into = "Other")]
+struct Amount(i64);
+
+struct Other;
+
+fn main() {}
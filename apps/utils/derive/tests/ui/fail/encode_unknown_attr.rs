@@ -0,0 +1,13 @@
+use essential_app_utils::inputs::Encode;
+
+#[derive(Encode)]
+#[encode(from = "Other")]
+enum Auth {
+    None,
+}
+
+enum Other {
+    None,
+}
+
+fn main() {}
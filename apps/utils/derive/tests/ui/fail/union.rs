@@ -0,0 +1,9 @@
+use essential_app_utils::inputs::{Decode, Encode, WriteDecVars};
+
+#[derive(WriteDecVars, Decode, Encode)]
+union Word {
+    a: i64,
+    b: u64,
+}
+
+fn main() {}
@@ -0,0 +1,39 @@
+use essential_app_utils::inputs::{Encode, Int};
+
+mod abi {
+    #[derive(Debug, PartialEq)]
+    pub enum Auth {
+        None,
+        Signed(i64),
+        Pair((i64, bool)),
+    }
+}
+
+#[derive(Encode)]
+#[encode(into = "abi::Auth")]
+enum Auth {
+    None,
+    Signed(Int),
+    Pair(Int, bool),
+}
+
+#[derive(Encode)]
+struct Amount(Int);
+
+#[derive(Encode)]
+struct Transfer {
+    to: [i64; 4],
+    amount: Int,
+}
+
+fn main() {
+    assert_eq!(Auth::None.encode(), abi::Auth::None);
+    assert_eq!(Auth::Signed(Int(1)).encode(), abi::Auth::Signed(1));
+    assert_eq!(Auth::Pair(Int(2), true).encode(), abi::Auth::Pair((2, true)));
+    assert_eq!(Amount(Int(3)).encode(), 3);
+    let transfer = Transfer {
+        to: [1, 2, 3, 4],
+        amount: Int(5),
+    };
+    assert_eq!(transfer.encode(), ([1, 2, 3, 4], 5));
+}
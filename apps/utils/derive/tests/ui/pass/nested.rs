@@ -0,0 +1,27 @@
+use essential_app_utils::inputs::{to_words, Decode, WordSize, WriteDecVars};
+
+#[derive(Debug, PartialEq, WriteDecVars, Decode)]
+struct Pair(i64, [i64; 2]);
+
+#[derive(Debug, PartialEq, WriteDecVars, Decode)]
+enum Maybe {
+    Nothing,
+    Just(Pair),
+    Named { flag: bool },
+}
+
+fn main() {
+    assert_eq!(Pair::WORD_SIZE, 3);
+    let pair = Pair(1, [2, 3]);
+    assert_eq!(to_words(&pair), vec![1, 2, 3]);
+    assert_eq!(Pair::decode(&[1, 2, 3]).unwrap(), pair);
+
+    assert_eq!(Maybe::WORD_SIZE, 4);
+    assert_eq!(to_words(&Maybe::Nothing), vec![0, 0, 0, 0]);
+    let just = Maybe::Just(Pair(4, [5, 6]));
+    assert_eq!(to_words(&just), vec![1, 4, 5, 6]);
+    assert_eq!(Maybe::decode(&[1, 4, 5, 6]).unwrap(), just);
+    let named = Maybe::Named { flag: true };
+    assert_eq!(to_words(&named), vec![2, 1, 0, 0]);
+    assert_eq!(Maybe::decode(&[2, 1, 0, 0]).unwrap(), named);
+}
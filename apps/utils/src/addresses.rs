@@ -1,10 +1,17 @@
 use essential_types::{PredicateAddress, Word};
 
+/// The account key owned by a predicate, as checked by `std::auth::@check_if_predicate_owns`.
+///
+/// This is the sha256 of the predicate address words, `{ contract, addr }`.
+pub fn predicate_owned_key(predicate: &PredicateAddress) -> [Word; 4] {
+    let contract = essential_types::convert::word_4_from_u8_32(predicate.contract.0);
+    let addr = essential_types::convert::word_4_from_u8_32(predicate.predicate.0);
+    let mut words = contract.to_vec();
+    words.extend_from_slice(&addr);
+    essential_types::convert::word_4_from_u8_32(essential_hash::hash_words(&words))
+}
+
+#[deprecated(note = "use `predicate_owned_key`")]
 pub fn contract_hash(contract: &PredicateAddress) -> [Word; 4] {
-    let set_hash = essential_types::convert::word_4_from_u8_32(contract.contract.0);
-    let predicate_hash = essential_types::convert::word_4_from_u8_32(contract.predicate.0);
-    let mut words = set_hash.to_vec();
-    words.extend_from_slice(&predicate_hash);
-    let contract_hash = essential_hash::hash_words(&words);
-    essential_types::convert::word_4_from_u8_32(contract_hash)
+    predicate_owned_key(contract)
 }
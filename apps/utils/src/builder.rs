@@ -1,22 +1,224 @@
 use essential_builder::{error::BuildBlockError, SolutionsSummary};
-use essential_types::{solution::Solution, ContentAddress};
+use essential_types::{solution::Solution, ContentAddress, Key, Word};
+use std::{collections::HashSet, ops::Range, sync::Arc, time::Duration};
 
-use crate::db::Dbs;
+use crate::{
+    clock::{Clock, SystemClock},
+    db::Dbs,
+};
 
-pub async fn build_default(dbs: &Dbs) -> Result<SolutionsSummary, BuildBlockError> {
-    essential_builder::build_block_fifo(&dbs.builder, &dbs.node, &Default::default()).await
+pub use auto::{AutoBuilder, Trigger};
+pub use essential_builder::Config;
+
+mod auto;
+
+const MAX_TIMESTAMP_RANGE: Range<Duration> =
+    Duration::from_secs(0)..Duration::from_secs(i64::MAX as _);
+
+/// A solution submission waiting in the builder's pool.
+#[derive(Clone, Debug)]
+pub struct Pending {
+    pub address: ContentAddress,
+    pub solution: Solution,
+    /// The time the solution was submitted, since the unix epoch.
+    pub submitted: Duration,
+}
+
+/// The outcome of a [`BlockStrategy`] for a single block.
+#[derive(Clone, Debug, Default)]
+pub struct BlockPlan {
+    /// Solutions to attempt in the next block, in the order they are attempted.
+    pub attempt: Vec<Pending>,
+    /// Solutions left in the pool for a later block.
+    pub defer: Vec<Pending>,
+}
+
+/// Decides which pending solutions are attempted in the next block and in what order.
+pub trait BlockStrategy {
+    fn plan(&self, pending: Vec<Pending>) -> BlockPlan;
+}
+
+/// Attempt solutions in order of their submission timestamps.
+///
+/// Unlike [`build_with_config`], which follows pool insertion order, this
+/// respects timestamps from a [`Clock`], such as [`Dbs::clock`]. It is the
+/// strategy used by [`build_default`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fifo;
+
+/// Attempt solutions with the highest fee hint first.
+///
+/// Solutions with equal fee hints are attempted in the order they were submitted.
+#[derive(Clone, Copy, Debug)]
+pub struct PriorityByFee<F>(pub F);
+
+/// Attempt solutions in the order they were submitted, deferring any solution
+/// that mutates a key already mutated by an earlier solution in the block.
+///
+/// Deferred solutions stay in the pool and are attempted against the
+/// resulting state when the next block is built.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConflictAware;
+
+/// Attempt only the given solutions, in the order they were submitted.
+///
+/// Every other solution stays in the pool for a later block.
+#[derive(Clone, Debug, Default)]
+pub struct Only(pub HashSet<ContentAddress>);
+
+impl BlockStrategy for Fifo {
+    fn plan(&self, mut pending: Vec<Pending>) -> BlockPlan {
+        pending.sort_by_key(|p| p.submitted);
+        BlockPlan {
+            attempt: pending,
+            defer: vec![],
+        }
+    }
+}
+
+impl<F> BlockStrategy for PriorityByFee<F>
+where
+    F: Fn(&Solution) -> Word,
+{
+    fn plan(&self, pending: Vec<Pending>) -> BlockPlan {
+        let mut plan = Fifo.plan(pending);
+        plan.attempt
+            .sort_by_key(|p| std::cmp::Reverse((self.0)(&p.solution)));
+        plan
+    }
+}
+
+impl BlockStrategy for ConflictAware {
+    fn plan(&self, pending: Vec<Pending>) -> BlockPlan {
+        let mut claimed: HashSet<(ContentAddress, Key)> = HashSet::new();
+        let mut plan = BlockPlan::default();
+        for p in Fifo.plan(pending).attempt {
+            let keys: HashSet<_> = p
+                .solution
+                .data
+                .iter()
+                .flat_map(|data| {
+                    data.state_mutations
+                        .iter()
+                        .map(|m| (data.predicate_to_solve.contract.clone(), m.key.clone()))
+                })
+                .collect();
+            if keys.is_disjoint(&claimed) {
+                claimed.extend(keys);
+                plan.attempt.push(p);
+            } else {
+                plan.defer.push(p);
+            }
+        }
+        plan
+    }
+}
+
+impl BlockStrategy for Only {
+    fn plan(&self, pending: Vec<Pending>) -> BlockPlan {
+        let (attempt, defer) = Fifo
+            .plan(pending)
+            .attempt
+            .into_iter()
+            .partition(|p| self.0.contains(&p.address));
+        BlockPlan { attempt, defer }
+    }
+}
+
+/// The default config, using the contract registry and block state from [`Dbs::big_bang`].
+pub fn default_config(dbs: &Dbs) -> Config {
+    Config {
+        contract_registry: dbs.big_bang.contract_registry.clone(),
+        block_state: dbs.big_bang.block_state.clone(),
+        ..Default::default()
+    }
+}
+
+/// Build a block with [`default_config`], attempting solutions in order of
+/// their submission timestamps.
+///
+/// Solutions submitted with the same timestamp are attempted in the order they
+/// were inserted. Use [`Dbs::with_clock`] or [`submit_with_clock`] to control
+/// the timestamps. The block's own timestamp is always taken from the system clock.
+pub async fn build_default(dbs: &Dbs) -> anyhow::Result<SolutionsSummary> {
+    build_with(dbs, &default_config(dbs), &Fifo).await
+}
+
+/// Build a block, attempting solutions in the order they were inserted into the pool.
+///
+/// The builder lists the pool with a query that has no `ORDER BY`, so in
+/// practice solutions come back in SQLite rowid order, which is insertion
+/// order. Submission timestamps are not used for ordering.
+pub async fn build_with_config(
+    dbs: &Dbs,
+    config: &Config,
+) -> Result<SolutionsSummary, BuildBlockError> {
+    essential_builder::build_block_fifo(&dbs.builder, &dbs.node, config).await
+}
+
+/// Build a block, letting `strategy` choose which pending solutions are
+/// attempted and in what order.
+///
+/// The pool is rewritten in the planned order before building: every pending
+/// solution is deleted and the planned ones are re-inserted in order, which
+/// relies on [`build_with_config`] attempting them in insertion order. Deferred
+/// solutions are resubmitted with their original timestamps afterwards, even
+/// if the build fails.
+///
+/// Strategies receive the pool in insertion order, so strategies that sort by
+/// timestamp keep insertion order for equal timestamps.
+pub async fn build_with(
+    dbs: &Dbs,
+    config: &Config,
+    strategy: &impl BlockStrategy,
+) -> anyhow::Result<SolutionsSummary> {
+    let pending: Vec<_> = dbs
+        .builder
+        .list_solutions(MAX_TIMESTAMP_RANGE, i64::MAX)
+        .await?
+        .into_iter()
+        .map(|(address, solution, submitted)| Pending {
+            address,
+            solution,
+            submitted,
+        })
+        .collect();
+    let cas: HashSet<_> = pending.iter().map(|p| p.address.clone()).collect();
+    dbs.builder.delete_solutions(cas).await?;
+
+    let plan = strategy.plan(pending);
+    resubmit(&dbs.builder, plan.attempt).await?;
+    let result = build_with_config(dbs, config).await;
+    resubmit(&dbs.builder, plan.defer).await?;
+    Ok(result?)
+}
+
+async fn resubmit(
+    builder: &essential_builder_db::ConnectionPool,
+    pending: Vec<Pending>,
+) -> anyhow::Result<()> {
+    for p in pending {
+        builder
+            .insert_solution_submission(Arc::new(p.solution), p.submitted)
+            .await?;
+    }
+    Ok(())
 }
 
 pub async fn submit(
     builder: &essential_builder_db::ConnectionPool,
     solution: Solution,
+) -> anyhow::Result<ContentAddress> {
+    submit_with_clock(builder, solution, &SystemClock).await
+}
+
+/// Submit a solution, stamping the submission with the given clock.
+pub async fn submit_with_clock(
+    builder: &essential_builder_db::ConnectionPool,
+    solution: Solution,
+    clock: &impl Clock,
 ) -> anyhow::Result<ContentAddress> {
     Ok(builder
-        .insert_solution_submission(
-            std::sync::Arc::new(solution),
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap(),
-        )
+        .insert_solution_submission(Arc::new(solution), clock.now())
         .await?)
 }
@@ -0,0 +1,135 @@
+use super::{build_with, Config, Fifo, MAX_TIMESTAMP_RANGE};
+use crate::db::Dbs;
+use anyhow::bail;
+use essential_builder::SolutionsSummary;
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::{sync::watch, task::JoinHandle};
+
+/// When an [`AutoBuilder`] builds a block.
+#[derive(Clone, Copy, Debug)]
+pub enum Trigger {
+    /// Build a block every period, whether or not there are pending solutions.
+    Interval(Duration),
+    /// Check the builder's pool every `poll` period and build a block when it
+    /// has pending solutions.
+    OnSubmission { poll: Duration },
+}
+
+/// Builds blocks in the background against a [`Dbs`].
+///
+/// Like [`build_default`](super::build_default), solutions are attempted in
+/// order of their submission timestamps.
+///
+/// Dropping the handle stops the background task once any block in progress
+/// is built. The task is never cancelled mid-build, so the builder's pool is
+/// left intact. Use [`AutoBuilder::shutdown`] to also wait for it to stop.
+pub struct AutoBuilder {
+    blocks: watch::Receiver<Status>,
+    shutdown: watch::Sender<bool>,
+    handle: Option<JoinHandle<anyhow::Result<()>>>,
+}
+
+/// The latest news from the build loop.
+#[derive(Clone)]
+enum Status {
+    /// No block has been built yet.
+    Started,
+    /// A block was built.
+    Built(Arc<SolutionsSummary>),
+    /// The loop stopped, with the error that stopped it, if any.
+    Stopped(Option<Arc<str>>),
+}
+
+impl AutoBuilder {
+    /// Spawn the build loop on the current tokio runtime.
+    pub fn spawn(dbs: Dbs, trigger: Trigger, config: Config) -> Self {
+        let (blocks_tx, blocks) = watch::channel(Status::Started);
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let handle = tokio::spawn(async move {
+            let result = run(dbs, trigger, config, &blocks_tx, shutdown_rx).await;
+            let err = result.as_ref().err().map(|e| format!("{:#}", e).into());
+            blocks_tx.send_replace(Status::Stopped(err));
+            result
+        });
+        Self {
+            blocks,
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+
+    /// A future that resolves with the summary of the next block built.
+    ///
+    /// Only blocks built after this is called are observed, so call it before
+    /// submitting the solutions you want to wait on.
+    ///
+    /// Fails if the build loop stops first, with the loop's error if it failed.
+    pub fn next_block(&self) -> impl Future<Output = anyhow::Result<Arc<SolutionsSummary>>> {
+        let mut rx = self.blocks.clone();
+        // A loop that has already stopped won't send again.
+        let stopped = matches!(*rx.borrow(), Status::Stopped(_));
+        if !stopped {
+            rx.mark_unchanged();
+        }
+        async move {
+            if rx.changed().await.is_err() {
+                bail!("auto builder stopped");
+            }
+            match rx.borrow_and_update().clone() {
+                Status::Built(summary) => Ok(summary),
+                Status::Stopped(Some(err)) => bail!("auto builder failed: {}", err),
+                Status::Started | Status::Stopped(None) => bail!("auto builder stopped"),
+            }
+        }
+    }
+
+    /// Stop the build loop and wait for it to finish, returning the first
+    /// error the loop encountered, if any.
+    pub async fn shutdown(mut self) -> anyhow::Result<()> {
+        let _ = self.shutdown.send(true);
+        match self.handle.take() {
+            Some(handle) => handle.await?,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for AutoBuilder {
+    fn drop(&mut self) {
+        // Detach the task rather than aborting it, which could cancel it
+        // between emptying the pool and refilling it in `build_with`.
+        let _ = self.shutdown.send(true);
+    }
+}
+
+async fn run(
+    dbs: Dbs,
+    trigger: Trigger,
+    config: Config,
+    blocks: &watch::Sender<Status>,
+    mut shutdown: watch::Receiver<bool>,
+) -> anyhow::Result<()> {
+    let period = match trigger {
+        Trigger::Interval(period) => period,
+        Trigger::OnSubmission { poll } => poll,
+    };
+    let mut interval = tokio::time::interval(period);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        if *shutdown.borrow_and_update() {
+            return Ok(());
+        }
+        tokio::select! {
+            _ = interval.tick() => (),
+            _ = shutdown.changed() => return Ok(()),
+        }
+        if let Trigger::OnSubmission { .. } = trigger {
+            let pending = dbs.builder.list_submissions(MAX_TIMESTAMP_RANGE, 1).await?;
+            if pending.is_empty() {
+                continue;
+            }
+        }
+        let summary = build_with(&dbs, &config, &Fifo).await?;
+        blocks.send_replace(Status::Built(Arc::new(summary)));
+    }
+}
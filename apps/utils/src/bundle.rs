@@ -0,0 +1,77 @@
+use crate::inputs::Instance;
+use anyhow::bail;
+use essential_types::{
+    solution::{Solution, SolutionData},
+    ContentAddress, Key, PredicateAddress, Word,
+};
+use std::collections::HashMap;
+
+/// Composes solution data from several predicates into a single atomic solution.
+///
+/// Each piece of data is solved at its path, which is its index in the
+/// solution. Predicates can refer to each other by that path, for example with
+/// Pint's `__predicate_at`.
+#[derive(Clone, Debug, Default)]
+pub struct SolutionBundle {
+    data: Vec<SolutionData>,
+}
+
+impl SolutionBundle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The instance the next pushed data will be solved at.
+    ///
+    /// Useful when earlier data has to reference a predicate that is pushed later.
+    pub fn next_instance(&self, address: PredicateAddress) -> Instance {
+        Instance {
+            address,
+            path: self.data.len() as Word,
+        }
+    }
+
+    /// Add data to the bundle, returning the instance it is solved at.
+    pub fn push(&mut self, data: SolutionData) -> Instance {
+        let instance = self.next_instance(data.predicate_to_solve.clone());
+        self.data.push(data);
+        instance
+    }
+
+    /// Add all of a solution's data to the bundle, returning the instances in order.
+    pub fn extend(&mut self, solution: Solution) -> Vec<Instance> {
+        solution
+            .data
+            .into_iter()
+            .map(|data| self.push(data))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Build the solution, failing if more than one piece of data mutates the same key.
+    pub fn build(self) -> anyhow::Result<Solution> {
+        let mut mutated: HashMap<(&ContentAddress, &Key), usize> = HashMap::new();
+        for (path, data) in self.data.iter().enumerate() {
+            let contract = &data.predicate_to_solve.contract;
+            for mutation in &data.state_mutations {
+                if let Some(other) = mutated.insert((contract, &mutation.key), path) {
+                    bail!(
+                        "Conflicting mutations at paths {} and {} of contract {} key {:?}",
+                        other,
+                        path,
+                        contract,
+                        mutation.key
+                    );
+                }
+            }
+        }
+        Ok(Solution { data: self.data })
+    }
+}
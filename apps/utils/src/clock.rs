@@ -0,0 +1,92 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// A source of submission timestamps, as a duration since the unix epoch.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Duration;
+}
+
+/// The system wall clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+/// A clock that only moves when it is told to.
+///
+/// Clones share the same time, so a test can hold one handle while the code
+/// under test holds another.
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock(Arc<Mutex<Duration>>);
+
+/// A clock that advances by a fixed step every time it is read.
+///
+/// Each call to [`Clock::now`] returns a distinct, increasing timestamp, which
+/// makes submission order reproducible without any manual stepping.
+#[derive(Clone, Debug)]
+pub struct SteppingClock {
+    next: Arc<Mutex<Duration>>,
+    step: Duration,
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
+    }
+}
+
+impl ManualClock {
+    pub fn new(start: Duration) -> Self {
+        Self(Arc::new(Mutex::new(start)))
+    }
+
+    pub fn set(&self, time: Duration) {
+        *self.0.lock().unwrap() = time;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.0.lock().unwrap() += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.0.lock().unwrap()
+    }
+}
+
+impl SteppingClock {
+    pub fn new(start: Duration, step: Duration) -> Self {
+        Self {
+            next: Arc::new(Mutex::new(start)),
+            step,
+        }
+    }
+}
+
+impl Default for SteppingClock {
+    fn default() -> Self {
+        Self::new(Duration::ZERO, Duration::from_secs(1))
+    }
+}
+
+impl Clock for SteppingClock {
+    fn now(&self) -> Duration {
+        let mut next = self.next.lock().unwrap();
+        let now = *next;
+        *next += self.step;
+        now
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
@@ -1,6 +1,10 @@
-use anyhow::{bail, ensure};
-use essential_types::{contract::Contract, predicate::Predicate};
-use std::path::PathBuf;
+use crate::deploy::{Deployment, Deployments};
+use anyhow::{anyhow, bail, ensure};
+use essential_types::{contract::Contract, predicate::Predicate, ContentAddress, PredicateAddress};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 use tokio::{
     io::{AsyncReadExt, BufReader},
     process::Command,
@@ -19,6 +23,14 @@ pub struct NamedContract {
     pub source: String,
 }
 
+/// The address of a contract and each of its predicates by name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractAddresses {
+    pub contract: ContentAddress,
+    /// Predicate addresses keyed by name, without the leading `::`.
+    pub predicates: BTreeMap<String, PredicateAddress>,
+}
+
 pub async fn compile_pint_project(path: PathBuf) -> anyhow::Result<Contract> {
     let (bytes, _, _) = compile_pint_project_inner(path, false).await?;
     let contract: Contract = serde_json::from_slice(&bytes)?;
@@ -53,16 +65,7 @@ pub async fn compile_pint_project_inner(
         "pint.toml not found: {:?}",
         pint_manifest_path
     );
-
-    let pint_toml = tokio::fs::read_to_string(&pint_manifest_path).await?;
-    let pint_toml = pint_toml.parse::<toml::Table>()?;
-    let Some(name) = pint_toml
-        .get("package")
-        .and_then(|p| p.as_table()?.get("name"))
-        .and_then(|name| name.as_str())
-    else {
-        bail!("name not found in pint.toml")
-    };
+    let name = package_name(&pint_manifest_path).await?;
 
     let output = if include_source {
         Command::new("pint")
@@ -98,6 +101,47 @@ pub async fn compile_pint_project_inner(
         String::new()
     };
 
+    let (bytes, abi_bytes) = read_pint_out(&path, &name).await?;
+    Ok((bytes, abi_bytes, source))
+}
+
+/// The output of `pint --version`.
+pub async fn pint_version() -> anyhow::Result<String> {
+    let output = Command::new("pint").arg("--version").output().await?;
+    ensure!(
+        output.status.success(),
+        "pint failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Read a project's contract and ABI from its `out/debug` directory without compiling.
+pub async fn load_pint_project_and_abi(
+    path: PathBuf,
+) -> anyhow::Result<(Contract, serde_json::Value)> {
+    let name = package_name(&path.join("pint.toml")).await?;
+    let (bytes, abi) = read_pint_out(&path, &name).await?;
+    let contract: Contract = serde_json::from_slice(&bytes)?;
+    let abi: serde_json::Value = serde_json::from_slice(&abi)?;
+    Ok((contract, abi))
+}
+
+/// The package name in a `pint.toml`.
+pub async fn package_name(pint_manifest_path: &Path) -> anyhow::Result<String> {
+    let pint_toml = tokio::fs::read_to_string(pint_manifest_path).await?;
+    let pint_toml = pint_toml.parse::<toml::Table>()?;
+    let Some(name) = pint_toml
+        .get("package")
+        .and_then(|p| p.as_table()?.get("name"))
+        .and_then(|name| name.as_str())
+    else {
+        bail!("name not found in pint.toml")
+    };
+    Ok(name.to_string())
+}
+
+async fn read_pint_out(path: &Path, name: &str) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
     let file = tokio::fs::File::open(
         path.join("out")
             .join("debug")
@@ -118,7 +162,7 @@ pub async fn compile_pint_project_inner(
     let mut reader = BufReader::new(abi_file);
     reader.read_to_end(&mut abi_bytes).await?;
 
-    Ok((bytes, abi_bytes, source))
+    Ok((bytes, abi_bytes))
 }
 
 pub async fn get_contracts(
@@ -130,13 +174,7 @@ pub async fn get_contracts(
     for name in contracts {
         let (contract, abi, source) =
             compile_pint_project_and_abi_with_source(pint_directory.clone().join(name)).await?;
-        let predicate_names = abi["predicates"]
-            .as_array()
-            .unwrap()
-            .iter()
-            .filter(|predicate| !predicate["name"].as_str().unwrap().is_empty())
-            .map(|predicate| predicate["name"].as_str().unwrap().to_string())
-            .collect();
+        let predicate_names = predicate_names(&abi);
         let contract = NamedContract {
             name: name.to_string(),
             contract,
@@ -148,6 +186,98 @@ pub async fn get_contracts(
     Ok(NamedContracts { contracts: out })
 }
 
+/// The names of the predicates in an ABI, in the same order as the contract's predicates.
+fn predicate_names(abi: &serde_json::Value) -> Vec<String> {
+    abi["predicates"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|predicate| !predicate["name"].as_str().unwrap().is_empty())
+        .map(|predicate| predicate["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn normalize_predicate_name(name: &str) -> String {
+    name.trim().trim_start_matches("::").to_lowercase()
+}
+
+impl ContractAddresses {
+    /// Compile the project and resolve its addresses.
+    pub async fn compile(path: PathBuf) -> anyhow::Result<Self> {
+        let (contract, abi) = compile_pint_project_and_abi(path).await?;
+        Ok(Self::new(&contract, &abi))
+    }
+
+    /// Resolve the addresses of a project that has already been built, without compiling.
+    pub async fn load(path: PathBuf) -> anyhow::Result<Self> {
+        let (contract, abi) = load_pint_project_and_abi(path).await?;
+        Ok(Self::new(&contract, &abi))
+    }
+
+    /// Resolve the project's addresses from a deployment manifest if one is
+    /// given, otherwise by compiling it.
+    pub async fn resolve(path: PathBuf, manifest: Option<&Path>) -> anyhow::Result<Self> {
+        let Some(manifest) = manifest else {
+            return Self::compile(path).await;
+        };
+        let name = package_name(&path.join("pint.toml")).await?;
+        let deployments = Deployments::load(manifest).await?;
+        let deployment = deployments
+            .get(&name)
+            .ok_or_else(|| anyhow!("{} not found in {}", name, manifest.display()))?;
+        Ok(Self::from(deployment))
+    }
+
+    pub fn new(contract: &Contract, abi: &serde_json::Value) -> Self {
+        let contract_address = essential_hash::contract_addr::from_contract(contract);
+        let predicates = predicate_names(abi)
+            .into_iter()
+            .zip(&contract.predicates)
+            .map(|(name, predicate)| {
+                let address = PredicateAddress {
+                    contract: contract_address.clone(),
+                    predicate: essential_hash::content_addr(predicate),
+                };
+                (name.trim_start_matches("::").to_string(), address)
+            })
+            .collect();
+        Self {
+            contract: contract_address,
+            predicates,
+        }
+    }
+
+    /// The address of the named predicate, matched case-insensitively.
+    pub fn predicate(&self, name: &str) -> anyhow::Result<PredicateAddress> {
+        let name = normalize_predicate_name(name);
+        self.predicates
+            .iter()
+            .find(|(predicate, _)| normalize_predicate_name(predicate) == name)
+            .map(|(_, address)| address.clone())
+            .ok_or_else(|| anyhow!("predicate {} not found in contract {}", name, self.contract))
+    }
+}
+
+impl From<&Deployment> for ContractAddresses {
+    fn from(deployment: &Deployment) -> Self {
+        let predicates = deployment
+            .predicates
+            .iter()
+            .map(|(name, predicate)| {
+                let address = PredicateAddress {
+                    contract: deployment.contract.clone(),
+                    predicate: predicate.clone(),
+                };
+                (name.clone(), address)
+            })
+            .collect();
+        Self {
+            contract: deployment.contract.clone(),
+            predicates,
+        }
+    }
+}
+
 impl NamedContracts {
     pub fn get_contract(&self, name: &str) -> Option<&NamedContract> {
         self.contracts.iter().find(|contract| contract.name == name)
@@ -159,8 +289,7 @@ impl NamedContract {
         self.predicates
             .iter()
             .position(|predicate| {
-                predicate.trim().trim_start_matches("::").to_lowercase()
-                    == name.trim().trim_start_matches("::").to_lowercase()
+                normalize_predicate_name(predicate) == normalize_predicate_name(name)
             })
             .and_then(|pos| self.contract.predicates.get(pos))
     }
@@ -0,0 +1,126 @@
+use crate::signer::AccountSigner;
+use anyhow::Context;
+use clap::Args;
+use essential_sign::secp256k1::SecretKey;
+use essential_wallet::Wallet;
+use std::path::{Path, PathBuf};
+
+/// Where the credentials to unlock a wallet come from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CredentialSource {
+    /// Prompt for the password on the terminal.
+    Prompt,
+    /// Read the password from a file.
+    PasswordFile(PathBuf),
+    /// Read the password from an environment variable.
+    PasswordEnv(String),
+    /// Read a secp256k1 secret key as hex from a file and sign with it directly.
+    RawKeyFile(PathBuf),
+}
+
+/// Command line arguments for unlocking a wallet.
+///
+/// Flatten into an app's CLI so every app is unlocked the same way.
+/// Without any of the options the password is prompted for.
+#[derive(Args, Clone, Debug, Default)]
+pub struct Credentials {
+    /// Essential wallet directory.
+    /// If not set then a sensible default will be used (like ~/.essential-wallet).
+    #[arg(short, long, global = true)]
+    pub wallet: Option<PathBuf>,
+    /// Read the wallet password from a file instead of prompting for it.
+    #[arg(long, global = true, conflicts_with_all = ["password_env", "raw_key_file"])]
+    pub password_file: Option<PathBuf>,
+    /// Read the wallet password from an environment variable instead of prompting for it.
+    #[arg(
+        long,
+        global = true,
+        value_name = "VAR",
+        conflicts_with_all = ["password_file", "raw_key_file"]
+    )]
+    pub password_env: Option<String>,
+    /// Sign with a secp256k1 secret key read as hex from a file instead of using a wallet.
+    /// The key signs for whichever account name is given to the command.
+    #[arg(long, global = true, conflicts_with = "wallet")]
+    pub raw_key_file: Option<PathBuf>,
+}
+
+impl Credentials {
+    /// The source chosen by the arguments.
+    pub fn source(&self) -> CredentialSource {
+        if let Some(path) = &self.raw_key_file {
+            CredentialSource::RawKeyFile(path.clone())
+        } else if let Some(path) = &self.password_file {
+            CredentialSource::PasswordFile(path.clone())
+        } else if let Some(var) = &self.password_env {
+            CredentialSource::PasswordEnv(var.clone())
+        } else {
+            CredentialSource::Prompt
+        }
+    }
+
+    /// Opens a signer for `account`.
+    pub fn open_signer(&self, account: &str) -> anyhow::Result<AccountSigner> {
+        self.source().open_signer(self.wallet.clone(), account)
+    }
+}
+
+impl CredentialSource {
+    /// Opens a signer for `account` in the wallet in `dir`, or the default
+    /// wallet directory if `None`.
+    ///
+    /// A raw key signs directly, without opening a wallet.
+    pub fn open_signer(
+        &self,
+        dir: Option<PathBuf>,
+        account: &str,
+    ) -> anyhow::Result<AccountSigner> {
+        if let CredentialSource::RawKeyFile(path) = self {
+            return Ok(AccountSigner::Key(read_secret_key(path)?));
+        }
+        let pass = self.password()?;
+        let wallet = match dir {
+            Some(path) => Wallet::new(&pass, path)?,
+            None => Wallet::with_default_path(&pass)?,
+        };
+        Ok(AccountSigner::Wallet {
+            wallet,
+            account: account.to_string(),
+        })
+    }
+
+    /// The wallet password.
+    ///
+    /// A trailing newline in a password file is not part of the password.
+    pub fn password(&self) -> anyhow::Result<String> {
+        match self {
+            CredentialSource::Prompt => Ok(rpassword::prompt_password(
+                "Enter password to unlock wallet: ",
+            )?),
+            CredentialSource::PasswordFile(path) => {
+                let pass = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read password file {}", path.display()))?;
+                let pass = pass.strip_suffix('\n').unwrap_or(&pass);
+                Ok(pass.strip_suffix('\r').unwrap_or(pass).to_string())
+            }
+            CredentialSource::PasswordEnv(var) => {
+                std::env::var(var).with_context(|| format!("Failed to read password from ${}", var))
+            }
+            CredentialSource::RawKeyFile(_) => {
+                anyhow::bail!("A raw key file doesn't have a password")
+            }
+        }
+    }
+}
+
+/// Reads a secp256k1 secret key written as hex, with or without a `0x` prefix.
+pub fn read_secret_key(path: &Path) -> anyhow::Result<SecretKey> {
+    let hex_key = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read key file {}", path.display()))?;
+    let hex_key = hex_key.trim();
+    let hex_key = hex_key.strip_prefix("0x").unwrap_or(hex_key);
+    let bytes =
+        hex::decode(hex_key).with_context(|| format!("Key file {} is not hex", path.display()))?;
+    SecretKey::from_slice(&bytes)
+        .with_context(|| format!("Key file {} is not a secp256k1 secret key", path.display()))
+}
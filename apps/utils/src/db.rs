@@ -1,29 +1,178 @@
+use anyhow::bail;
 use essential_builder_db as builder_db;
 use essential_node as node;
+use essential_node_types::BigBang;
+use essential_types::{
+    solution::{Mutation, SolutionData},
+    ContentAddress, Key, PredicateAddress, Value,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path, sync::Arc};
 
+use crate::clock::{Clock, SystemClock};
+
+#[derive(Clone)]
 pub struct Dbs {
     pub builder: builder_db::ConnectionPool,
     pub node: node::db::ConnectionPool,
+    /// The big bang the node DB was initialised with.
+    ///
+    /// Its contract registry is where [`deploy`](crate::deploy) registers contracts.
+    pub big_bang: BigBang,
+    /// Stamps solutions submitted through the harness, such as with [`Dbs::submit`].
+    ///
+    /// [`build_default`](crate::builder::build_default) attempts solutions in
+    /// timestamp order, so this decides the order they are attempted in.
+    pub clock: Arc<dyn Clock>,
+}
+
+/// Describes how to initialize the node DB before the first block is built.
+#[derive(Clone, Debug, Default)]
+pub struct Genesis {
+    /// The big bang configuration the genesis block is derived from.
+    ///
+    /// If this is not the default, blocks must be built with a builder config
+    /// whose `contract_registry` and `block_state` match.
+    pub big_bang: BigBang,
+    /// State to write into the node DB as part of the big bang block.
+    pub state: GenesisState,
 }
 
+/// Seeded contract state, mapping contract address -> key -> value.
+///
+/// Deserializes from JSON or TOML, with contract addresses as upper hex strings:
+///
+/// ```json
+/// { "<contract-address>": [{ "key": [0, 1], "value": [42] }] }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GenesisState(pub BTreeMap<ContentAddress, Vec<Mutation>>);
+
 pub async fn new_dbs() -> Dbs {
+    new_dbs_with_genesis(&Genesis::default()).await
+}
+
+pub async fn new_dbs_with_genesis(genesis: &Genesis) -> Dbs {
     let config = node::db::Config {
         source: node::db::Source::Memory(uuid::Uuid::new_v4().to_string()),
         ..Default::default()
     };
     let node = node::db(&config).unwrap();
-    init_node_db(&node).await.unwrap();
+    init_node_db_with_genesis(&node, genesis).await.unwrap();
     let config = builder_db::pool::Config {
         source: builder_db::pool::Source::Memory(uuid::Uuid::new_v4().to_string()),
         ..Default::default()
     };
     let builder = builder_db::ConnectionPool::with_tables(&config).unwrap();
-    Dbs { builder, node }
+    Dbs {
+        builder,
+        node,
+        big_bang: genesis.big_bang.clone(),
+        clock: Arc::new(SystemClock),
+    }
+}
+
+impl Dbs {
+    /// Use `clock` to stamp solutions submitted through the harness.
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            ..self
+        }
+    }
+
+    /// Submit a solution to the builder, stamped with [`Dbs::clock`].
+    pub async fn submit(
+        &self,
+        solution: essential_types::solution::Solution,
+    ) -> anyhow::Result<ContentAddress> {
+        crate::builder::submit_with_clock(&self.builder, solution, &self.clock).await
+    }
 }
 
 pub async fn init_node_db(db: &node::db::ConnectionPool) -> anyhow::Result<()> {
-    let big_bang = essential_node_types::BigBang::default();
+    init_node_db_with_genesis(db, &Genesis::default()).await
+}
+
+pub async fn init_node_db_with_genesis(
+    db: &node::db::ConnectionPool,
+    genesis: &Genesis,
+) -> anyhow::Result<()> {
+    let big_bang = genesis.to_big_bang();
 
     essential_node::ensure_big_bang_block(db, &big_bang).await?;
     Ok(())
 }
+
+impl Genesis {
+    pub fn new(big_bang: BigBang) -> Self {
+        Self {
+            big_bang,
+            state: GenesisState::default(),
+        }
+    }
+
+    pub fn with_state(mut self, state: GenesisState) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Seed a single key of the given contract's state.
+    pub fn insert(mut self, contract: ContentAddress, key: Key, value: Value) -> Self {
+        self.state.insert(contract, key, value);
+        self
+    }
+
+    /// The big bang with the seeded state appended to its solution.
+    ///
+    /// Each seeded contract gets its own `SolutionData`. The node applies big bang
+    /// mutations without checking them, so the predicate address is left zeroed.
+    pub fn to_big_bang(&self) -> BigBang {
+        let mut big_bang = self.big_bang.clone();
+        big_bang
+            .solution
+            .data
+            .extend(
+                self.state
+                    .0
+                    .iter()
+                    .map(|(contract, mutations)| SolutionData {
+                        predicate_to_solve: PredicateAddress {
+                            contract: contract.clone(),
+                            predicate: ContentAddress([0; 32]),
+                        },
+                        decision_variables: Default::default(),
+                        transient_data: Default::default(),
+                        state_mutations: mutations.clone(),
+                    }),
+            );
+        big_bang
+    }
+}
+
+impl GenesisState {
+    pub fn insert(&mut self, contract: ContentAddress, key: Key, value: Value) {
+        let mutations = self.0.entry(contract).or_default();
+        mutations.retain(|m| m.key != key);
+        mutations.push(Mutation { key, value });
+    }
+
+    pub fn from_json_str(s: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    pub fn from_toml_str(s: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(s)?)
+    }
+
+    /// Read a fixture file, choosing the format from its `.json` or `.toml` extension.
+    pub async fn read(path: &Path) -> anyhow::Result<Self> {
+        let contents = tokio::fs::read_to_string(path).await?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json_str(&contents),
+            Some("toml") => Self::from_toml_str(&contents),
+            _ => bail!("unknown genesis state format: {}", path.display()),
+        }
+    }
+}
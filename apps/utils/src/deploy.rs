@@ -1,26 +1,205 @@
-use essential_node_types::register_contract_solution;
-use essential_types::{contract::Contract, solution::Solution, ContentAddress};
-
-pub async fn deploy_contract(
-    builder_conn: &essential_builder_db::ConnectionPool,
-    contract: &Contract,
-) -> anyhow::Result<ContentAddress> {
-    let solution = deploy_contract_solution(contract)?;
-    let r = builder_conn
-        .insert_solution_submission(
-            std::sync::Arc::new(solution),
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap(),
-        )
-        .await?;
-    Ok(r)
+use crate::{
+    builder::{build_with, default_config, submit_with_clock, Only},
+    compile::{compile_pint_project_and_abi, package_name, pint_version, ContractAddresses},
+    db::Dbs,
+};
+use anyhow::ensure;
+use essential_node_types::{contract_registry, register_contract_solution};
+use essential_types::{
+    contract::Contract, solution::Solution, Block, ContentAddress, Key, PredicateAddress, Word,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+pub mod remote;
+
+/// The default file name for a [`Deployments`] manifest.
+pub const MANIFEST_FILE: &str = "deployments.json";
+
+/// The result of deploying a contract that may already be registered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeployOutcome {
+    /// The registration solution was submitted with this content address.
+    Submitted(ContentAddress),
+    /// The contract was already in the registry so nothing was submitted.
+    AlreadyDeployed,
+}
+
+/// A manifest of deployed projects keyed by the package name in their `pint.toml`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Deployments(pub BTreeMap<String, Deployment>);
+
+/// Where a project was deployed and what it was compiled with.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployment {
+    pub contract: ContentAddress,
+    /// Predicate content addresses keyed by name.
+    pub predicates: BTreeMap<String, ContentAddress>,
+    /// The output of `pint --version` for the compiler that built the contract.
+    pub compiler: String,
+    /// The number of the block the contract was registered in.
+    pub block: Word,
+}
+
+/// Submit the contract's registration solution unless it is already registered.
+///
+/// The contract is registered with the registry in [`Dbs::big_bang`] and the
+/// submission is stamped with [`Dbs::clock`].
+pub async fn deploy_contract(dbs: &Dbs, contract: &Contract) -> anyhow::Result<DeployOutcome> {
+    if is_deployed(dbs, contract).await? {
+        return Ok(DeployOutcome::AlreadyDeployed);
+    }
+    let solution = register_solution(dbs.big_bang.contract_registry.clone(), contract)?;
+    let ca = submit_with_clock(&dbs.builder, solution, &dbs.clock).await?;
+    Ok(DeployOutcome::Submitted(ca))
 }
 
+/// The solution registering the contract with the default big bang's registry.
 pub fn deploy_contract_solution(contract: &Contract) -> anyhow::Result<Solution> {
     let registry_predicate = essential_node_types::BigBang::default().contract_registry;
-    let solution = register_contract_solution(registry_predicate, contract)?;
+    register_solution(registry_predicate, contract)
+}
+
+fn register_solution(registry: PredicateAddress, contract: &Contract) -> anyhow::Result<Solution> {
+    let solution = register_contract_solution(registry, contract)?;
     Ok(Solution {
         data: vec![solution],
     })
 }
+
+/// Whether the contract is in the registry as of the latest block.
+pub async fn is_deployed(dbs: &Dbs, contract: &Contract) -> anyhow::Result<bool> {
+    let registry = &dbs.big_bang.contract_registry;
+    let key = registry_key(contract);
+    let salt = crate::node::query_state_head(&dbs.node, &registry.contract, &key).await?;
+    Ok(salt.is_some_and(|salt| !salt.is_empty()))
+}
+
+/// The registry key that is set once the contract is deployed.
+pub fn registry_key(contract: &Contract) -> essential_types::Key {
+    let address = essential_hash::contract_addr::from_contract(contract);
+    contract_registry::contract_salt_key(&address)
+}
+
+/// Whether the block sets the registry key, and so registered the contract it belongs to.
+pub fn registers(block: &Block, registry: &ContentAddress, key: &Key) -> bool {
+    block.solutions.iter().flat_map(|s| &s.data).any(|data| {
+        data.predicate_to_solve.contract == *registry
+            && data
+                .state_mutations
+                .iter()
+                .any(|m| m.key == *key && !m.value.is_empty())
+    })
+}
+
+/// The number of the block the contract was registered in, if it is registered.
+///
+/// The registry key is only ever set once, so this binary searches the
+/// registry state as of each block instead of reading every block.
+pub async fn registration_block(dbs: &Dbs, contract: &Contract) -> anyhow::Result<Option<Word>> {
+    let registry = &dbs.big_bang.contract_registry.contract;
+    let key = registry_key(contract);
+    let head = crate::node::head_block_number(&dbs.node).await?;
+    let mut conn = dbs.node.acquire().await?;
+    let tx = conn.transaction()?;
+    let registered_at = |block| -> anyhow::Result<bool> {
+        let salt =
+            essential_node_db::finalized::query_state_inclusive_block(&tx, registry, &key, block)?;
+        Ok(salt.is_some_and(|salt| !salt.is_empty()))
+    };
+    if !registered_at(head)? {
+        return Ok(None);
+    }
+    // The contract is registered as of `high` and not before `low`.
+    let (mut low, mut high) = (0, head);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if registered_at(mid)? {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Ok(Some(high))
+}
+
+/// Compile and deploy a pint project, recording it in the manifest at `manifest`.
+///
+/// If the contract is not yet registered, a block is built that includes only
+/// the registration, leaving anything else in the pool. The manifest is written
+/// whether or not the contract was already registered.
+pub async fn deploy_project(
+    dbs: &Dbs,
+    path: PathBuf,
+    manifest: &Path,
+) -> anyhow::Result<(DeployOutcome, Deployment)> {
+    let name = package_name(&path.join("pint.toml")).await?;
+    let (contract, abi) = compile_pint_project_and_abi(path).await?;
+    let outcome = deploy_contract(dbs, &contract).await?;
+    if let DeployOutcome::Submitted(ca) = &outcome {
+        let only = Only([ca.clone()].into_iter().collect());
+        let summary = build_with(dbs, &default_config(dbs), &only).await?;
+        ensure!(
+            summary.failed.is_empty(),
+            "registering {} failed: {:?}",
+            name,
+            summary.failed
+        );
+    }
+    let block = registration_block(dbs, &contract)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("{} was not registered in the built block", name))?;
+    let deployment = Deployment::new(
+        &ContractAddresses::new(&contract, &abi),
+        pint_version().await?,
+        block,
+    );
+    Deployments::record(manifest, &name, deployment.clone()).await?;
+    Ok((outcome, deployment))
+}
+
+impl Deployments {
+    /// Read a manifest, or an empty one if the file doesn't exist.
+    pub async fn load(path: &Path) -> anyhow::Result<Self> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn save(&self, path: &Path) -> anyhow::Result<()> {
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// Add or replace a project's deployment in the manifest at `path`.
+    pub async fn record(path: &Path, name: &str, deployment: Deployment) -> anyhow::Result<()> {
+        let mut deployments = Self::load(path).await?;
+        deployments.0.insert(name.to_string(), deployment);
+        deployments.save(path).await
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Deployment> {
+        self.0.get(name)
+    }
+}
+
+impl Deployment {
+    pub fn new(addresses: &ContractAddresses, compiler: String, block: Word) -> Self {
+        Self {
+            contract: addresses.contract.clone(),
+            predicates: addresses
+                .predicates
+                .iter()
+                .map(|(name, address)| (name.clone(), address.predicate.clone()))
+                .collect(),
+            compiler,
+            block,
+        }
+    }
+}
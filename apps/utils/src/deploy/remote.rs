@@ -0,0 +1,106 @@
+//! Deploying Pint projects through a remote node and builder.
+
+use super::{registers, registry_key, DeployOutcome, Deployment, Deployments};
+use crate::compile::{compile_pint_project_and_abi, package_name, pint_version, ContractAddresses};
+use essential_rest_client::{
+    builder_client::EssentialBuilderClient,
+    node_client::{EssentialNodeClient, BLOCK_PAGE},
+};
+use essential_types::{ContentAddress, Key, Word};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const DEPLOY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const DEPLOY_POLL_ATTEMPTS: usize = 60;
+
+/// Deploy the project if it isn't registered yet and wait for it to be included in a block.
+///
+/// The deployment is recorded in the manifest at `manifest` whether or not the
+/// contract was already registered. The node is expected to use the default big bang.
+///
+/// The registering block is searched for from the block after the latest one
+/// before submitting. For a contract that was already registered, the search
+/// starts from the block recorded in the manifest, and only searches every
+/// block if the manifest has no record of the contract.
+pub async fn deploy_project(
+    node: &EssentialNodeClient,
+    builder: &EssentialBuilderClient,
+    pint_directory: PathBuf,
+    manifest: &Path,
+) -> anyhow::Result<(DeployOutcome, Deployment)> {
+    let registry = essential_node_types::BigBang::default().contract_registry;
+    let name = package_name(&pint_directory.join("pint.toml")).await?;
+    let (contract, abi) = compile_pint_project_and_abi(pint_directory).await?;
+    let addresses = ContractAddresses::new(&contract, &abi);
+    let key = registry_key(&contract);
+    let registered = || async {
+        let salt = node
+            .query_state(registry.contract.clone(), key.clone())
+            .await?;
+        anyhow::Ok(salt.is_some_and(|salt| !salt.is_empty()))
+    };
+
+    // Read before checking the registry, so a registration not yet seen is in a later block.
+    let head = node.latest_block_number().await?;
+    let (outcome, start) = if registered().await? {
+        let recorded = Deployments::load(manifest)
+            .await?
+            .get(&name)
+            .filter(|d| d.contract == addresses.contract)
+            .map(|d| d.block);
+        (DeployOutcome::AlreadyDeployed, recorded)
+    } else {
+        let ca = builder.deploy_contract(&contract).await?;
+        let mut attempts = 0;
+        while !registered().await? {
+            attempts += 1;
+            if attempts >= DEPLOY_POLL_ATTEMPTS {
+                anyhow::bail!("{} was not registered after {} attempts", name, attempts);
+            }
+            tokio::time::sleep(DEPLOY_POLL_INTERVAL).await;
+        }
+        (
+            DeployOutcome::Submitted(ca),
+            Some(head.map_or(0, |head| head + 1)),
+        )
+    };
+
+    let mut block = match start {
+        Some(start) => find_registration(node, start, &registry.contract, &key).await?,
+        None => None,
+    };
+    if block.is_none() && outcome == DeployOutcome::AlreadyDeployed {
+        block = find_registration(node, 0, &registry.contract, &key).await?;
+    }
+    let block = block.ok_or_else(|| {
+        anyhow::anyhow!(
+            "{} is registered but no block registering it was found",
+            name
+        )
+    })?;
+    let deployment = Deployment::new(&addresses, pint_version().await?, block);
+    Deployments::record(manifest, &name, deployment.clone()).await?;
+    Ok((outcome, deployment))
+}
+
+/// The number of the first block from `start` that sets the registry key.
+async fn find_registration(
+    node: &EssentialNodeClient,
+    start: Word,
+    registry: &ContentAddress,
+    key: &Key,
+) -> anyhow::Result<Option<Word>> {
+    let mut next = start;
+    loop {
+        let blocks = node.list_blocks(next..next + BLOCK_PAGE).await?;
+        let Some(last) = blocks.last() else {
+            return Ok(None);
+        };
+        if let Some(block) = blocks.iter().find(|b| registers(b, registry, key)) {
+            return Ok(Some(block.number));
+        }
+        next = last.number + 1;
+    }
+}
@@ -1,19 +1,41 @@
-use essential_sign::secp256k1::{ecdsa::RecoverableSignature, PublicKey};
+use anyhow::{anyhow, bail, ensure};
+use essential_sign::secp256k1::{
+    ecdsa::{RecoverableSignature, RecoveryId},
+    PublicKey,
+};
 use essential_types::{
-    convert::word_4_from_u8_32, solution::Mutation, ContentAddress, Hash, Key, PredicateAddress,
-    Value, Word,
+    convert::{
+        bool_from_word, bytes_from_word, u8_32_from_word_4, u8_64_from_word_8, word_4_from_u8_32,
+    },
+    solution::Mutation,
+    ContentAddress, Hash, Key, PredicateAddress, Value, Word,
 };
 
-#[derive(Clone)]
+// `Int`, `B256` and `Instance` use these derives. The hand-written impls below
+// are for the primitives and arrays the derives build on, and for foreign types
+// such as `ContentAddress` that can't derive them.
+pub use essential_app_utils_derive::{Decode, Encode, WriteDecVars};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow::Error;
+    pub use essential_types::{Value, Word};
+}
+
+#[derive(Clone, WriteDecVars, Encode, Decode)]
 pub struct Instance {
     pub address: PredicateAddress,
     pub path: Word,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, WriteDecVars, Encode, Decode,
+)]
 pub struct Int(pub Word);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, WriteDecVars, Encode, Decode,
+)]
 pub struct B256(pub [Word; 4]);
 
 pub fn index_key(index: Word, key: Key) -> Key {
@@ -83,15 +105,120 @@ pub trait WriteDecVars {
     fn write_dec_var(&self, decision_variables: &mut Vec<Value>);
 }
 
-impl WriteDecVars for B256 {
+/// The number of words a type occupies once flattened into a slot.
+///
+/// Used to pad derived enums to the size of their largest variant.
+pub trait WordSize {
+    const WORD_SIZE: usize;
+}
+
+/// Flatten a value into the words it would occupy in a decision variable slot.
+///
+/// This is the layout Pint uses when hashing a tuple of the same types, so it
+/// is also the data to sign for `@verify_key`.
+pub fn to_words(value: &impl WriteDecVars) -> Vec<Word> {
+    let mut slots = Vec::new();
+    value.write_dec_var(&mut slots);
+    slots.into_iter().flatten().collect()
+}
+
+/// The inverse of [`WriteDecVars`], reading a value back out of its flattened words.
+///
+/// This recovers values such as signatures and addresses from the decision
+/// variables of solutions found in blocks.
+pub trait Decode: WordSize + Sized {
+    /// Decode from exactly [`WordSize::WORD_SIZE`] words.
+    fn decode(words: &[Word]) -> anyhow::Result<Self>;
+}
+
+/// Decode a value from a single decision variable slot.
+pub fn decode_slot<T: Decode>(slot: &Value) -> anyhow::Result<T> {
+    T::decode(slot)
+}
+
+/// Decode a value from the front of `words`, advancing past the words it occupies.
+pub fn decode_next<T: Decode>(words: &mut &[Word]) -> anyhow::Result<T> {
+    ensure!(
+        words.len() >= T::WORD_SIZE,
+        "expected at least {} words, found {}",
+        T::WORD_SIZE,
+        words.len()
+    );
+    let (head, tail) = words.split_at(T::WORD_SIZE);
+    *words = tail;
+    T::decode(head)
+}
+
+#[doc(hidden)]
+pub fn check_word_size<T: WordSize>(words: &[Word]) -> anyhow::Result<()> {
+    ensure!(
+        words.len() == T::WORD_SIZE,
+        "expected {} words, found {}",
+        T::WORD_SIZE,
+        words.len()
+    );
+    Ok(())
+}
+
+#[doc(hidden)]
+pub fn unknown_tag(ty: &str, tag: Word) -> anyhow::Error {
+    anyhow!("unknown tag {} for {}", tag, ty)
+}
+
+#[doc(hidden)]
+pub fn check_padding(ty: &str, padding: &[Word]) -> anyhow::Result<()> {
+    ensure!(
+        padding.iter().all(|&w| w == 0),
+        "non-zero padding for {}",
+        ty
+    );
+    Ok(())
+}
+
+#[doc(hidden)]
+pub const fn max_word_size(sizes: &[usize]) -> usize {
+    let mut max = 0;
+    let mut i = 0;
+    while i < sizes.len() {
+        if sizes[i] > max {
+            max = sizes[i];
+        }
+        i += 1;
+    }
+    max
+}
+
+impl WriteDecVars for Word {
+    fn write_dec_var(&self, decision_variables: &mut Vec<Value>) {
+        decision_variables.push(vec![*self]);
+    }
+}
+
+impl WriteDecVars for bool {
+    fn write_dec_var(&self, decision_variables: &mut Vec<Value>) {
+        decision_variables.push(vec![*self as Word]);
+    }
+}
+
+impl<T: WriteDecVars, const N: usize> WriteDecVars for [T; N] {
+    fn write_dec_var(&self, decision_variables: &mut Vec<Value>) {
+        let mut slot = Vec::new();
+        for item in self {
+            item.write_dec_var(&mut slot);
+        }
+        decision_variables.to_slot(slot.into_iter().flatten());
+    }
+}
+
+impl WriteDecVars for RecoverableSignature {
     fn write_dec_var(&self, decision_variables: &mut Vec<Value>) {
-        decision_variables.to_slot(self.0);
+        decision_variables.to_slot(essential_sign::encode::signature(self));
     }
 }
 
-impl WriteDecVars for Int {
+impl WriteDecVars for PublicKey {
     fn write_dec_var(&self, decision_variables: &mut Vec<Value>) {
-        decision_variables.push(vec![self.0]);
+        decision_variables.to_slot(essential_sign::encode::public_key(self));
     }
 }
 
@@ -110,13 +237,95 @@ impl WriteDecVars for ContentAddress {
     }
 }
 
-impl WriteDecVars for Instance {
-    fn write_dec_var(&self, decision_variables: &mut Vec<Value>) {
-        let mut slot = Vec::new();
-        self.address.contract.write_dec_var(&mut slot);
-        self.address.predicate.write_dec_var(&mut slot);
-        Int::from(self.path).write_dec_var(&mut slot);
-        decision_variables.to_slot(slot.into_iter().flatten());
+impl WordSize for Word {
+    const WORD_SIZE: usize = 1;
+}
+
+impl WordSize for bool {
+    const WORD_SIZE: usize = 1;
+}
+
+impl<T: WordSize, const N: usize> WordSize for [T; N] {
+    const WORD_SIZE: usize = T::WORD_SIZE * N;
+}
+
+impl WordSize for ContentAddress {
+    const WORD_SIZE: usize = 4;
+}
+
+impl WordSize for PredicateAddress {
+    const WORD_SIZE: usize = 8;
+}
+
+impl WordSize for RecoverableSignature {
+    const WORD_SIZE: usize = 9;
+}
+
+impl WordSize for PublicKey {
+    const WORD_SIZE: usize = 5;
+}
+
+impl Decode for Word {
+    fn decode(words: &[Word]) -> anyhow::Result<Self> {
+        check_word_size::<Self>(words)?;
+        Ok(words[0])
+    }
+}
+
+impl Decode for bool {
+    fn decode(words: &[Word]) -> anyhow::Result<Self> {
+        check_word_size::<Self>(words)?;
+        bool_from_word(words[0]).ok_or_else(|| anyhow!("invalid bool: {}", words[0]))
+    }
+}
+
+impl<T: Decode, const N: usize> Decode for [T; N] {
+    fn decode(mut words: &[Word]) -> anyhow::Result<Self> {
+        check_word_size::<Self>(words)?;
+        let items = (0..N)
+            .map(|_| decode_next(&mut words))
+            .collect::<anyhow::Result<Vec<T>>>()?;
+        items
+            .try_into()
+            .map_err(|_| anyhow!("expected {} items", N))
+    }
+}
+
+impl Decode for ContentAddress {
+    fn decode(words: &[Word]) -> anyhow::Result<Self> {
+        <[Word; 4]>::decode(words).map(|words| ContentAddress(u8_32_from_word_4(words)))
+    }
+}
+
+impl Decode for PredicateAddress {
+    fn decode(mut words: &[Word]) -> anyhow::Result<Self> {
+        check_word_size::<Self>(words)?;
+        Ok(PredicateAddress {
+            contract: decode_next(&mut words)?,
+            predicate: decode_next(&mut words)?,
+        })
+    }
+}
+
+impl Decode for RecoverableSignature {
+    fn decode(words: &[Word]) -> anyhow::Result<Self> {
+        check_word_size::<Self>(words)?;
+        let compact = u8_64_from_word_8(words[..8].try_into()?);
+        let Ok(rec_id) = i32::try_from(words[8]) else {
+            bail!("invalid recovery id: {}", words[8]);
+        };
+        let rec_id = RecoveryId::from_i32(rec_id)?;
+        Ok(RecoverableSignature::from_compact(&compact, rec_id)?)
+    }
+}
+
+impl Decode for PublicKey {
+    fn decode(words: &[Word]) -> anyhow::Result<Self> {
+        check_word_size::<Self>(words)?;
+        let mut bytes = [0u8; 33];
+        bytes[..32].copy_from_slice(&u8_32_from_word_4(words[..4].try_into()?));
+        bytes[32] = bytes_from_word(words[4])[7];
+        Ok(PublicKey::from_slice(&bytes)?)
     }
 }
 
@@ -135,6 +344,30 @@ pub trait Encode {
     fn encode(&self) -> Self::Output;
 }
 
+impl Encode for Word {
+    type Output = Word;
+
+    fn encode(&self) -> Self::Output {
+        *self
+    }
+}
+
+impl Encode for bool {
+    type Output = bool;
+
+    fn encode(&self) -> Self::Output {
+        *self
+    }
+}
+
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    type Output = [T::Output; N];
+
+    fn encode(&self) -> Self::Output {
+        self.each_ref().map(Encode::encode)
+    }
+}
+
 impl Encode for RecoverableSignature {
     type Output = ([Word; 4], [Word; 4], Word);
 
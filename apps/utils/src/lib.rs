@@ -1,9 +1,18 @@
+// Lets the input derives, which name `::essential_app_utils`, be used in this crate.
+extern crate self as essential_app_utils;
+
 pub mod addresses;
 pub mod builder;
+pub mod bundle;
+pub mod clock;
 pub mod compile;
+pub mod credentials;
 pub mod db;
 pub mod deploy;
 pub mod inputs;
 pub mod node;
+pub mod nonce;
 pub mod print;
 pub mod read;
+pub mod signer;
+pub mod state;
@@ -1,4 +1,4 @@
-use essential_types::{ContentAddress, Key, Value};
+use essential_types::{ContentAddress, Key, Value, Word};
 
 pub async fn query_state_head(
     conn: &essential_node::db::ConnectionPool,
@@ -16,6 +16,18 @@ pub async fn query_state_head(
     Ok(r)
 }
 
+/// The number of the latest finalized block, or 0 if there are none.
+pub async fn head_block_number(conn: &essential_node::db::ConnectionPool) -> anyhow::Result<Word> {
+    let mut c = conn.acquire().await?;
+    let tx = c.transaction()?;
+    let ca = essential_node_db::get_latest_finalized_block_address(&tx)?;
+    let num = match ca {
+        Some(ca) => essential_node_db::get_block_number(&tx, &ca)?.unwrap_or_default(),
+        None => 0,
+    };
+    Ok(num)
+}
+
 pub async fn validate_solution(
     conn: &essential_node::db::ConnectionPool,
     solution: essential_types::solution::Solution,
@@ -0,0 +1,70 @@
+use essential_types::{ContentAddress, Key, Word};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Hands out nonces for solutions that are submitted before earlier ones are included.
+///
+/// Nonces are tracked per (contract, nonce key). Each call to [`next`](Self::next)
+/// returns the nonce after the highest one handed out, or after the on-chain
+/// nonce if that is higher. Clones share the same state.
+///
+/// The state is only held in memory, so it is for long running processes that
+/// keep submitting as earlier solutions are included or fail.
+#[derive(Clone, Debug, Default)]
+pub struct NonceManager(Arc<Mutex<HashMap<(ContentAddress, Key), Word>>>);
+
+impl NonceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The next nonce to sign with, given the nonce currently stored on chain.
+    ///
+    /// Fails if the nonce would overflow.
+    pub fn next(
+        &self,
+        contract: &ContentAddress,
+        key: &Key,
+        on_chain: Word,
+    ) -> anyhow::Result<Word> {
+        let mut pending = self.0.lock().unwrap();
+        let last = pending
+            .entry((contract.clone(), key.clone()))
+            .or_insert(on_chain);
+        *last = (*last)
+            .max(on_chain)
+            .checked_add(1)
+            .ok_or_else(|| anyhow::anyhow!("Nonce overflows"))?;
+        Ok(*last)
+    }
+
+    /// The highest nonce handed out that is not yet known to be on chain.
+    pub fn pending(&self, contract: &ContentAddress, key: &Key) -> Option<Word> {
+        let pending = self.0.lock().unwrap();
+        pending.get(&(contract.clone(), key.clone())).copied()
+    }
+
+    /// Update with the nonce stored on chain after a block.
+    ///
+    /// Once the chain has caught up with every nonce handed out, the key stops being tracked.
+    pub fn reconcile(&self, contract: &ContentAddress, key: &Key, on_chain: Word) {
+        let mut pending = self.0.lock().unwrap();
+        let id = (contract.clone(), key.clone());
+        if pending.get(&id).is_some_and(|last| *last <= on_chain) {
+            pending.remove(&id);
+        }
+    }
+
+    /// Release `nonce` after the solution using it failed or was never submitted.
+    ///
+    /// Nonces after it can no longer be included either, so they are released too
+    /// and will be handed out again.
+    pub fn rollback(&self, contract: &ContentAddress, key: &Key, nonce: Word) {
+        let mut pending = self.0.lock().unwrap();
+        if let Some(last) = pending.get_mut(&(contract.clone(), key.clone())) {
+            *last = (*last).min(nonce.saturating_sub(1));
+        }
+    }
+}
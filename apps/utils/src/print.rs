@@ -1,5 +1,7 @@
 use essential_types::{ContentAddress, PredicateAddress};
 
+pub use essential_print::{DisplayBlock, DisplaySolution, DisplaySolutionData, Labels};
+
 pub fn print_predicate_address(name: &str, address: &PredicateAddress) {
     println!(
         "{}: contract:{}, predicate: {}",
@@ -0,0 +1,212 @@
+use crate::inputs::Encode;
+use anyhow::{anyhow, bail, Context};
+use essential_sign::secp256k1::{
+    ecdsa::{RecoverableSignature, RecoveryId},
+    Message, PublicKey, Secp256k1, SecretKey,
+};
+use essential_types::{convert::word_4_from_u8_32, Word};
+use essential_wallet::Wallet;
+use serde::{Deserialize, Serialize};
+use std::{
+    ffi::OsString,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Signs data for an account the way Pint's `@verify_key` expects.
+pub trait Signer {
+    /// The account's secp256k1 public key.
+    fn public_key(&mut self) -> anyhow::Result<PublicKey>;
+
+    /// Sign over the sha256 hash of the given words.
+    fn sign(&mut self, words: &[Word]) -> anyhow::Result<RecoverableSignature>;
+
+    /// The hash of the account's encoded public key, used as its key in contract storage.
+    fn hashed_key(&mut self) -> anyhow::Result<[Word; 4]> {
+        Ok(hash_public_key(&self.public_key()?))
+    }
+
+    /// Sign the words, returning the signature in the form expected by the generated ABI types.
+    fn sign_words(
+        &mut self,
+        words: &[Word],
+    ) -> anyhow::Result<<RecoverableSignature as Encode>::Output> {
+        Ok(self.sign(words)?.encode())
+    }
+}
+
+/// Signs with a named account in an [`essential_wallet::Wallet`].
+pub struct WalletSigner<'a> {
+    pub wallet: &'a mut Wallet,
+    pub account: String,
+}
+
+/// Signs as the account chosen on the command line, with either a wallet or a raw key.
+///
+/// Opened from [`Credentials`](crate::credentials::Credentials).
+pub enum AccountSigner {
+    /// A named account in an unlocked wallet.
+    Wallet { wallet: Wallet, account: String },
+    /// A secp256k1 secret key held in memory.
+    Key(SecretKey),
+}
+
+/// Delegates signing to an external program, speaking JSON over stdio.
+///
+/// The program is run once per request. It is given a single JSON request on
+/// stdin and must write a single JSON response to stdout:
+///
+/// | Request | Response |
+/// | --- | --- |
+/// | `{"method": "public_key"}` | `{"public_key": "<hex 33 byte compressed key>"}` |
+/// | `{"method": "sign_words", "words": [..]}` | `{"signature": "<hex 64 byte compact signature + recovery id>"}` |
+///
+/// Either request may instead be answered with `{"error": "<message>"}`.
+#[derive(Clone, Debug)]
+pub struct ProcessSigner {
+    pub program: OsString,
+    pub args: Vec<OsString>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request<'a> {
+    PublicKey,
+    SignWords { words: &'a [Word] },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Response {
+    PublicKey(String),
+    Signature(String),
+    Error(String),
+}
+
+/// Hash an encoded public key, matching `sha256` over the key in Pint.
+pub fn hash_public_key(public_key: &PublicKey) -> [Word; 4] {
+    let encoded = essential_sign::encode::public_key(public_key);
+    word_4_from_u8_32(essential_hash::hash_words(&encoded))
+}
+
+impl<'a> WalletSigner<'a> {
+    pub fn new(wallet: &'a mut Wallet, account: impl Into<String>) -> Self {
+        Self {
+            wallet,
+            account: account.into(),
+        }
+    }
+}
+
+impl Signer for WalletSigner<'_> {
+    fn public_key(&mut self) -> anyhow::Result<PublicKey> {
+        match self.wallet.get_public_key(&self.account)? {
+            essential_signer::PublicKey::Secp256k1(key) => Ok(key),
+            _ => bail!("Account {} does not have a secp256k1 key", self.account),
+        }
+    }
+
+    fn sign(&mut self, words: &[Word]) -> anyhow::Result<RecoverableSignature> {
+        match self.wallet.sign_words(words, &self.account)? {
+            essential_signer::Signature::Secp256k1(sig) => Ok(sig),
+            _ => bail!("Invalid signature"),
+        }
+    }
+}
+
+impl Signer for AccountSigner {
+    fn public_key(&mut self) -> anyhow::Result<PublicKey> {
+        match self {
+            AccountSigner::Wallet { wallet, account } => {
+                WalletSigner::new(wallet, account.as_str()).public_key()
+            }
+            AccountSigner::Key(key) => key.public_key(),
+        }
+    }
+
+    fn sign(&mut self, words: &[Word]) -> anyhow::Result<RecoverableSignature> {
+        match self {
+            AccountSigner::Wallet { wallet, account } => {
+                WalletSigner::new(wallet, account.as_str()).sign(words)
+            }
+            AccountSigner::Key(key) => Signer::sign(key, words),
+        }
+    }
+}
+
+impl Signer for SecretKey {
+    fn public_key(&mut self) -> anyhow::Result<PublicKey> {
+        Ok(PublicKey::from_secret_key(&Secp256k1::new(), self))
+    }
+
+    fn sign(&mut self, words: &[Word]) -> anyhow::Result<RecoverableSignature> {
+        let message = Message::from_digest(essential_hash::hash_words(words));
+        Ok(Secp256k1::new().sign_ecdsa_recoverable(&message, self))
+    }
+}
+
+impl ProcessSigner {
+    pub fn new(program: impl Into<OsString>) -> Self {
+        Self {
+            program: program.into(),
+            args: vec![],
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    fn request(&self, request: &Request) -> anyhow::Result<Response> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start signer {:?}", self.program))?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        serde_json::to_writer(&mut stdin, request)?;
+        stdin.write_all(b"\n")?;
+        drop(stdin);
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            bail!("Signer {:?} exited with {}", self.program, output.status);
+        }
+        match serde_json::from_slice(&output.stdout)? {
+            Response::Error(e) => bail!("Signer {:?} failed: {}", self.program, e),
+            response => Ok(response),
+        }
+    }
+}
+
+impl Signer for ProcessSigner {
+    fn public_key(&mut self) -> anyhow::Result<PublicKey> {
+        let Response::PublicKey(key) = self.request(&Request::PublicKey)? else {
+            bail!("Expected a public key from signer");
+        };
+        Ok(PublicKey::from_slice(&hex::decode(key)?)?)
+    }
+
+    fn sign(&mut self, words: &[Word]) -> anyhow::Result<RecoverableSignature> {
+        let Response::Signature(sig) = self.request(&Request::SignWords { words })? else {
+            bail!("Expected a signature from signer");
+        };
+        let bytes = hex::decode(sig)?;
+        let [compact @ .., rec_id]: [u8; 65] = bytes
+            .try_into()
+            .map_err(|_| anyhow!("Expected a 65 byte signature"))?;
+        let rec_id = RecoveryId::from_i32(rec_id.into())?;
+        Ok(RecoverableSignature::from_compact(&compact, rec_id)?)
+    }
+}
+
+impl<S: Signer + ?Sized> Signer for &mut S {
+    fn public_key(&mut self) -> anyhow::Result<PublicKey> {
+        (**self).public_key()
+    }
+
+    fn sign(&mut self, words: &[Word]) -> anyhow::Result<RecoverableSignature> {
+        (**self).sign(words)
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{bail, ensure};
+use essential_types::{convert::bool_from_word, Value, Word};
+use std::{fmt, marker::PhantomData};
+
+/// A state value read from the node, to be interpreted as a `T`.
+///
+/// A key that has never been set, or that has been deleted, is nil. Whether
+/// nil is valid depends on `T`, see [`FromState::nil`].
+pub struct Typed<T>(pub Option<Value>, PhantomData<T>);
+
+/// Types that can be read from a single state value.
+///
+/// Words, hashed keys and bools read a nil key as zero, matching how Pint
+/// treats unset storage. ABI types implemented with [`impl_from_state_for_abi`]
+/// are the exception: they have no zero value, so a nil key is an error.
+pub trait FromState: Sized {
+    /// The value of a nil key.
+    fn nil() -> anyhow::Result<Self>;
+
+    /// Interpret a non-empty state value.
+    fn from_words(words: &[Word]) -> anyhow::Result<Self>;
+}
+
+/// Implement [`FromState`] for ABI-generated types, such as Pint unions.
+///
+/// These have no sensible default so reading a nil key is an error.
+///
+/// ```ignore
+/// essential_app_utils::impl_from_state_for_abi!(abi::Resolution);
+/// ```
+#[macro_export]
+macro_rules! impl_from_state_for_abi {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl $crate::state::FromState for $ty {
+                fn nil() -> $crate::state::__private::anyhow::Result<Self> {
+                    $crate::state::__private::anyhow::bail!(
+                        "expected {} to be non-nil",
+                        stringify!($ty)
+                    )
+                }
+
+                fn from_words(
+                    words: &[$crate::state::__private::Word],
+                ) -> $crate::state::__private::anyhow::Result<Self> {
+                    $crate::state::__private::pint_abi::decode(words).map_err(|e| {
+                        $crate::state::__private::anyhow::anyhow!(
+                            "failed to decode {}: {}",
+                            stringify!($ty),
+                            e
+                        )
+                    })
+                }
+            }
+        )*
+    };
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow;
+    pub use essential_types::Word;
+    pub use pint_abi;
+}
+
+impl<T> Typed<T> {
+    pub fn new(value: Option<Value>) -> Self {
+        Self(value, PhantomData)
+    }
+
+    /// Whether the key is nil.
+    pub fn is_nil(&self) -> bool {
+        self.0.as_ref().is_none_or(|v| v.is_empty())
+    }
+}
+
+impl<T: FromState> Typed<T> {
+    /// Interpret the value, falling back to [`FromState::nil`] if the key is nil.
+    pub fn get(&self) -> anyhow::Result<T> {
+        match &self.0 {
+            Some(words) if !words.is_empty() => T::from_words(words),
+            _ => T::nil(),
+        }
+    }
+}
+
+impl<T> From<Option<Value>> for Typed<T> {
+    fn from(value: Option<Value>) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> Clone for Typed<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+
+impl<T> fmt::Debug for Typed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Typed").field(&self.0).finish()
+    }
+}
+
+impl FromState for Word {
+    fn nil() -> anyhow::Result<Self> {
+        Ok(0)
+    }
+
+    fn from_words(words: &[Word]) -> anyhow::Result<Self> {
+        match words {
+            [word] => Ok(*word),
+            _ => bail!("Expected single word, got: {:?}", words),
+        }
+    }
+}
+
+impl FromState for [Word; 4] {
+    fn nil() -> anyhow::Result<Self> {
+        Ok(Default::default())
+    }
+
+    fn from_words(words: &[Word]) -> anyhow::Result<Self> {
+        ensure!(words.len() == 4, "Expected four words, got: {:?}", words);
+        Ok(words.try_into()?)
+    }
+}
+
+impl FromState for bool {
+    fn nil() -> anyhow::Result<Self> {
+        Ok(false)
+    }
+
+    fn from_words(words: &[Word]) -> anyhow::Result<Self> {
+        match words {
+            [word] => {
+                bool_from_word(*word).ok_or_else(|| anyhow::anyhow!("Expected bool, got: {}", word))
+            }
+            _ => bail!("Expected single word, got: {:?}", words),
+        }
+    }
+}
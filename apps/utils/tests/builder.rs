@@ -0,0 +1,111 @@
+use essential_app_utils::{
+    builder::{self, AutoBuilder, ConflictAware, Trigger},
+    db::{new_dbs, Dbs},
+};
+use essential_types::{
+    solution::{Mutation, Solution, SolutionData},
+    ContentAddress, PredicateAddress, Word,
+};
+use std::time::Duration;
+
+#[tokio::test]
+async fn auto_builder_reports_build_errors() {
+    let dbs = new_dbs().await;
+    let auto = AutoBuilder::spawn(
+        dbs.clone(),
+        Trigger::Interval(Duration::from_millis(10)),
+        Default::default(),
+    );
+    auto.next_block().await.unwrap();
+
+    // Building fails once the node can't store blocks.
+    dbs.node
+        .acquire()
+        .await
+        .unwrap()
+        .execute_batch("PRAGMA foreign_keys = OFF; DROP TABLE block;")
+        .unwrap();
+    let err = tokio::time::timeout(Duration::from_secs(10), auto.next_block())
+        .await
+        .expect("next_block should fail instead of waiting forever")
+        .unwrap_err();
+    assert!(err.to_string().starts_with("auto builder failed"), "{err}");
+
+    // Waiting after the loop has stopped fails straight away.
+    let err = tokio::time::timeout(Duration::from_secs(1), auto.next_block())
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert!(err.to_string().starts_with("auto builder failed"), "{err}");
+    assert!(auto.shutdown().await.is_err());
+}
+
+#[tokio::test]
+async fn dropping_auto_builder_stops_building() {
+    let dbs = new_dbs().await;
+    let auto = AutoBuilder::spawn(
+        dbs.clone(),
+        Trigger::OnSubmission {
+            poll: Duration::from_millis(10),
+        },
+        Default::default(),
+    );
+    let next = auto.next_block();
+    builder::submit(&dbs.builder, set_key(1)).await.unwrap();
+    next.await.unwrap();
+    drop(auto);
+
+    // The loop stops once the handle is gone, so nothing takes this from the pool.
+    builder::submit(&dbs.builder, set_key(2)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(pool_len(&dbs).await, 1);
+}
+
+#[tokio::test]
+async fn build_with_keeps_pool_when_build_fails() {
+    let dbs = new_dbs().await;
+
+    // Both mutate the same key, so the second is deferred.
+    builder::submit(&dbs.builder, set_key(1)).await.unwrap();
+    builder::submit(&dbs.builder, set_key(2)).await.unwrap();
+
+    dbs.node
+        .acquire()
+        .await
+        .unwrap()
+        .execute_batch("PRAGMA foreign_keys = OFF; DROP TABLE block;")
+        .unwrap();
+    let config = builder::default_config(&dbs);
+    assert!(builder::build_with(&dbs, &config, &ConflictAware)
+        .await
+        .is_err());
+
+    // Neither the attempted nor the deferred solution is lost.
+    assert_eq!(pool_len(&dbs).await, 2);
+}
+
+/// A solution to an unregistered contract that sets key `[0]` to `value`.
+fn set_key(value: Word) -> Solution {
+    Solution {
+        data: vec![SolutionData {
+            predicate_to_solve: PredicateAddress {
+                contract: ContentAddress([1; 32]),
+                predicate: ContentAddress([2; 32]),
+            },
+            decision_variables: Default::default(),
+            transient_data: Default::default(),
+            state_mutations: vec![Mutation {
+                key: vec![0],
+                value: vec![value],
+            }],
+        }],
+    }
+}
+
+async fn pool_len(dbs: &Dbs) -> usize {
+    dbs.builder
+        .list_solutions(Duration::ZERO..Duration::from_secs(i64::MAX as _), i64::MAX)
+        .await
+        .unwrap()
+        .len()
+}
@@ -0,0 +1,86 @@
+use clap::Parser;
+use essential_app_utils::{
+    credentials::{CredentialSource, Credentials},
+    signer::{AccountSigner, Signer},
+};
+use essential_sign::secp256k1::SecretKey;
+
+const PRIV_KEY: &str = "128A3D2146A69581FD8FC4C0A9B7A96A5755D85255D4E47F814AFA69D7726C8D";
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    credentials: Credentials,
+}
+
+fn parse(args: &[&str]) -> Result<Credentials, clap::Error> {
+    Cli::try_parse_from(std::iter::once("app").chain(args.iter().copied()))
+        .map(|cli| cli.credentials)
+}
+
+fn temp_file(name: &str, contents: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn sources_from_args() {
+    assert_eq!(parse(&[]).unwrap().source(), CredentialSource::Prompt);
+    assert_eq!(
+        parse(&["--password-file", "pass.txt"]).unwrap().source(),
+        CredentialSource::PasswordFile("pass.txt".into())
+    );
+    assert_eq!(
+        parse(&["--password-env", "PASS"]).unwrap().source(),
+        CredentialSource::PasswordEnv("PASS".into())
+    );
+    assert_eq!(
+        parse(&["--raw-key-file", "key.hex"]).unwrap().source(),
+        CredentialSource::RawKeyFile("key.hex".into())
+    );
+
+    // Only one source can be chosen, and a raw key doesn't use a wallet directory.
+    assert!(parse(&["--password-file", "pass.txt", "--password-env", "PASS"]).is_err());
+    assert!(parse(&["--password-env", "PASS", "--raw-key-file", "key.hex"]).is_err());
+    assert!(parse(&["--wallet", "dir", "--raw-key-file", "key.hex"]).is_err());
+}
+
+#[test]
+fn password_from_file_and_env() {
+    let path = temp_file("password", "correct horse \n");
+    let source = CredentialSource::PasswordFile(path.clone());
+    assert_eq!(source.password().unwrap(), "correct horse ");
+    std::fs::remove_file(path).unwrap();
+
+    let var = "ESSENTIAL_APP_UTILS_TEST_PASSWORD";
+    let source = CredentialSource::PasswordEnv(var.into());
+    assert!(source.password().is_err());
+    std::env::set_var(var, "battery staple");
+    assert_eq!(source.password().unwrap(), "battery staple");
+}
+
+#[test]
+fn raw_key_file_signs_as_account() {
+    let path = temp_file("key.hex", &format!("0x{}\n", PRIV_KEY.to_lowercase()));
+    let mut signer = CredentialSource::RawKeyFile(path.clone())
+        .open_signer(None, "alice")
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let mut key = SecretKey::from_slice(&hex::decode(PRIV_KEY).unwrap()).unwrap();
+    assert!(matches!(signer, AccountSigner::Key(_)));
+    assert_eq!(signer.hashed_key().unwrap(), key.hashed_key().unwrap());
+    assert_eq!(
+        signer.sign(&[1, 2, 3]).unwrap(),
+        key.sign(&[1, 2, 3]).unwrap()
+    );
+
+    let path = temp_file("bad-key.hex", "not a key");
+    let result = CredentialSource::RawKeyFile(path.clone()).open_signer(None, "alice");
+    std::fs::remove_file(path).unwrap();
+    let Err(err) = result else {
+        panic!("Opened a wallet from an invalid key");
+    };
+    assert!(err.to_string().contains("is not hex"), "{err}");
+}
@@ -0,0 +1,143 @@
+use essential_app_utils::inputs::{
+    decode_slot, to_words, Decode, Instance, Int, WordSize, WriteDecVars, B256,
+};
+use essential_sign::secp256k1::{
+    ecdsa::RecoverableSignature, Message, PublicKey, Secp256k1, SecretKey,
+};
+use essential_types::{ContentAddress, PredicateAddress, Word};
+use proptest::prelude::*;
+use std::fmt::Debug;
+
+#[derive(Clone, Debug, PartialEq, WriteDecVars, Decode)]
+struct Transfer {
+    from: B256,
+    to: [Word; 4],
+    amount: Int,
+    approved: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, WriteDecVars, Decode)]
+enum Auth {
+    None,
+    Signed(RecoverableSignature),
+    Predicate(PredicateAddress),
+    Owner { key: PublicKey, path: Word },
+}
+
+fn round_trip<T: WriteDecVars + Decode + PartialEq + Debug>(value: T) {
+    let mut slots = Vec::new();
+    value.write_dec_var(&mut slots);
+    assert_eq!(slots.len(), 1);
+    assert_eq!(slots[0].len(), T::WORD_SIZE);
+    assert_eq!(decode_slot::<T>(&slots[0]).unwrap(), value);
+}
+
+fn content_address() -> impl Strategy<Value = ContentAddress> {
+    any::<[u8; 32]>().prop_map(ContentAddress)
+}
+
+fn predicate_address() -> impl Strategy<Value = PredicateAddress> {
+    (content_address(), content_address()).prop_map(|(contract, predicate)| PredicateAddress {
+        contract,
+        predicate,
+    })
+}
+
+fn secret_key() -> impl Strategy<Value = SecretKey> {
+    any::<[u8; 32]>().prop_filter_map("invalid secret key", |b| SecretKey::from_slice(&b).ok())
+}
+
+fn signature() -> impl Strategy<Value = RecoverableSignature> {
+    (secret_key(), any::<[u8; 32]>()).prop_map(|(sk, digest)| {
+        Secp256k1::new().sign_ecdsa_recoverable(&Message::from_digest(digest), &sk)
+    })
+}
+
+fn public_key() -> impl Strategy<Value = PublicKey> {
+    secret_key().prop_map(|sk| sk.public_key(&Secp256k1::new()))
+}
+
+fn auth() -> impl Strategy<Value = Auth> {
+    prop_oneof![
+        Just(Auth::None),
+        signature().prop_map(Auth::Signed),
+        predicate_address().prop_map(Auth::Predicate),
+        (public_key(), any::<Word>()).prop_map(|(key, path)| Auth::Owner { key, path }),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn words(w in any::<Word>(), b in any::<bool>(), a in any::<[Word; 4]>()) {
+        round_trip(w);
+        round_trip(b);
+        round_trip(a);
+        round_trip(Int(w));
+        round_trip(B256(a));
+
+        // Each fills a single slot.
+        let mut slots = vec![];
+        Int(w).write_dec_var(&mut slots);
+        B256(a).write_dec_var(&mut slots);
+        prop_assert_eq!(slots, vec![vec![w], a.to_vec()]);
+    }
+
+    #[test]
+    fn addresses(address in predicate_address(), path in any::<Word>()) {
+        round_trip(address.contract.clone());
+        round_trip(address.clone());
+        let instance = Instance { address: address.clone(), path };
+        let mut expected = to_words(&address);
+        expected.push(path);
+        prop_assert_eq!(to_words(&instance), expected);
+        let decoded = Instance::decode(&to_words(&instance)).unwrap();
+        prop_assert_eq!(decoded.address, address);
+        prop_assert_eq!(decoded.path, path);
+    }
+
+    #[test]
+    fn keys(sig in signature(), key in public_key()) {
+        round_trip(sig);
+        round_trip(key);
+    }
+
+    #[test]
+    fn derived_struct(
+        from in any::<[Word; 4]>(),
+        to in any::<[Word; 4]>(),
+        amount in any::<Word>(),
+        approved in any::<bool>(),
+    ) {
+        round_trip(Transfer { from: B256(from), to, amount: Int(amount), approved });
+    }
+
+    #[test]
+    fn derived_union(auth in auth()) {
+        round_trip(auth);
+    }
+}
+
+#[test]
+fn union_is_padded_to_largest_variant() {
+    assert_eq!(Auth::WORD_SIZE, 1 + RecoverableSignature::WORD_SIZE);
+    let words = to_words(&Auth::None);
+    assert_eq!(words, vec![0; Auth::WORD_SIZE]);
+}
+
+#[test]
+fn decode_rejects_bad_input() {
+    let mut words = to_words(&Auth::None);
+    words[0] = 4;
+    assert!(Auth::decode(&words).is_err());
+    assert!(Auth::decode(&words[1..]).is_err());
+    assert!(bool::decode(&[2]).is_err());
+}
+
+#[test]
+fn decode_rejects_non_zero_padding() {
+    let mut words = to_words(&Auth::None);
+    assert_eq!(Auth::decode(&words).unwrap(), Auth::None);
+    *words.last_mut().unwrap() = 1;
+    let err = Auth::decode(&words).unwrap_err();
+    assert!(err.to_string().contains("non-zero padding"), "{err}");
+}
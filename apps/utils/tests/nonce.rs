@@ -0,0 +1,47 @@
+use essential_app_utils::nonce::NonceManager;
+use essential_types::{ContentAddress, Word};
+
+#[test]
+fn hands_out_consecutive_nonces() {
+    let nonces = NonceManager::new();
+    let contract = ContentAddress([1; 32]);
+    let alice = vec![0, 1];
+    let bob = vec![0, 2];
+
+    assert_eq!(nonces.next(&contract, &alice, 3).unwrap(), 4);
+    assert_eq!(nonces.next(&contract, &alice, 3).unwrap(), 5);
+    assert_eq!(nonces.next(&contract, &bob, 0).unwrap(), 1);
+    assert_eq!(nonces.pending(&contract, &alice), Some(5));
+
+    // The chain moving past the pending nonces takes precedence.
+    assert_eq!(nonces.next(&contract, &alice, 9).unwrap(), 10);
+}
+
+#[test]
+fn reconcile_and_rollback() {
+    let nonces = NonceManager::new();
+    let contract = ContentAddress([1; 32]);
+    let key = vec![0, 1];
+
+    for expected in 1..=3 {
+        assert_eq!(nonces.next(&contract, &key, 0).unwrap(), expected);
+    }
+
+    // Only the first was included.
+    nonces.reconcile(&contract, &key, 1);
+    assert_eq!(nonces.pending(&contract, &key), Some(3));
+
+    // The second failed, so the third can never be included either.
+    nonces.rollback(&contract, &key, 2);
+    assert_eq!(nonces.next(&contract, &key, 1).unwrap(), 2);
+
+    nonces.reconcile(&contract, &key, 2);
+    assert_eq!(nonces.pending(&contract, &key), None);
+}
+
+#[test]
+fn next_fails_on_overflow() {
+    let nonces = NonceManager::new();
+    let contract = ContentAddress([1; 32]);
+    assert!(nonces.next(&contract, &vec![0], Word::MAX).is_err());
+}
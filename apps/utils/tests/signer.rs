@@ -0,0 +1,76 @@
+use essential_app_utils::{
+    inputs::Encode,
+    signer::{hash_public_key, ProcessSigner, Signer, WalletSigner},
+};
+use essential_sign::secp256k1::{Message, Secp256k1, SecretKey};
+use essential_types::Word;
+
+const PRIV_KEY: &str = "128A3D2146A69581FD8FC4C0A9B7A96A5755D85255D4E47F814AFA69D7726C8D";
+const WORDS: [Word; 3] = [1, 2, 3];
+
+fn secret_key() -> SecretKey {
+    SecretKey::from_slice(&hex::decode(PRIV_KEY).unwrap()).unwrap()
+}
+
+#[test]
+fn secret_key_signature_recovers_public_key() {
+    let mut signer = secret_key();
+    let public_key = Signer::public_key(&mut signer).unwrap();
+    assert_eq!(signer.hashed_key().unwrap(), hash_public_key(&public_key));
+
+    let sig = signer.sign(&WORDS).unwrap();
+    let message = Message::from_digest(essential_hash::hash_words(&WORDS));
+    let recovered = Secp256k1::new().recover_ecdsa(&message, &sig).unwrap();
+    assert_eq!(recovered, public_key);
+    assert_eq!(signer.sign_words(&WORDS).unwrap(), sig.encode());
+}
+
+#[test]
+fn wallet_matches_secret_key() {
+    let mut wallet = essential_wallet::Wallet::temp().unwrap();
+    wallet
+        .insert_key("alice", essential_signer::Key::Secp256k1(secret_key()))
+        .unwrap();
+    let mut wallet_signer = WalletSigner::new(&mut wallet, "alice");
+    let mut key_signer = secret_key();
+    assert_eq!(
+        wallet_signer.hashed_key().unwrap(),
+        key_signer.hashed_key().unwrap()
+    );
+    assert_eq!(
+        wallet_signer.sign(&WORDS).unwrap(),
+        key_signer.sign(&WORDS).unwrap()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn process_signer_speaks_json() {
+    let mut key_signer = secret_key();
+    let public_key = hex::encode(Signer::public_key(&mut key_signer).unwrap().serialize());
+    let (rec_id, compact) = key_signer.sign(&WORDS).unwrap().serialize_compact();
+    let mut sig = compact.to_vec();
+    sig.push(rec_id.to_i32() as u8);
+    let sig = hex::encode(sig);
+
+    // A signer that checks the request and replies with precomputed values.
+    let script = format!(
+        r#"read req
+case "$req" in
+  *'"method":"public_key"'*) echo '{{"public_key":"{public_key}"}}' ;;
+  *'"method":"sign_words","words":[1,2,3]'*) echo '{{"signature":"{sig}"}}' ;;
+  *) echo '{{"error":"unexpected request"}}' ;;
+esac"#
+    );
+    let mut signer = ProcessSigner::new("sh").arg("-c").arg(script);
+    assert_eq!(
+        signer.hashed_key().unwrap(),
+        key_signer.hashed_key().unwrap()
+    );
+    assert_eq!(
+        signer.sign(&WORDS).unwrap(),
+        key_signer.sign(&WORDS).unwrap()
+    );
+    let err = signer.sign(&[4]).unwrap_err();
+    assert!(err.to_string().contains("unexpected request"), "{err}");
+}
@@ -0,0 +1,27 @@
+use essential_app_utils::state::Typed;
+use essential_types::Word;
+
+#[test]
+fn nil_reads_as_default() {
+    for nil in [None, Some(vec![])] {
+        assert_eq!(Typed::<Word>::new(nil.clone()).get().unwrap(), 0);
+        assert_eq!(Typed::<[Word; 4]>::new(nil.clone()).get().unwrap(), [0; 4]);
+        assert!(!Typed::<bool>::new(nil.clone()).get().unwrap());
+        assert!(Typed::<Word>::new(nil).is_nil());
+    }
+}
+
+#[test]
+fn values_must_match_type() {
+    assert_eq!(Typed::<Word>::new(Some(vec![42])).get().unwrap(), 42);
+    assert_eq!(
+        Typed::<[Word; 4]>::new(Some(vec![1, 2, 3, 4]))
+            .get()
+            .unwrap(),
+        [1, 2, 3, 4]
+    );
+    assert!(Typed::<bool>::new(Some(vec![1])).get().unwrap());
+    assert!(Typed::<Word>::new(Some(vec![1, 2])).get().is_err());
+    assert!(Typed::<[Word; 4]>::new(Some(vec![1])).get().is_err());
+    assert!(Typed::<bool>::new(Some(vec![2])).get().is_err());
+}
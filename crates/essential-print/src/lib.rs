@@ -0,0 +1,415 @@
+//! Pretty printers for solutions and blocks, labelled from contract ABIs.
+
+use anyhow::{ensure, Context};
+use essential_types::{
+    contract::Contract,
+    solution::{Mutation, Solution, SolutionData},
+    Block, ContentAddress, PredicateAddress, Word,
+};
+use pint_abi::types::{ContractABI, PredicateABI, TypeABI, VarABI};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+/// Names for the contracts, predicates, variables and storage that appear in solutions.
+///
+/// Anything without a label is printed as raw addresses and words.
+#[derive(Clone, Debug, Default)]
+pub struct Labels {
+    contracts: HashMap<ContentAddress, ContractLabels>,
+}
+
+#[derive(Clone, Debug)]
+struct ContractLabels {
+    name: String,
+    storage: Vec<VarABI>,
+    predicates: HashMap<ContentAddress, PredicateABI>,
+}
+
+/// Pretty prints a [`Block`] and its solutions.
+pub struct DisplayBlock<'a> {
+    block: &'a Block,
+    labels: &'a Labels,
+}
+
+/// Pretty prints a [`Solution`].
+pub struct DisplaySolution<'a> {
+    solution: &'a Solution,
+    labels: &'a Labels,
+}
+
+/// Pretty prints a [`SolutionData`].
+pub struct DisplaySolutionData<'a> {
+    data: &'a SolutionData,
+    labels: &'a Labels,
+}
+
+impl Labels {
+    /// Label a contract from its ABI.
+    pub fn with_contract(mut self, name: &str, contract: &Contract, abi: &ContractABI) -> Self {
+        let address = essential_hash::contract_addr::from_contract(contract);
+        let predicates = abi
+            .predicates
+            .iter()
+            .filter(|predicate| !predicate.name.is_empty())
+            .zip(&contract.predicates)
+            .map(|(abi, predicate)| (essential_hash::content_addr(predicate), abi.clone()))
+            .collect();
+        self.contracts.insert(
+            address,
+            ContractLabels {
+                name: name.to_string(),
+                storage: abi.storage.clone(),
+                predicates,
+            },
+        );
+        self
+    }
+
+    /// Label the contracts of a pint project that has already been built.
+    ///
+    /// Each contract is named after the file pint wrote it to, which is the
+    /// package name in the project's `pint.toml`.
+    pub async fn load(mut self, path: PathBuf) -> anyhow::Result<Self> {
+        let out = path.join("out").join("debug");
+        let mut entries = tokio::fs::read_dir(&out)
+            .await
+            .with_context(|| format!("Failed to read {}", out.display()))?;
+        let mut loaded = 0;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name();
+            let Some(name) = file_name.to_str().and_then(|f| f.strip_suffix("-abi.json")) else {
+                continue;
+            };
+            let contract = tokio::fs::read(out.join(format!("{}.json", name))).await?;
+            let contract: Contract = serde_json::from_slice(&contract)?;
+            let abi: ContractABI = serde_json::from_slice(&tokio::fs::read(entry.path()).await?)?;
+            self = self.with_contract(name, &contract, &abi);
+            loaded += 1;
+        }
+        ensure!(loaded > 0, "No built contracts found in {}", out.display());
+        Ok(self)
+    }
+
+    pub fn block<'a>(&'a self, block: &'a Block) -> DisplayBlock<'a> {
+        DisplayBlock {
+            block,
+            labels: self,
+        }
+    }
+
+    pub fn solution<'a>(&'a self, solution: &'a Solution) -> DisplaySolution<'a> {
+        DisplaySolution {
+            solution,
+            labels: self,
+        }
+    }
+
+    pub fn solution_data<'a>(&'a self, data: &'a SolutionData) -> DisplaySolutionData<'a> {
+        DisplaySolutionData { data, labels: self }
+    }
+
+    fn predicate(
+        &self,
+        address: &PredicateAddress,
+    ) -> (Option<&ContractLabels>, Option<&PredicateABI>) {
+        let contract = self.contracts.get(&address.contract);
+        let predicate = contract.and_then(|c| c.predicates.get(&address.predicate));
+        (contract, predicate)
+    }
+}
+
+impl Display for DisplayBlock<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Block {
+            number,
+            timestamp,
+            solutions,
+        } = self.block;
+        writeln!(
+            f,
+            "block {} at {}.{:09}s",
+            number,
+            timestamp.as_secs(),
+            timestamp.subsec_nanos()
+        )?;
+        for (ix, solution) in solutions.iter().enumerate() {
+            writeln!(
+                f,
+                "solution {}: {}",
+                ix,
+                essential_hash::content_addr(solution)
+            )?;
+            write_indented(f, self.labels.solution(solution), 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DisplaySolution<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (ix, data) in self.solution.data.iter().enumerate() {
+            writeln!(f, "data {}:", ix)?;
+            write_indented(f, self.labels.solution_data(data), 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DisplaySolutionData<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let SolutionData {
+            predicate_to_solve,
+            decision_variables,
+            transient_data,
+            state_mutations,
+        } = self.data;
+        let (contract, predicate) = self.labels.predicate(predicate_to_solve);
+        match (contract, predicate) {
+            (Some(contract), Some(predicate)) => writeln!(
+                f,
+                "predicate: {}::{}",
+                contract.name,
+                trim_name(&predicate.name)
+            )?,
+            _ => writeln!(
+                f,
+                "predicate: contract: {}, predicate: {}",
+                predicate_to_solve.contract, predicate_to_solve.predicate
+            )?,
+        }
+
+        if !decision_variables.is_empty() {
+            writeln!(f, "decision variables:")?;
+        }
+        let vars = predicate.map(|p| p.vars.as_slice()).unwrap_or_default();
+        for (ix, value) in decision_variables.iter().enumerate() {
+            match vars.get(ix) {
+                Some(var) => writeln!(
+                    f,
+                    "  {}: {}",
+                    trim_name(&var.name),
+                    format_value(Some(&var.ty), value)
+                )?,
+                None => writeln!(f, "  {}: {}", ix, format_value(None, value))?,
+            }
+        }
+
+        let pub_vars = predicate.map(|p| p.pub_vars.as_slice()).unwrap_or_default();
+        write_mutations(f, "transient data", pub_vars, transient_data)?;
+        let storage = contract.map(|c| c.storage.as_slice()).unwrap_or_default();
+        write_mutations(f, "state mutations", storage, state_mutations)
+    }
+}
+
+fn write_mutations(
+    f: &mut fmt::Formatter<'_>,
+    heading: &str,
+    vars: &[VarABI],
+    mutations: &[Mutation],
+) -> fmt::Result {
+    if mutations.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "{}:", heading)?;
+    for Mutation { key, value } in mutations {
+        let (path, ty) = key_path(vars, key);
+        let value = if value.is_empty() {
+            "nil".to_string()
+        } else {
+            format_value(ty, value)
+        };
+        writeln!(f, "  {}: {}", path, value)?;
+    }
+    Ok(())
+}
+
+fn write_indented(f: &mut fmt::Formatter<'_>, item: impl Display, depth: usize) -> fmt::Result {
+    let indent = "  ".repeat(depth);
+    for line in item.to_string().lines() {
+        writeln!(f, "{}{}", indent, line)?;
+    }
+    Ok(())
+}
+
+fn trim_name(name: &str) -> &str {
+    name.trim_start_matches("::")
+}
+
+/// The storage path of a key, along with the type of the value it points to.
+///
+/// Falls back to the raw key if it doesn't match the layout of `vars`.
+fn key_path<'a>(vars: &'a [VarABI], key: &[Word]) -> (String, Option<&'a TypeABI>) {
+    let raw = || (format!("{:?}", key), None);
+    let Some((&ix, mut rest)) = key.split_first() else {
+        return raw();
+    };
+    let Some(var) = usize::try_from(ix).ok().and_then(|ix| vars.get(ix)) else {
+        return raw();
+    };
+    let mut path = trim_name(&var.name).to_string();
+    let mut ty = &var.ty;
+    loop {
+        match ty {
+            TypeABI::Map { ty_from, ty_to } => {
+                let size = ty_size(ty_from);
+                if rest.len() < size {
+                    return raw();
+                }
+                let (map_key, r) = rest.split_at(size);
+                path.push_str(&format!("[{}]", format_words(ty_from, map_key)));
+                rest = r;
+                ty = ty_to;
+            }
+            TypeABI::Tuple(_) | TypeABI::Array { .. } => {
+                let Some((&flat, r)) = rest.split_first() else {
+                    return raw();
+                };
+                let Some((suffix, inner)) = usize::try_from(flat)
+                    .ok()
+                    .and_then(|flat| flattened_path(ty, flat))
+                else {
+                    return raw();
+                };
+                path.push_str(&suffix);
+                rest = r;
+                ty = inner;
+            }
+            _ => break,
+        }
+    }
+    if rest.is_empty() {
+        (path, Some(ty))
+    } else {
+        raw()
+    }
+}
+
+/// Find the field or element at a flattened index into nested tuples and arrays.
+fn flattened_path(ty: &TypeABI, flat: usize) -> Option<(String, &TypeABI)> {
+    match ty {
+        TypeABI::Tuple(fields) => {
+            let mut offset = 0;
+            for (ix, field) in fields.iter().enumerate() {
+                let count = flattened_count(&field.ty);
+                if flat < offset + count {
+                    let name = match &field.name {
+                        Some(name) => format!(".{}", name),
+                        None => format!(".{}", ix),
+                    };
+                    let (suffix, ty) = flattened_path(&field.ty, flat - offset)?;
+                    return Some((name + &suffix, ty));
+                }
+                offset += count;
+            }
+            None
+        }
+        TypeABI::Array { ty, .. } => {
+            let count = flattened_count(ty);
+            if count == 0 {
+                return None;
+            }
+            let (suffix, ty) = flattened_path(ty, flat % count)?;
+            Some((format!("[{}]{}", flat / count, suffix), ty))
+        }
+        _ => (flat == 0).then(|| (String::new(), ty)),
+    }
+}
+
+fn flattened_count(ty: &TypeABI) -> usize {
+    match ty {
+        TypeABI::Tuple(fields) => fields.iter().map(|f| flattened_count(&f.ty)).sum(),
+        TypeABI::Array { ty, size } => flattened_count(ty) * (*size).max(0) as usize,
+        _ => 1,
+    }
+}
+
+fn ty_size(ty: &TypeABI) -> usize {
+    match ty {
+        TypeABI::Bool | TypeABI::Int | TypeABI::Real | TypeABI::String | TypeABI::Map { .. } => 1,
+        TypeABI::B256 => 4,
+        TypeABI::Tuple(fields) => fields.iter().map(|f| ty_size(&f.ty)).sum(),
+        TypeABI::Array { ty, size } => ty_size(ty) * (*size).max(0) as usize,
+        TypeABI::Union { variants, .. } => {
+            1 + variants
+                .iter()
+                .filter_map(|v| v.ty.as_ref())
+                .map(ty_size)
+                .max()
+                .unwrap_or_default()
+        }
+    }
+}
+
+/// Format a value as its type, or as raw words if it doesn't match.
+fn format_value(ty: Option<&TypeABI>, words: &[Word]) -> String {
+    match ty {
+        Some(ty) if ty_size(ty) == words.len() => format_words(ty, words),
+        _ => format!("{:?}", words),
+    }
+}
+
+fn format_words(ty: &TypeABI, mut words: &[Word]) -> String {
+    next_value(ty, &mut words).unwrap_or_else(|| format!("{:?}", words))
+}
+
+fn next_value(ty: &TypeABI, words: &mut &[Word]) -> Option<String> {
+    let size = ty_size(ty);
+    if words.len() < size {
+        return None;
+    }
+    let s = match ty {
+        TypeABI::Bool => match words[0] {
+            0 => "false".to_string(),
+            1 => "true".to_string(),
+            w => w.to_string(),
+        },
+        TypeABI::Int | TypeABI::Real | TypeABI::String | TypeABI::Map { .. } => {
+            words[0].to_string()
+        }
+        TypeABI::B256 => {
+            let bytes: Vec<u8> = words[..4].iter().flat_map(|w| w.to_be_bytes()).collect();
+            format!("0x{}", hex::encode_upper(bytes))
+        }
+        TypeABI::Tuple(fields) => {
+            let mut rest = *words;
+            let mut out = Vec::new();
+            for field in fields {
+                let value = next_value(&field.ty, &mut rest)?;
+                out.push(match &field.name {
+                    Some(name) => format!("{}: {}", name, value),
+                    None => value,
+                });
+            }
+            if fields.iter().all(|f| f.name.is_some()) {
+                format!("{{ {} }}", out.join(", "))
+            } else {
+                format!("({})", out.join(", "))
+            }
+        }
+        TypeABI::Array { ty, size } => {
+            let mut rest = *words;
+            let out = (0..*size)
+                .map(|_| next_value(ty, &mut rest))
+                .collect::<Option<Vec<_>>>()?;
+            format!("[{}]", out.join(", "))
+        }
+        TypeABI::Union { variants, .. } => {
+            let variant = usize::try_from(words[0])
+                .ok()
+                .and_then(|tag| variants.get(tag))?;
+            let name = trim_name(&variant.name);
+            match &variant.ty {
+                Some(ty) => {
+                    let mut rest = &words[1..];
+                    format!("{}({})", name, next_value(ty, &mut rest)?)
+                }
+                None => name.to_string(),
+            }
+        }
+    };
+    *words = &words[size..];
+    Some(s)
+}
@@ -0,0 +1,136 @@
+use essential_print::Labels;
+use essential_types::{
+    contract::Contract,
+    predicate::Predicate,
+    solution::{Mutation, Solution, SolutionData},
+    Block, ContentAddress, PredicateAddress,
+};
+use pint_abi::types::ContractABI;
+
+const ABI: &str = r#"{
+  "predicates": [
+    { "name": "", "vars": [], "pub_vars": [] },
+    {
+      "name": "::Pay",
+      "vars": [
+        { "name": "::auth", "ty": { "Union": { "name": "::Auth", "variants": [
+          { "name": "Auth::Signed", "ty": "Int" },
+          { "name": "Auth::None", "ty": null }
+        ] } } }
+      ],
+      "pub_vars": [{ "name": "::amount", "ty": "Int" }]
+    }
+  ],
+  "storage": [
+    { "name": "balances", "ty": { "Map": { "ty_from": "B256", "ty_to": "Int" } } },
+    { "name": "config", "ty": { "Tuple": [
+      { "name": "owner", "ty": "B256" },
+      { "name": "limits", "ty": { "Array": { "ty": "Int", "size": 2 } } }
+    ] } }
+  ]
+}"#;
+
+fn contract() -> Contract {
+    Contract {
+        predicates: vec![Predicate {
+            state_read: vec![],
+            constraints: vec![vec![1]],
+        }],
+        salt: [0; 32],
+    }
+}
+
+fn pay(contract: &Contract) -> SolutionData {
+    SolutionData {
+        predicate_to_solve: PredicateAddress {
+            contract: essential_hash::contract_addr::from_contract(contract),
+            predicate: essential_hash::content_addr(&contract.predicates[0]),
+        },
+        decision_variables: vec![vec![0, 42]],
+        transient_data: vec![Mutation {
+            key: vec![0],
+            value: vec![5],
+        }],
+        state_mutations: vec![
+            Mutation {
+                key: vec![0, 0, 0, 0, 255],
+                value: vec![95],
+            },
+            Mutation {
+                key: vec![1, 2],
+                value: vec![10],
+            },
+            Mutation {
+                key: vec![1, 0],
+                value: vec![],
+            },
+        ],
+    }
+}
+
+#[test]
+fn labels_solution_data_from_abi() {
+    let contract = contract();
+    let abi: ContractABI = serde_json::from_str(ABI).unwrap();
+    let labels = Labels::default().with_contract("shop", &contract, &abi);
+
+    let printed = labels.solution_data(&pay(&contract)).to_string();
+    let expected = "\
+predicate: shop::Pay
+decision variables:
+  auth: Auth::Signed(42)
+transient data:
+  amount: 5
+state mutations:
+  balances[0x00000000000000000000000000000000000000000000000000000000000000FF]: 95
+  config.limits[1]: 10
+  config.owner: nil
+";
+    assert_eq!(printed, expected);
+}
+
+#[test]
+fn unlabelled_solutions_print_raw_words() {
+    let contract = contract();
+    let solution = Solution {
+        data: vec![pay(&contract)],
+    };
+    let block = Block {
+        number: 3,
+        timestamp: std::time::Duration::from_millis(1500),
+        solutions: vec![solution.clone()],
+    };
+
+    let printed = Labels::default().block(&block).to_string();
+    assert!(
+        printed.starts_with("block 3 at 1.500000000s\n"),
+        "{}",
+        printed
+    );
+    assert!(printed.contains(&format!(
+        "solution 0: {}",
+        essential_hash::content_addr(&solution)
+    )));
+    assert!(printed.contains("    0: [0, 42]\n"), "{}", printed);
+    assert!(
+        printed.contains("    [0, 0, 0, 0, 255]: [95]\n"),
+        "{}",
+        printed
+    );
+
+    // Keys that don't match the ABI layout fall back to raw words.
+    let abi: ContractABI = serde_json::from_str(ABI).unwrap();
+    let labels = Labels::default().with_contract("shop", &contract, &abi);
+    let mut data = pay(&contract);
+    data.state_mutations = vec![Mutation {
+        key: vec![7],
+        value: vec![1],
+    }];
+    let printed = labels.solution_data(&data).to_string();
+    assert!(printed.contains("  [7]: [1]\n"), "{}", printed);
+
+    let other = ContentAddress([9; 32]);
+    data.predicate_to_solve.contract = other.clone();
+    let printed = labels.solution_data(&data).to_string();
+    assert!(printed.starts_with(&format!("predicate: contract: {}", other)));
+}
@@ -1,4 +1,5 @@
 use clap::{Parser, Subcommand};
+use essential_print::Labels;
 use essential_rest_client::{
     builder_client::EssentialBuilderClient, node_client::EssentialNodeClient,
 };
@@ -37,6 +38,13 @@ enum NodeCommands {
     ListBlocks {
         /// Range of block number of blocks to list, end of range exclusive.
         range: BlockRange,
+        /// Print the blocks for reading instead of as json.
+        #[arg(long)]
+        pretty: bool,
+        /// Built pint projects used to label pretty printed solutions by predicate,
+        /// variable and storage name.
+        #[arg(long, requires = "pretty")]
+        pint_directory: Vec<PathBuf>,
     },
     /// Query the state of a contract.
     QueryState {
@@ -93,9 +101,23 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
         let node_client = EssentialNodeClient::new(addr)?;
         match commands {
             Commands::Node(ref node_commands) => match node_commands {
-                NodeCommands::ListBlocks { range } => {
+                NodeCommands::ListBlocks {
+                    range,
+                    pretty,
+                    pint_directory,
+                } => {
                     let output = node_client.list_blocks(range.start..range.end).await?;
-                    print!("{}", serde_json::to_string(&output)?);
+                    if *pretty {
+                        let mut labels = Labels::default();
+                        for path in pint_directory {
+                            labels = labels.load(path.clone()).await?;
+                        }
+                        for block in &output {
+                            print!("{}", labels.block(block));
+                        }
+                    } else {
+                        print!("{}", serde_json::to_string(&output)?);
+                    }
                 }
                 NodeCommands::QueryState { address, key } => {
                     let output = node_client
@@ -3,6 +3,9 @@ use essential_types::{convert::bytes_from_word, Block, ContentAddress, Key, Valu
 use reqwest::{Client, ClientBuilder};
 use std::ops::Range;
 
+/// The number of blocks requested at a time by [`EssentialNodeClient::for_each_block`].
+pub const BLOCK_PAGE: Word = 100;
+
 /// Client that binds to an Essential node address.
 #[derive(Clone)]
 pub struct EssentialNodeClient {
@@ -33,6 +36,39 @@ impl EssentialNodeClient {
         Ok(response.json::<Vec<Block>>().await?)
     }
 
+    /// Call `f` with every block from number `start` onwards, in order.
+    ///
+    /// Blocks are requested [`BLOCK_PAGE`] at a time until the node has no more.
+    /// Returns the number of the block after the last one visited, or `start`
+    /// if there were none.
+    pub async fn for_each_block(
+        &self,
+        start: Word,
+        mut f: impl FnMut(&Block) -> anyhow::Result<()>,
+    ) -> anyhow::Result<Word> {
+        let mut next = start;
+        loop {
+            let blocks = self.list_blocks(next..next + BLOCK_PAGE).await?;
+            let Some(last) = blocks.last() else {
+                return Ok(next);
+            };
+            next = last.number + 1;
+            for block in &blocks {
+                f(block)?;
+            }
+        }
+    }
+
+    /// The number of the latest block, or `None` if no block has been built.
+    ///
+    /// The builder records each block's number in the block state contract,
+    /// so this reads it from the block state of the default big bang.
+    pub async fn latest_block_number(&self) -> anyhow::Result<Option<Word>> {
+        let block_state = essential_node_types::BigBang::default().block_state;
+        let number = self.query_state(block_state.contract, vec![0]).await?;
+        Ok(number.and_then(|number| number.first().copied()))
+    }
+
     /// Query state in the given contract address and key.
     ///
     /// This is the main way the front end application will interact with state.